@@ -0,0 +1,68 @@
+// Copyright 2019-2024 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use forest_filecoin::benchmark_private::gas::{
+    gas_premium_from_samples, GasMeta, GasPremiumConfig, TokenAmount,
+};
+use fvm_shared4::BLOCK_GAS_LIMIT;
+use rand::{rngs::StdRng, Rng as _, SeedableRng as _};
+use std::hint::black_box;
+
+const MESSAGE_COUNT: u64 = 10_000;
+// the gas target of this many blocks is reached about halfway through the messages
+const BLOCKS: usize = 20;
+const BLOCK_GAS_TARGET: u64 = BLOCK_GAS_LIMIT / 2;
+
+/// Premium of [`gas_premium_from_samples`], computed by sorting all samples.
+fn sorted_gas_premium(prices: &[GasMeta], blocks: usize) -> Option<TokenAmount> {
+    let mut sorted: Vec<_> = prices.iter().collect();
+    sorted.sort_by(|a, b| b.price.cmp(&a.price));
+    let mut at = (BLOCK_GAS_TARGET * blocks as u64 / 2) as i128;
+    let mut prev = TokenAmount::from_atto(0);
+    for price in sorted {
+        at -= price.limit as i128;
+        if at > 0 {
+            prev = price.price.clone();
+            continue;
+        }
+        if prev == TokenAmount::from_atto(0) {
+            return Some(&price.price + TokenAmount::from_atto(1));
+        }
+        return Some((&price.price + &prev).div_floor(2) + TokenAmount::from_atto(1));
+    }
+    None
+}
+
+fn bench_gas_premium(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let prices: Vec<_> = (0..MESSAGE_COUNT)
+        .map(|_| GasMeta {
+            price: TokenAmount::from_atto(rng.gen_range(100..200_000u64)),
+            limit: rng.gen_range(1_000_000..20_000_000),
+            fill_ratio: 0.5,
+        })
+        .collect();
+    let config = GasPremiumConfig {
+        noise_stddev: 0.0,
+        ..Default::default()
+    };
+    let expected = sorted_gas_premium(&prices, BLOCKS).expect("gas target not reached");
+    assert_eq!(
+        gas_premium_from_samples(&prices, BLOCKS, 10, &config).unwrap(),
+        expected
+    );
+
+    let mut group = c.benchmark_group("gas-premium");
+    group.throughput(Throughput::Elements(MESSAGE_COUNT));
+    group.bench_function("select", |b| {
+        b.iter(|| gas_premium_from_samples(black_box(&prices), BLOCKS, 10, &config).unwrap())
+    });
+    group.bench_function("sort", |b| {
+        b.iter(|| sorted_gas_premium(black_box(&prices), BLOCKS).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_gas_premium);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+// Copyright 2019-2024 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use fil_actor_market_state::v16::{DealProposal, Label, State as V16State};
+use forest_filecoin::benchmark_private::{cid::CidCborExt as _, market};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared4::{address::Address, econ::TokenAmount, piece::PaddedPieceSize};
+use std::hint::black_box;
+
+const DEAL_COUNT: u64 = 10_000;
+
+// Throughput of iterating pending deals over a synthetic market state where
+// none of the published proposals has been activated yet.
+fn bench_for_each_pending_deal(c: &mut Criterion) {
+    let store = MemoryBlockstore::new();
+    let mut st = V16State::new(&store).unwrap();
+    let mut proposals = st.load_proposals(&store).unwrap();
+    for deal_id in 0..DEAL_COUNT {
+        proposals
+            .set(
+                deal_id,
+                DealProposal {
+                    piece_cid: cid::Cid::from_cbor_blake2b256(&deal_id).unwrap(),
+                    piece_size: PaddedPieceSize(2048),
+                    verified_deal: false,
+                    client: Address::new_id(1000 + deal_id),
+                    provider: Address::new_id(1000),
+                    label: Label::String(format!("deal-{deal_id}")),
+                    start_epoch: 100,
+                    end_epoch: 200_000,
+                    storage_price_per_epoch: TokenAmount::from_atto(1),
+                    provider_collateral: TokenAmount::from_atto(1),
+                    client_collateral: TokenAmount::from_atto(1),
+                },
+            )
+            .unwrap();
+    }
+    st.proposals = proposals.flush().unwrap();
+    let state = market::State::V16(st);
+
+    let mut group = c.benchmark_group("market-deals");
+    group.throughput(Throughput::Elements(DEAL_COUNT));
+    group.bench_function("for_each_pending_deal", |b| {
+        b.iter(|| {
+            let mut count = 0;
+            state
+                .for_each_pending_deal(&store, |deal_id, proposal| {
+                    black_box((deal_id, proposal));
+                    count += 1;
+                    Ok(())
+                })
+                .unwrap();
+            assert_eq!(count, DEAL_COUNT);
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_for_each_pending_deal);
+criterion_main!(benches);
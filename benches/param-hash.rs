@@ -0,0 +1,43 @@
+// Copyright 2019-2024 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use forest_filecoin::benchmark_private::proofs_api::{
+    param_file_digest, param_file_digest_blocking,
+};
+use std::io::Write as _;
+
+const FILE_SIZE: usize = 1024 * 1024 * 1024;
+
+// Throughput of hashing a 1 GiB proofs parameter file with async chunked reads vs. synchronous
+// reads on the blocking thread pool.
+fn bench_param_hash(c: &mut Criterion) {
+    let mut file = tempfile::Builder::new().tempfile().unwrap();
+    let chunk: Vec<u8> = (0..1024 * 1024).map(|i| i as u8).collect();
+    for _ in 0..FILE_SIZE / chunk.len() {
+        file.write_all(&chunk).unwrap();
+    }
+    file.flush().unwrap();
+    let path = file.path();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("param-hash");
+    group
+        .sample_size(10)
+        .throughput(Throughput::Bytes(FILE_SIZE as u64));
+
+    group
+        .bench_function("async", |b| {
+            b.to_async(&runtime)
+                .iter(|| async { param_file_digest(path, None).await.unwrap() })
+        })
+        .bench_function("blocking", |b| {
+            b.to_async(&runtime)
+                .iter(|| async { param_file_digest_blocking(path).await.unwrap() })
+        });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_param_hash);
+criterion_main!(benches);
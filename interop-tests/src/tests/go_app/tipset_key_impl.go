@@ -0,0 +1,90 @@
+package main
+
+import (
+	"bytes"
+	"encoding/json"
+	"strings"
+
+	"github.com/ipfs/go-cid"
+)
+
+func init() {
+	GoTipsetKeyImpl = &tipsetKeyImpl{}
+}
+
+type tipsetKeyImpl struct{}
+
+// tipSetKey mirrors `types.TipSetKey` of lotus, which keeps the concatenated binary CIDs and
+// encodes them as a JSON array of CIDs.
+// See <https://github.com/filecoin-project/lotus/blob/master/chain/types/tipset_key.go>
+type tipSetKey struct {
+	value string
+}
+
+func newTipSetKey(cids ...cid.Cid) tipSetKey {
+	var buf bytes.Buffer
+	for _, c := range cids {
+		buf.Write(c.Bytes())
+	}
+	return tipSetKey{value: buf.String()}
+}
+
+func (k tipSetKey) cids() ([]cid.Cid, error) {
+	cids := []cid.Cid{}
+	for rest := []byte(k.value); len(rest) > 0; {
+		n, c, err := cid.CidFromBytes(rest)
+		if err != nil {
+			return nil, err
+		}
+		cids = append(cids, c)
+		rest = rest[n:]
+	}
+	return cids, nil
+}
+
+func (k tipSetKey) MarshalJSON() ([]byte, error) {
+	cids, err := k.cids()
+	if err != nil {
+		return nil, err
+	}
+	return json.Marshal(cids)
+}
+
+func (k *tipSetKey) UnmarshalJSON(b []byte) error {
+	var cids []cid.Cid
+	if err := json.Unmarshal(b, &cids); err != nil {
+		return err
+	}
+	*k = newTipSetKey(cids...)
+	return nil
+}
+
+// Returns the space separated CIDs of the JSON tipset key, or the error prefixed with `error: `.
+func (impl *tipsetKeyImpl) parse_tipset_key_json(keyJSON string) string {
+	var key tipSetKey
+	if err := json.Unmarshal([]byte(keyJSON), &key); err != nil {
+		return "error: " + err.Error()
+	}
+	cids, err := key.cids()
+	if err != nil {
+		return "error: " + err.Error()
+	}
+	strs := make([]string, len(cids))
+	for i, c := range cids {
+		strs[i] = c.String()
+	}
+	return strings.Join(strs, " ")
+}
+
+// Returns the JSON tipset key of the space separated CIDs.
+func (impl *tipsetKeyImpl) tipset_key_json(cidStrs string) string {
+	cids := []cid.Cid{}
+	for _, s := range strings.Fields(cidStrs) {
+		c, err := cid.Decode(s)
+		checkError(err)
+		cids = append(cids, c)
+	}
+	b, err := json.Marshal(newTipSetKey(cids...))
+	checkError(err)
+	return string(b)
+}
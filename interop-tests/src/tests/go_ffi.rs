@@ -24,3 +24,10 @@ pub trait GoBitswapNode {
 
     fn get_block(cid: &String) -> bool;
 }
+
+#[rust2go::r2g]
+pub trait GoTipsetKey {
+    fn parse_tipset_key_json(json: &String) -> String;
+
+    fn tipset_key_json(cids: &String) -> String;
+}
@@ -5,3 +5,4 @@ mod go_ffi;
 
 mod bitswap_go_compat;
 mod kad_go_compat;
+mod tipset_key_go_compat;
@@ -0,0 +1,93 @@
+// Copyright 2019-2024 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::go_ffi::*;
+use anyhow::Context as _;
+use cid::Cid;
+use forest_filecoin::interop_tests_private::{
+    lotus_json::{HasLotusJson as _, LotusJson},
+    rpc::types::ApiTipsetKey,
+};
+use multihash_codetable::{Code, MultihashDigest as _};
+
+const DAG_CBOR: u64 = 0x71;
+
+#[test]
+fn tipset_key_go_compat_test() -> anyhow::Result<()> {
+    // the empty key selects the heaviest tipset in RPC calls
+    for blocks in [0, 1, 4] {
+        let cids = (0..blocks)
+            .map(|i| {
+                Cid::new_v1(
+                    DAG_CBOR,
+                    Code::Blake2b256.digest(format!("block {i}").as_bytes()),
+                )
+            })
+            .collect::<Vec<_>>();
+        rust_to_go(&cids).with_context(|| format!("Rust to Go, {blocks} blocks"))?;
+        go_to_rust(&cids).with_context(|| format!("Go to Rust, {blocks} blocks"))?;
+    }
+    Ok(())
+}
+
+/// Encodes the tipset key in Rust and decodes it in Go.
+fn rust_to_go(cids: &[Cid]) -> anyhow::Result<()> {
+    let key = ApiTipsetKey::from_lotus_json(cids.to_vec().into_lotus_json());
+    let json = serde_json::to_string(&LotusJson(key))?;
+    let parsed = GoTipsetKeyImpl::parse_tipset_key_json(&json);
+    if let Some(error) = parsed.strip_prefix("error: ") {
+        anyhow::bail!("[Go] failed to parse {json}: {error}");
+    }
+    let parsed = parsed
+        .split_whitespace()
+        .map(Cid::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    ensure_same_cids(cids, &parsed).with_context(|| format!("[Go] parsed {json}"))
+}
+
+/// Encodes the tipset key in Go and decodes it in Rust.
+fn go_to_rust(cids: &[Cid]) -> anyhow::Result<()> {
+    let cid_strs = cids
+        .iter()
+        .map(Cid::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let json = GoTipsetKeyImpl::tipset_key_json(&cid_strs);
+    let LotusJson(key) = serde_json::from_str::<LotusJson<ApiTipsetKey>>(&json)
+        .with_context(|| format!("failed to parse {json}"))?;
+    let rust_json = serde_json::to_string(&LotusJson(key.clone()))?;
+    let parsed: Vec<Cid> = key
+        .0
+        .map(|key| key.into_cids().into_iter().collect())
+        .unwrap_or_default();
+    ensure_same_cids(cids, &parsed).with_context(|| format!("parsed {json}"))?;
+    anyhow::ensure!(
+        json == rust_json,
+        "JSON encodings differ\n  Go:   {json}\n  Rust: {rust_json}"
+    );
+    Ok(())
+}
+
+/// Fails with the CIDs that didn't round-trip, by position in the key.
+fn ensure_same_cids(expected: &[Cid], actual: &[Cid]) -> anyhow::Result<()> {
+    let describe = |cid: Option<&Cid>| match cid {
+        Some(cid) => format!("{cid} ({:?}, codec {:#x})", cid.version(), cid.codec()),
+        None => "nothing".into(),
+    };
+    let diff = (0..expected.len().max(actual.len()))
+        .filter(|&i| expected.get(i) != actual.get(i))
+        .map(|i| {
+            format!(
+                "  #{i}: expected {}, got {}",
+                describe(expected.get(i)),
+                describe(actual.get(i))
+            )
+        })
+        .collect::<Vec<_>>();
+    anyhow::ensure!(
+        diff.is_empty(),
+        "CIDs didn't round-trip:\n{}",
+        diff.join("\n")
+    );
+    Ok(())
+}
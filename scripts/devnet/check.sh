@@ -60,3 +60,16 @@ if [ "$height" -lt 1 ]; then
   echo "latest cert instance should be greater than zero: $height"
   exit 1
 fi
+
+# Check that the base fee reported by the gas API is the one of the head
+head=$(curl --silent -X POST -H "Content-Type: application/json" \
+       --data '{"jsonrpc":"2.0","id":2,"method":"Filecoin.ChainHead","param":"null"}' \
+       "http://127.0.0.1:${FOREST_RPC_PORT}/rpc/v1")
+head_base_fee=$(echo "$head" | jq -r '.result.Blocks[0].ParentBaseFee')
+base_fee=$(curl --silent -X POST -H "Content-Type: application/json" \
+       --data "$(jq -n --argjson tsk "$(echo "$head" | jq '.result.Cids')" '{jsonrpc: "2.0", id: 2, method: "Forest.GasGetBaseFee", params: [$tsk]}')" \
+       "http://127.0.0.1:${FOREST_RPC_PORT}/rpc/v1" | jq -r '.result.current')
+if [ "$base_fee" != "$head_base_fee" ]; then
+  echo "Base fee $base_fee doesn't match the head's parent base fee $head_base_fee"
+  exit 1
+fi
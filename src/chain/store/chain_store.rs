@@ -562,7 +562,10 @@ where
         .ok_or_else(|| Error::UndefinedKey(key.to_string()))
 }
 
-/// Given a tipset this function will return all unique messages in that tipset.
+/// Given a tipset this function will return all unique messages in that tipset, in the order
+/// they are applied in. Messages that don't follow the previous nonce of their sender, or that
+/// their sender can't afford, are skipped. This also skips a message included by several blocks
+/// of the tipset after its first inclusion, as its nonce is used up by then.
 pub fn messages_for_tipset<DB>(db: Arc<DB>, ts: &Tipset) -> Result<Vec<ChainMessage>, Error>
 where
     DB: Blockstore,
@@ -581,7 +584,7 @@ where
 
             for message in unsigned_box.chain(signed_box) {
                 let from_address = &message.from();
-                if applied.contains_key(from_address) {
+                if !applied.contains_key(from_address) {
                     let actor_state = state
                         .get_actor(from_address)?
                         .ok_or_else(|| Error::Other("Actor state not found".to_string()))?;
@@ -702,6 +705,89 @@ mod tests {
         assert_eq!(cs.genesis_block_header(), &gen_block);
     }
 
+    /// Stores a tipset of blocks including the given messages, on top of a state where
+    /// `Address::new_id(100)` holds `balance`.
+    fn tipset_with_messages(
+        db: &Arc<crate::db::MemoryDB>,
+        balance: TokenAmount,
+        blocks: &[&[Message]],
+    ) -> Tipset {
+        use crate::chain_sync::TipsetValidator;
+        use crate::shim::state_tree::{ActorState, StateTreeVersion};
+
+        let empty = Cid::new_v1(DAG_CBOR, MultihashCode::Identity.digest(&[]));
+        let mut state = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        state
+            .set_actor(
+                &Address::new_id(100),
+                ActorState::new(empty, empty, balance, 0, None),
+            )
+            .unwrap();
+        let state_root = state.flush().unwrap();
+
+        let headers = blocks.iter().zip(0..).map(|(messages, miner)| {
+            for message in messages.iter() {
+                db.put_cbor_default(message).unwrap();
+            }
+            CachingBlockHeader::new(RawBlockHeader {
+                miner_address: Address::new_id(miner),
+                state_root,
+                messages: TipsetValidator::compute_msg_root(&**db, messages, &[]).unwrap(),
+                ..Default::default()
+            })
+        });
+        Tipset::new(headers).unwrap()
+    }
+
+    fn message(sequence: u64, value: u64) -> Message {
+        Message {
+            from: Address::new_id(100),
+            to: Address::new_id(101),
+            sequence,
+            value: TokenAmount::from_atto(value),
+            ..Default::default()
+        }
+    }
+
+    fn cids(messages: &[ChainMessage]) -> Vec<Cid> {
+        messages.iter().map(ChainMessage::cid).collect()
+    }
+
+    #[test]
+    fn messages_for_tipset_filters_nonce_and_balance() {
+        let db = Arc::new(crate::db::MemoryDB::default());
+        let messages = [
+            message(0, 100),
+            // can't be afforded, its nonce is still used up
+            message(1, 5_000),
+            message(2, 100),
+            // nonce gap
+            message(4, 100),
+        ];
+        let tipset = tipset_with_messages(&db, TokenAmount::from_atto(1_000), &[&messages]);
+
+        let applied = messages_for_tipset(db, &tipset).unwrap();
+        assert_eq!(
+            cids(&applied),
+            [&messages[0], &messages[2]].map(|message| message.cid())
+        );
+    }
+
+    #[test]
+    fn messages_for_tipset_dedup() {
+        let db = Arc::new(crate::db::MemoryDB::default());
+        let messages = [message(0, 100), message(1, 100)];
+        // the first message is included in both blocks
+        let tipset = tipset_with_messages(
+            &db,
+            TokenAmount::from_atto(1_000),
+            &[&messages[..1], &messages],
+        );
+
+        let applied = messages_for_tipset(db, &tipset).unwrap();
+        assert_eq!(cids(&applied), messages.map(|message| message.cid()));
+    }
+
     #[test]
     fn block_validation_cache_basic() {
         let db = Arc::new(crate::db::MemoryDB::default());
@@ -0,0 +1,116 @@
+// Copyright 2019-2024 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::{num::NonZeroUsize, sync::Arc};
+
+use crate::blocks::{Tipset, TipsetKey};
+use crate::message::ChainMessage;
+use fvm_ipld_blockstore::Blockstore;
+use lru::LruCache;
+use nonzero_ext::nonzero;
+use parking_lot::Mutex;
+
+use super::{messages_for_tipset, Error};
+
+/// Thread-safe cache of the messages included in recent tipsets, see [`messages_for_tipset`].
+/// Tipsets are immutable, so cached messages never go stale. Concurrent gas estimates walk the
+/// same recent tipsets, and only the first one has to read their messages from the store.
+#[derive(Debug)]
+pub struct MessageCache {
+    cache: Mutex<LruCache<TipsetKey, Arc<Vec<ChainMessage>>>>,
+}
+
+impl Default for MessageCache {
+    fn default() -> Self {
+        Self::new(nonzero!(128usize))
+    }
+}
+
+impl MessageCache {
+    /// Creates a cache of the messages of up to `capacity` tipsets.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            cache: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Like [`messages_for_tipset`], but the result is cached.
+    pub fn messages_for_tipset<DB: Blockstore>(
+        &self,
+        db: Arc<DB>,
+        ts: &Tipset,
+    ) -> Result<Arc<Vec<ChainMessage>>, Error> {
+        if let Some(messages) = self.cache.lock().get(ts.key()) {
+            return Ok(messages.clone());
+        }
+        let messages = Arc::new(messages_for_tipset(db, ts)?);
+        self.cache.lock().put(ts.key().clone(), messages.clone());
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, RawBlockHeader};
+    use crate::chain_sync::TipsetValidator;
+    use crate::db::MemoryDB;
+    use crate::shim::state_tree::{StateTree, StateTreeVersion};
+    use cid::Cid;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts the reads that reach the store.
+    #[derive(Default)]
+    struct CountingStore {
+        inner: MemoryDB,
+        gets: AtomicUsize,
+    }
+
+    impl Blockstore for CountingStore {
+        fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+            self.gets.fetch_add(1, Ordering::Relaxed);
+            self.inner.get(k)
+        }
+
+        fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+            self.inner.put_keyed(k, block)
+        }
+    }
+
+    #[test]
+    fn second_call_reads_nothing() {
+        let store = Arc::new(CountingStore::default());
+        let messages = TipsetValidator::compute_msg_root(&*store, &[], &[]).unwrap();
+        let state_root = StateTree::new(store.clone(), StateTreeVersion::V5)
+            .unwrap()
+            .flush()
+            .unwrap();
+        let tipset = |epoch| {
+            Tipset::from(CachingBlockHeader::new(RawBlockHeader {
+                messages,
+                state_root,
+                epoch,
+                ..Default::default()
+            }))
+        };
+        let (first, second) = (tipset(1), tipset(2));
+        let gets = || store.gets.load(Ordering::Relaxed);
+        let cache = MessageCache::new(nonzero!(1usize));
+
+        let before = gets();
+        assert!(cache
+            .messages_for_tipset(store.clone(), &first)
+            .unwrap()
+            .is_empty());
+        let reads = gets() - before;
+        assert!(reads > 0);
+        cache.messages_for_tipset(store.clone(), &first).unwrap();
+        assert_eq!(gets() - before, reads);
+
+        // evicts the first tipset
+        cache.messages_for_tipset(store.clone(), &second).unwrap();
+        assert_eq!(gets() - before, 2 * reads);
+        cache.messages_for_tipset(store.clone(), &first).unwrap();
+        assert_eq!(gets() - before, 3 * reads);
+    }
+}
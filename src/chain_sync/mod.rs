@@ -14,6 +14,6 @@ pub use self::{
     bad_block_cache::BadBlockCache,
     chain_muxer::{ChainMuxer, SyncConfig},
     consensus::collect_errs,
-    sync_state::{SyncStage, SyncState},
+    sync_state::{SyncHealthStatus, SyncStage, SyncState},
     validation::{TipsetValidationError, TipsetValidator},
 };
@@ -3,11 +3,16 @@
 
 use std::sync::Arc;
 
+use crate::beacon::BeaconSchedule;
 use crate::blocks::Tipset;
+use crate::lotus_json::lotus_json_with_self;
+use crate::networks::{calculate_expected_epoch, ChainConfig};
 use crate::shim::clock::ChainEpoch;
 #[cfg(test)]
 use chrono::TimeZone;
 use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 /// Current state of the `ChainSyncer` using the `ChainExchange` protocol.
 #[derive(PartialEq, Eq, Debug, Clone, Copy, strum::Display, strum::EnumString)]
@@ -131,6 +136,58 @@ impl SyncState {
     }
 }
 
+/// Sync progress of the node, measured against the epoch the network is
+/// expected to be at according to the wall clock. Unlike the reported head
+/// epoch alone, this detects a node that has stalled while syncing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct SyncHealthStatus {
+    pub synced: bool,
+    pub lag_epochs: i64,
+    pub lag_seconds: f64,
+    pub drand_round: u64,
+    pub expected_epoch: ChainEpoch,
+    pub actual_epoch: ChainEpoch,
+}
+
+lotus_json_with_self!(SyncHealthStatus);
+
+impl SyncHealthStatus {
+    /// Maximum number of epochs the node may lag behind the expected epoch
+    /// while still being considered in sync.
+    pub const MAX_LAG_EPOCHS: i64 = 5;
+
+    /// Computes the sync health of a node whose head is at `actual_epoch`, as
+    /// of `now_timestamp` (seconds since the Unix epoch).
+    pub fn new(
+        actual_epoch: ChainEpoch,
+        now_timestamp: u64,
+        genesis_timestamp: u64,
+        chain_config: &ChainConfig,
+        beacon: &BeaconSchedule,
+    ) -> anyhow::Result<Self> {
+        let expected_epoch = calculate_expected_epoch(
+            now_timestamp,
+            genesis_timestamp,
+            chain_config.block_delay_secs,
+        ) as ChainEpoch;
+        let (_, expected_beacon) = beacon.beacon_for_epoch(expected_epoch)?;
+        let drand_round = expected_beacon.max_beacon_round_for_epoch(
+            chain_config.network_version(expected_epoch),
+            expected_epoch,
+        );
+        let lag_epochs = expected_epoch - actual_epoch;
+        Ok(Self {
+            synced: lag_epochs <= Self::MAX_LAG_EPOCHS,
+            lag_epochs,
+            lag_seconds: (lag_epochs * chain_config.block_delay_secs as i64) as f64,
+            drand_round,
+            expected_epoch,
+            actual_epoch,
+        })
+    }
+}
+
 mod lotus_json {
     use super::SyncState;
     use crate::{blocks::Tipset, chain_sync::SyncStage, lotus_json::*};
@@ -10,6 +10,7 @@ use std::{
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
+use url::Url;
 
 use crate::daemon::db_util::ImportMode;
 
@@ -66,6 +67,13 @@ pub struct Client {
     pub load_actors: bool,
     /// `TTL` to set for Ethereum `Hash` to `Cid` entries or `None` to never reclaim them.
     pub eth_mapping_ttl: Option<u32>,
+    /// IPFS gateways to fetch proof parameters from, tried in order. Overrides the
+    /// `IPFS_GATEWAY` environment variable when non-empty.
+    #[cfg_attr(test, arbitrary(gen(|_| vec![])))]
+    pub proofs_gateways: Vec<Url>,
+    /// JSON manifest of the proof parameter files to download instead of the built-in one, in
+    /// the format of `parameters.json`.
+    pub params_file: Option<PathBuf>,
 }
 
 impl Default for Client {
@@ -93,6 +101,8 @@ impl Default for Client {
             ),
             load_actors: true,
             eth_mapping_ttl: None,
+            proofs_gateways: vec![],
+            params_file: None,
         }
     }
 }
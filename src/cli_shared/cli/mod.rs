@@ -17,6 +17,7 @@ use clap::Parser;
 use directories::ProjectDirs;
 use libp2p::Multiaddr;
 use tracing::error;
+use url::Url;
 
 pub use self::{client::*, config::*};
 
@@ -154,6 +155,14 @@ pub struct CliOpts {
     /// Skip loading actors from the actors bundle.
     #[arg(long)]
     pub skip_load_actors: bool,
+    /// IPFS gateway to fetch proof parameters from. Can be repeated, in which case the gateways
+    /// are tried in order, e.g., `--proofs-gateway https://proofs.filecoin.io/ipfs/ --proofs-gateway https://ipfs.io/ipfs/`
+    #[arg(long)]
+    pub proofs_gateway: Vec<Url>,
+    /// JSON manifest of the proof parameter files to download instead of the built-in one, in
+    /// the format of `parameters.json`
+    #[arg(long)]
+    pub params_file: Option<PathBuf>,
 }
 
 impl CliOpts {
@@ -224,6 +233,12 @@ impl CliOpts {
         }
 
         cfg.client.load_actors = !self.skip_load_actors;
+        if !self.proofs_gateway.is_empty() {
+            cfg.client.proofs_gateways.clone_from(&self.proofs_gateway);
+        }
+        if let Some(params_file) = &self.params_file {
+            cfg.client.params_file = Some(params_file.to_owned());
+        }
 
         Ok((cfg, path))
     }
@@ -3,16 +3,33 @@
 
 use std::{
     fmt::Display,
+    future::Future,
     path::{Path, PathBuf},
     str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use crate::{
     networks::NetworkChain,
-    utils::{retry, RetryArgs},
+    utils::{io::WithProgress, reqwest_resume},
 };
-use anyhow::{bail, Context as _};
+use anyhow::{bail, ensure, Context as _};
+use backon::{ExponentialBuilder, Retryable as _};
 use chrono::NaiveDate;
+use futures::TryStreamExt as _;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use prometheus_client::{
+    metrics::{
+        counter::Counter,
+        histogram::{exponential_buckets, Histogram},
+    },
+    registry::Registry,
+};
+use subtle::ConstantTimeEq as _;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+use tokio_util::either::Either::{Left, Right};
 use tracing::event;
 use url::Url;
 
@@ -60,19 +77,418 @@ pub fn filename(
     .to_string()
 }
 
-/// Returns the path to the downloaded file.
+/// Routes snapshot downloads through a SOCKS5 proxy if set, e.g. `socks5h://127.0.0.1:9050` for Tor.
+pub const SNAPSHOT_PROXY_ENV: &str = "FOREST_SNAPSHOT_PROXY";
+
+/// Default of [`SnapshotFetchConfig::connect_timeout`].
+pub const DEFAULT_SNAPSHOT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default of [`SnapshotFetchConfig::read_timeout`].
+pub const DEFAULT_SNAPSHOT_READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Sent with snapshot and checksum requests, so that mirror operators can tell which Forest
+/// versions fetch snapshots.
+pub const SNAPSHOT_USER_AGENT: &str = concat!("forest/", env!("CARGO_PKG_VERSION"));
+
+/// Timeouts of the HTTP requests made for snapshot and checksum downloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, smart_default::SmartDefault)]
+pub struct SnapshotFetchConfig {
+    /// How long to wait for a connection to the server.
+    #[default(DEFAULT_SNAPSHOT_CONNECT_TIMEOUT)]
+    pub connect_timeout: Duration,
+    /// How long to wait for data before giving up on a stalled request, which may then be retried.
+    #[default(DEFAULT_SNAPSHOT_READ_TIMEOUT)]
+    pub read_timeout: Duration,
+    /// How long a whole request may take, body included. Unlimited by default, as large snapshots
+    /// take hours to download on slow connections.
+    pub total_timeout: Option<Duration>,
+}
+
+impl SnapshotFetchConfig {
+    fn client_builder(&self) -> reqwest::ClientBuilder {
+        let builder = reqwest::Client::builder()
+            .user_agent(SNAPSHOT_USER_AGENT)
+            .connect_timeout(self.connect_timeout)
+            .read_timeout(self.read_timeout);
+        match self.total_timeout {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        }
+    }
+}
+
+/// Returns the HTTP client for snapshot downloads, going through [`SNAPSHOT_PROXY_ENV`] if set.
+fn http_client(config: &SnapshotFetchConfig) -> anyhow::Result<reqwest::Client> {
+    match std::env::var(SNAPSHOT_PROXY_ENV) {
+        Ok(proxy) => proxy_client(&proxy, config)
+            .with_context(|| format!("invalid {SNAPSHOT_PROXY_ENV} value {proxy:?}")),
+        Err(_) => Ok(config.client_builder().build()?),
+    }
+}
+
+fn proxy_client(proxy: &str, config: &SnapshotFetchConfig) -> anyhow::Result<reqwest::Client> {
+    let url = Url::parse(proxy)?;
+    anyhow::ensure!(
+        matches!(url.scheme(), "socks5" | "socks5h"),
+        "expected a socks5:// or socks5h:// URL"
+    );
+    anyhow::ensure!(
+        url.host().is_some() && url.port().is_some(),
+        "expected a host and a port"
+    );
+    Ok(config
+        .client_builder()
+        .proxy(reqwest::Proxy::all(url)?)
+        .build()?)
+}
+
+/// Default number of download attempts, see [`FetchOptions::max_attempts`].
+pub const DEFAULT_FETCH_ATTEMPTS: usize = 5;
+
+/// Optional settings of [`fetch`].
+#[derive(smart_default::SmartDefault)]
+pub struct FetchOptions {
+    /// `file://` URLs are used in place, and verified if there is a checksum file next to them.
+    pub url: Option<Url>,
+    /// Don't verify the snapshot against the checksum file published next to it.
+    pub skip_checksum: bool,
+    /// Progress is logged unless another reporter is given.
+    pub progress: Option<Box<dyn DownloadProgress + Send>>,
+    /// How many times to try the download before giving up. At least one attempt is made.
+    #[default(DEFAULT_FETCH_ATTEMPTS)]
+    pub max_attempts: usize,
+    /// Only resolve the snapshot metadata, without downloading or writing anything.
+    pub dry_run: bool,
+    /// Decompress `.car.zst` snapshots after downloading them, stripping the `.zst` extension.
+    /// The checksum is still verified against the compressed file.
+    pub decompress: bool,
+    /// Write the snapshot as a CARv2 file with an index of its blocks, see
+    /// [`write_v2`](crate::db::car::plain::write_v2). `.car.zst` snapshots are decompressed first.
+    pub car_v2: bool,
+    /// Metrics to update while downloading, if any.
+    pub metrics: Option<Arc<SnapshotDownloadMetrics>>,
+    /// Timeouts of the snapshot and checksum requests.
+    pub http: SnapshotFetchConfig,
+}
+
+/// Parses a snapshot URL, or a path on the local filesystem as a `file://` URL. Only HTTP(S) and
+/// `file://` URLs can be fetched.
+pub fn parse_url_or_path(s: &str) -> anyhow::Result<Url> {
+    match Url::parse(s) {
+        Ok(url) => match url.scheme() {
+            "http" | "https" | "file" => Ok(url),
+            "magnet" => anyhow::bail!(
+                "BitTorrent magnet links are not supported, download the snapshot with a torrent client and pass its path instead"
+            ),
+            scheme => anyhow::bail!("unsupported snapshot URL scheme {scheme}"),
+        },
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            Url::from_file_path(std::path::absolute(s)?)
+                .map_err(|()| anyhow::anyhow!("invalid snapshot path {s}"))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Prometheus metrics of snapshot downloads.
+#[derive(Debug, Clone)]
+pub struct SnapshotDownloadMetrics {
+    /// Duration of successful downloads, in seconds
+    pub duration: Histogram,
+    /// Bytes received, including those of failed attempts
+    pub bytes: Counter,
+    /// Downloads that matched their checksum
+    pub checksum_passed: Counter,
+    /// Downloads that didn't match their checksum
+    pub checksum_failed: Counter,
+}
+
+impl Default for SnapshotDownloadMetrics {
+    fn default() -> Self {
+        Self {
+            // 1 second to about 4.5 hours
+            duration: Histogram::new(exponential_buckets(1.0, 2.0, 15)),
+            bytes: Counter::default(),
+            checksum_passed: Counter::default(),
+            checksum_failed: Counter::default(),
+        }
+    }
+}
+
+impl SnapshotDownloadMetrics {
+    /// Returns the metrics registered in the default registry.
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: Lazy<Arc<SnapshotDownloadMetrics>> = Lazy::new(|| {
+            let metrics = SnapshotDownloadMetrics::default();
+            metrics.register(&mut crate::metrics::default_registry());
+            Arc::new(metrics)
+        });
+        GLOBAL.clone()
+    }
+
+    fn register(&self, registry: &mut Registry) {
+        registry.register(
+            "snapshot_download_duration_seconds",
+            "Duration of successful snapshot downloads",
+            self.duration.clone(),
+        );
+        registry.register(
+            "snapshot_download_bytes",
+            "Bytes received while downloading snapshots",
+            self.bytes.clone(),
+        );
+        registry.register(
+            "snapshot_checksum_passed",
+            "Snapshot downloads that matched their checksum",
+            self.checksum_passed.clone(),
+        );
+        registry.register(
+            "snapshot_checksum_failed",
+            "Snapshot downloads that didn't match their checksum",
+            self.checksum_failed.clone(),
+        );
+    }
+}
+
+/// A snapshot resolved by [`fetch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotMeta {
+    /// Final URL of the snapshot, after redirects
+    pub url: Url,
+    /// Path of the snapshot on disk. Nothing is written there in a [`FetchOptions::dry_run`].
+    pub path: PathBuf,
+    /// Size in bytes, as reported by the server
+    pub total_size: u64,
+    /// Checksum the snapshot is verified against, if any
+    pub checksum: Option<Checksum>,
+}
+
+/// Returns the metadata of the downloaded snapshot.
+/// The snapshot is verified against the checksum file published next to it, if there is one. The
+/// checksum file is required for snapshots from an overridden [`FetchOptions::url`], unless it is a
+/// `file://` URL.
+#[tracing::instrument(name = "snapshot_fetch", skip_all, fields(%chain, %vendor))]
 pub async fn fetch(
     directory: &Path,
     chain: &NetworkChain,
     vendor: TrustedVendor,
-) -> anyhow::Result<PathBuf> {
-    let (url, _len, path) = peek(vendor, chain).await?;
-    let (date, height, forest_format) = ParsedFilename::parse_str(&path)
-        .context("unexpected path format")?
-        .date_and_height_and_forest();
-    let filename = filename(vendor, chain, date, height, forest_format);
+    options: FetchOptions,
+) -> anyhow::Result<SnapshotMeta> {
+    let FetchOptions {
+        url: custom_url,
+        skip_checksum,
+        progress,
+        max_attempts,
+        dry_run,
+        decompress,
+        car_v2,
+        metrics,
+        http,
+    } = options;
+    if let Some(url) = custom_url.as_ref().filter(|url| url.scheme() == "file") {
+        ensure!(
+            !decompress && !car_v2,
+            "local snapshots are used in place and can't be decompressed or converted"
+        );
+        let path = url
+            .to_file_path()
+            .map_err(|()| anyhow::anyhow!("invalid file URL {url}"))?;
+        return fetch_local(url.clone(), &path, skip_checksum, dry_run).await;
+    }
+    let client = http_client(&http)?;
+    let is_custom = custom_url.is_some();
+    let stable_url = match custom_url {
+        Some(url) => url,
+        None => stable_url(vendor, chain)?,
+    };
+    let (url, total_size, path) =
+        with_backoff(max_attempts, || peek_url(&client, stable_url.clone())).await?;
+    let parsed = ParsedFilename::parse_str(&path).map(|parsed| parsed.date_and_height_and_forest());
+    let filename = match parsed {
+        Ok((date, height, forest_format)) => filename(vendor, chain, date, height, forest_format),
+        // mirrors may name their snapshots differently
+        Err(_) if is_custom => path,
+        Err(e) => return Err(e.context("unexpected path format")),
+    };
+    let checksum = if skip_checksum {
+        None
+    } else {
+        let checksum = probe_checksum(&client, &url).await;
+        match &checksum {
+            None if is_custom => bail!("no checksum file found for {url}"),
+            None => {
+                event!(target: "forest::snapshot", tracing::Level::WARN, %url, "no checksum file found, skipping verification")
+            }
+            Some(_) => {}
+        }
+        checksum
+    };
+    let decompress = (decompress || car_v2) && filename.ends_with(".car.zst");
+    ensure!(
+        !car_v2 || decompress || filename.ends_with(".car"),
+        "only CAR snapshots can be converted to CARv2, got {filename}"
+    );
+    if dry_run {
+        event!(
+            target: "forest::snapshot",
+            tracing::Level::INFO,
+            %url,
+            %filename,
+            total_size,
+            checksum = ?checksum,
+            "dry run, skipping download"
+        );
+        let path = directory.join(filename);
+        return Ok(SnapshotMeta {
+            url,
+            path: if decompress {
+                path.with_extension("")
+            } else {
+                path
+            },
+            total_size,
+            checksum,
+        });
+    }
+    let progress = Mutex::new(progress.unwrap_or_else(|| Box::new(LogProgress::new("Loading"))));
+
+    let path = with_backoff(max_attempts, || {
+        download_http(
+            &client,
+            &url,
+            directory,
+            &filename,
+            checksum.as_ref(),
+            &progress,
+            metrics.as_deref(),
+        )
+    })
+    .await?;
+    let path = if decompress {
+        decompress_zstd(&path).await?
+    } else {
+        path
+    };
+    if car_v2 {
+        convert_to_car_v2(&path).await?;
+    }
+    Ok(SnapshotMeta {
+        url,
+        path,
+        total_size,
+        checksum,
+    })
+}
 
-    download_file_with_retry(&url, directory, &filename).await
+/// [`fetch`] for a snapshot on the local filesystem, which is used in place rather than copied.
+async fn fetch_local(
+    url: Url,
+    path: &Path,
+    skip_checksum: bool,
+    dry_run: bool,
+) -> anyhow::Result<SnapshotMeta> {
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .with_context(|| format!("no snapshot at {}", path.display()))?;
+    ensure!(metadata.is_file(), "{} is not a file", path.display());
+    let checksum = if skip_checksum {
+        None
+    } else {
+        let checksum = local_checksum(path).await?;
+        match &checksum {
+            None => {
+                event!(target: "forest::snapshot", tracing::Level::WARN, path = %path.display(), "no checksum file found, skipping verification")
+            }
+            Some(checksum) if !dry_run => {
+                validate_checksum(checksum, &file_digest(path, checksum.algorithm).await?)?
+            }
+            Some(_) => {}
+        }
+        checksum
+    };
+    Ok(SnapshotMeta {
+        url,
+        path: path.to_owned(),
+        total_size: metadata.len(),
+        checksum,
+    })
+}
+
+/// Verifies the snapshot at `path` against a checksum file, without downloading the snapshot
+/// again. The checksum file is fetched from `checksum_url`, which may also be a `file://` URL, or
+/// read from next to the snapshot otherwise. Its algorithm follows from its extension, e.g.
+/// `.sha256sum`, and defaults to SHA-256. Returns the checksum the snapshot matched.
+pub async fn verify(path: &Path, checksum_url: Option<&Url>) -> anyhow::Result<Checksum> {
+    let checksum = match checksum_url {
+        Some(url) => remote_checksum(url).await?,
+        None => local_checksum(path)
+            .await?
+            .with_context(|| format!("no checksum file found next to {}", path.display()))?,
+    };
+    let actual = file_digest(path, checksum.algorithm)
+        .await
+        .with_context(|| format!("couldn't read snapshot {}", path.display()))?;
+    validate_checksum(&checksum, &actual)?;
+    Ok(checksum)
+}
+
+/// Downloads and parses the checksum file at `url`, see [`verify`].
+async fn remote_checksum(url: &Url) -> anyhow::Result<Checksum> {
+    let algorithm = filename_from_url(url)
+        .and_then(|filename| {
+            let extension = Path::new(&filename).extension()?.to_str()?.to_owned();
+            ChecksumAlgorithm::ALL
+                .into_iter()
+                .find(|algorithm| algorithm.extension() == extension)
+        })
+        .unwrap_or(ChecksumAlgorithm::Sha256);
+    let content = if url.scheme() == "file" {
+        let path = url
+            .to_file_path()
+            .map_err(|()| anyhow::anyhow!("invalid checksum file path {url}"))?;
+        tokio::fs::read_to_string(path).await?
+    } else {
+        http_client(&SnapshotFetchConfig::default())?
+            .get(url.clone())
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?
+    };
+    checksum_from_file(algorithm, &content).with_context(|| format!("invalid checksum file {url}"))
+}
+
+/// Reads the checksum file next to the snapshot at `path`, trying each of
+/// [`ChecksumAlgorithm::ALL`] in turn like [`detect_sidecar_url`].
+async fn local_checksum(path: &Path) -> anyhow::Result<Option<Checksum>> {
+    for algorithm in ChecksumAlgorithm::ALL {
+        let sidecar = path.with_extension(algorithm.extension());
+        match tokio::fs::read_to_string(&sidecar).await {
+            Ok(content) => {
+                return checksum_from_file(algorithm, &content)
+                    .with_context(|| format!("invalid checksum file {}", sidecar.display()))
+                    .map(Some)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the hex-encoded `algorithm` digest of the file at `path`.
+async fn file_digest(path: &Path, algorithm: ChecksumAlgorithm) -> anyhow::Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = algorithm.hasher();
+    let mut buffer = vec![0; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        match buffer.get(..read) {
+            Some([]) => return Ok(hasher.finalize()),
+            Some(chunk) => hasher.update(chunk),
+            None => bail!("read {read} bytes into a buffer of {}", buffer.len()),
+        }
+    }
 }
 
 pub async fn download_file_with_retry(
@@ -80,14 +496,251 @@ pub async fn download_file_with_retry(
     directory: &Path,
     filename: &str,
 ) -> anyhow::Result<PathBuf> {
-    Ok(retry(
-        RetryArgs {
-            timeout: None,
-            ..Default::default()
-        },
-        || download_http(url, directory, filename),
+    let client = http_client(&SnapshotFetchConfig::default())?;
+    let progress: Mutex<Box<dyn DownloadProgress + Send>> =
+        Mutex::new(Box::new(LogProgress::new("Loading")));
+    let metrics = SnapshotDownloadMetrics::global();
+    with_backoff(DEFAULT_FETCH_ATTEMPTS, || {
+        download_http(
+            &client,
+            url,
+            directory,
+            filename,
+            None,
+            &progress,
+            Some(&metrics),
+        )
+    })
+    .await
+}
+
+/// Runs `f` up to `max_attempts` times, waiting between attempts with exponential backoff from one
+/// second up to a minute. Interrupted downloads resume from the partially written file on the next
+/// attempt if the server supports range requests, see [`download_http`].
+async fn with_backoff<T, F>(max_attempts: usize, f: impl FnMut() -> F) -> anyhow::Result<T>
+where
+    F: Future<Output = anyhow::Result<T>>,
+{
+    let max_attempts = max_attempts.max(1);
+    let mut attempt = 1;
+    f.retry(
+        ExponentialBuilder::default()
+            .with_min_delay(Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(60))
+            .with_max_times(max_attempts - 1),
     )
-    .await?)
+    .notify(|error, wait| {
+        attempt += 1;
+        event!(
+            target: "forest::snapshot",
+            tracing::Level::INFO,
+            "attempt {attempt}/{max_attempts} in {}, after: {error:#}",
+            humantime::format_duration(wait)
+        );
+    })
+    .await
+}
+
+/// Receives progress updates of snapshot downloads, e.g. to display them in a GUI.
+pub trait DownloadProgress {
+    /// Called after each downloaded chunk, with the total number of bytes downloaded so far and
+    /// the size of the file, or zero if the latter is unknown.
+    fn on_chunk(&mut self, downloaded: u64, total: u64);
+
+    /// Called once the file has been downloaded and verified.
+    fn on_complete(&mut self) {}
+}
+
+/// Logs download progress periodically, like other long-running operations. See [`WithProgress`].
+pub struct LogProgress(WithProgress<()>);
+
+impl LogProgress {
+    pub fn new(message: &str) -> Self {
+        Self(WithProgress::new(message).bytes())
+    }
+}
+
+impl DownloadProgress for LogProgress {
+    fn on_chunk(&mut self, downloaded: u64, total: u64) {
+        self.0.set(downloaded, total);
+    }
+}
+
+/// Ignores download progress, for library use.
+pub struct SilentProgress;
+
+impl DownloadProgress for SilentProgress {
+    fn on_chunk(&mut self, _downloaded: u64, _total: u64) {}
+}
+
+/// Digest algorithms of the checksum files published alongside snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    /// BLAKE2b-512, as computed by `b2sum`
+    Blake2b,
+    Blake3,
+}
+
+impl ChecksumAlgorithm {
+    /// Algorithms in the order their checksum files are probed for.
+    pub const ALL: [Self; 3] = [Self::Sha256, Self::Blake2b, Self::Blake3];
+
+    /// Extension of the checksum file, e.g. `sha256sum`.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256sum",
+            Self::Blake2b => "blake2bsum",
+            Self::Blake3 => "blake3sum",
+        }
+    }
+
+    /// Length of the digest in bytes.
+    fn digest_len(self) -> usize {
+        match self {
+            Self::Sha256 | Self::Blake3 => 32,
+            Self::Blake2b => 64,
+        }
+    }
+
+    fn hasher(self) -> ChecksumHasher {
+        match self {
+            Self::Sha256 => ChecksumHasher::Sha256(Default::default()),
+            Self::Blake2b => ChecksumHasher::Blake2b(Default::default()),
+            Self::Blake3 => ChecksumHasher::Blake3(Default::default()),
+        }
+    }
+}
+
+/// Expected digest of a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    pub algorithm: ChecksumAlgorithm,
+    /// Hex-encoded digest
+    pub digest: String,
+}
+
+enum ChecksumHasher {
+    Sha256(sha2::Sha256),
+    Blake2b(Box<blake2b_simd::State>),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl ChecksumHasher {
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => sha2::Digest::update(hasher, bytes),
+            Self::Blake2b(hasher) => {
+                hasher.update(bytes);
+            }
+            Self::Blake3(hasher) => {
+                hasher.update(bytes);
+            }
+        }
+    }
+
+    /// Returns the hex-encoded digest.
+    fn finalize(self) -> String {
+        match self {
+            Self::Sha256(hasher) => hex::encode(sha2::Digest::finalize(hasher)),
+            Self::Blake2b(hasher) => hasher.finalize().to_hex().to_string(),
+            Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// Parses the contents of a checksum file in the `sha256sum` format, i.e.
+/// `<hex digest> <filename>`.
+pub fn checksum_from_file(algorithm: ChecksumAlgorithm, content: &str) -> anyhow::Result<Checksum> {
+    let digest = content
+        .split_whitespace()
+        .next()
+        .context("empty checksum file")?
+        .to_lowercase();
+    anyhow::ensure!(
+        digest.len() == 2 * algorithm.digest_len() && digest.chars().all(|c| c.is_ascii_hexdigit()),
+        "malformed {algorithm} checksum: {digest}"
+    );
+    Ok(Checksum { algorithm, digest })
+}
+
+/// Checks that `actual` (hex-encoded, computed with `expected.algorithm`) matches `expected`.
+///
+/// The digests are compared in constant time so a mismatch doesn't reveal how
+/// many leading bytes were correct.
+pub fn validate_checksum(expected: &Checksum, actual: &str) -> anyhow::Result<()> {
+    let actual_lower = actual.to_ascii_lowercase();
+    let expected_lower = expected.digest.to_ascii_lowercase();
+    ensure!(
+        actual_lower.len() == expected_lower.len(),
+        "{} checksum length mismatch: expected {} hex digits, got {}",
+        expected.algorithm,
+        expected_lower.len(),
+        actual_lower.len()
+    );
+    if bool::from(actual_lower.as_bytes().ct_eq(expected_lower.as_bytes())) {
+        Ok(())
+    } else {
+        bail!(
+            "{} checksum mismatch: expected {}, got {actual}",
+            expected.algorithm,
+            expected.digest
+        )
+    }
+}
+
+/// Location of the checksum file for the snapshot at `url`, named like the
+/// ones written by `forest-cli snapshot export`.
+fn checksum_url(url: &Url, algorithm: ChecksumAlgorithm) -> Option<Url> {
+    let filename = filename_from_url(url)?;
+    let stem = Path::new(&filename).file_stem()?.to_str()?;
+    // The `./` prefix keeps a stem containing `:` from being parsed as a URL scheme.
+    url.join(&format!("./{stem}.{}", algorithm.extension()))
+        .ok()
+}
+
+/// Finds the checksum file next to the snapshot at `url` by sending `HEAD` requests for each of
+/// [`ChecksumAlgorithm::ALL`] in turn. Returns the first one that exists.
+async fn detect_sidecar_url(
+    client: &reqwest::Client,
+    url: &Url,
+) -> Option<(ChecksumAlgorithm, Url)> {
+    for algorithm in ChecksumAlgorithm::ALL {
+        let Some(checksum_url) = checksum_url(url, algorithm) else {
+            continue;
+        };
+        match client.head(checksum_url.clone()).send().await {
+            Ok(response) if response.status() == reqwest::StatusCode::OK => {
+                return Some((algorithm, checksum_url))
+            }
+            _ => continue,
+        }
+    }
+    None
+}
+
+/// Downloads and parses the checksum file next to the snapshot at `url`, see
+/// [`detect_sidecar_url`].
+async fn probe_checksum(client: &reqwest::Client, url: &Url) -> Option<Checksum> {
+    let (algorithm, checksum_url) = detect_sidecar_url(client, url).await?;
+    let checksum = async {
+        let content = client
+            .get(checksum_url.clone())
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        checksum_from_file(algorithm, &content)
+    };
+    match checksum.await {
+        Ok(checksum) => Some(checksum),
+        Err(error) => {
+            event!(target: "forest::snapshot", tracing::Level::WARN, %checksum_url, %error, "invalid checksum file");
+            None
+        }
+    }
 }
 
 /// Returns
@@ -98,30 +751,52 @@ pub async fn peek(
     vendor: TrustedVendor,
     chain: &NetworkChain,
 ) -> anyhow::Result<(Url, u64, String)> {
-    let stable_url = stable_url(vendor, chain)?;
+    peek_url(
+        &http_client(&SnapshotFetchConfig::default())?,
+        stable_url(vendor, chain)?,
+    )
+    .await
+}
+
+async fn peek_url(client: &reqwest::Client, stable_url: Url) -> anyhow::Result<(Url, u64, String)> {
     // issue an actual GET, so the content length will be of the body
     // (we never actually fetch the body)
     // if we issue a HEAD, the content-length will be zero for our stable URLs
     // (this is a bug, maybe in reqwest - HEAD _should_ give us the length)
     // (probably because the stable URLs are all double-redirects 301 -> 302 -> 200)
-    let response = reqwest::get(stable_url)
+    let response = client
+        .get(stable_url)
+        .send()
         .await?
         .error_for_status()
         .context("server returned an error response")?;
     let final_url = response.url().clone();
+    // not every mirror sets a content-disposition header, in which case the
+    // final URL is expected to point at the snapshot file itself
     let cd_path = response
         .headers()
         .get(reqwest::header::CONTENT_DISPOSITION)
-        .and_then(parse_content_disposition);
+        .and_then(parse_content_disposition)
+        .or_else(|| filename_from_url(&final_url));
     Ok((
         final_url,
         response
             .content_length()
             .context("no content-length header")?,
-        cd_path.context("no content-disposition filepath")?,
+        cd_path.context("no content-disposition or URL filepath")?,
     ))
 }
 
+// Extract the last path segment of a URL, if it is non-empty:
+//   "https://example.com/calibnet/911520_2023_09_14T06_13_00Z.car.zst"
+// => "911520_2023_09_14T06_13_00Z.car.zst"
+fn filename_from_url(url: &Url) -> Option<String> {
+    url.path_segments()?
+        .last()
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_owned)
+}
+
 // Extract file paths from content-disposition values:
 //   "attachment; filename=\"911520_2023_09_14T06_13_00Z.car.zst\""
 // => "911520_2023_09_14T06_13_00Z.car.zst"
@@ -132,12 +807,22 @@ fn parse_content_disposition(value: &reqwest::header::HeaderValue) -> Option<Str
     Some(cap.get(1)?.as_str().to_owned())
 }
 
-/// Download the file at `url` with a private HTTP client, returning the path to the downloaded file
-async fn download_http(url: &Url, directory: &Path, filename: &str) -> anyhow::Result<PathBuf> {
+/// Download the file at `url` with a private HTTP client, returning the path to the downloaded file.
+/// If `checksum` is given, the digest is computed while streaming and the download fails on a mismatch.
+#[tracing::instrument(name = "snapshot_download", skip_all, fields(%url, %filename))]
+async fn download_http(
+    client: &reqwest::Client,
+    url: &Url,
+    directory: &Path,
+    filename: &str,
+    checksum: Option<&Checksum>,
+    progress: &Mutex<Box<dyn DownloadProgress + Send>>,
+    metrics: Option<&SnapshotDownloadMetrics>,
+) -> anyhow::Result<PathBuf> {
+    let started = Instant::now();
     let dst_path = directory.join(filename);
     let destination = dst_path.display();
     event!(target: "forest::snapshot", tracing::Level::INFO, %url, %destination, "downloading snapshot");
-    let mut reader = crate::utils::net::reader(url.as_str()).await?;
     let tmp_dst_path = {
         // like `crdownload` for the chrome browser
         const DOWNLOAD_EXTENSION: &str = "frdownload";
@@ -152,20 +837,232 @@ async fn download_http(url: &Url, directory: &Path, filename: &str) -> anyhow::R
         }
         path
     };
-    let mut tempfile = tokio::fs::File::create(&tmp_dst_path)
+    // pick up where a previous, interrupted download left off
+    let downloaded = tokio::fs::metadata(&tmp_dst_path)
         .await
-        .context("couldn't create destination file")?;
-    tokio::io::copy(&mut reader, &mut tempfile)
-        .await
-        .context("couldn't download file")?;
+        .map(|metadata| metadata.len())
+        .unwrap_or_default();
+    let (mut reader, resumed, total) = download_reader(client, url, downloaded).await?;
+    let mut downloaded = if resumed { downloaded } else { 0 };
+    let mut hasher = checksum.map(|checksum| checksum.algorithm.hasher());
+    let tempfile = if resumed {
+        if let Some(hasher) = &mut hasher {
+            let mut existing = tokio::io::BufReader::new(
+                tokio::fs::File::open(&tmp_dst_path)
+                    .await
+                    .context("couldn't open partially downloaded file")?,
+            );
+            hash_reader(&mut existing, hasher).await?;
+        }
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&tmp_dst_path)
+            .await
+            .context("couldn't open partially downloaded file")?
+    } else {
+        tokio::fs::File::create(&tmp_dst_path)
+            .await
+            .context("couldn't create destination file")?
+    };
+    let mut tempfile = tokio::io::BufWriter::new(tempfile);
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .await
+            .context("couldn't download file")?;
+        if n == 0 {
+            break;
+        }
+        #[allow(clippy::indexing_slicing)]
+        let chunk = &buf[..n];
+        if let Some(hasher) = &mut hasher {
+            hasher.update(chunk);
+        }
+        tempfile
+            .write_all(chunk)
+            .await
+            .context("couldn't write file")?;
+        downloaded += n as u64;
+        if let Some(metrics) = metrics {
+            metrics.bytes.inc_by(n as u64);
+        }
+        progress.lock().on_chunk(downloaded, total);
+    }
+    tempfile.flush().await.context("couldn't write file")?;
+    if let (Some(checksum), Some(hasher)) = (checksum, hasher) {
+        let validated = validate_checksum(checksum, &hasher.finalize());
+        if let Some(metrics) = metrics {
+            match validated {
+                Ok(()) => metrics.checksum_passed.inc(),
+                Err(_) => metrics.checksum_failed.inc(),
+            };
+        }
+        if let Err(e) = validated {
+            // don't resume from corrupted data on the next attempt
+            tokio::fs::remove_file(&tmp_dst_path).await?;
+            return Err(e);
+        }
+    }
     std::fs::rename(&tmp_dst_path, &dst_path).context("couldn't rename file")?;
+    progress.lock().on_complete();
+    if let Some(metrics) = metrics {
+        metrics.duration.observe(started.elapsed().as_secs_f64());
+    }
 
     Ok(dst_path)
 }
 
+/// Decompresses the `.zst` file at `path` next to it, without the extension, and removes it.
+/// Returns the path of the decompressed file.
+async fn decompress_zstd(path: &Path) -> anyhow::Result<PathBuf> {
+    let dst_path = path.with_extension("");
+    let source = tokio::fs::File::open(path)
+        .await
+        .context("couldn't open downloaded file")?;
+    let mut decoder =
+        async_compression::tokio::bufread::ZstdDecoder::new(tokio::io::BufReader::new(source));
+    // forest snapshots consist of many zstd frames
+    decoder.multiple_members(true);
+    let mut destination = tokio::io::BufWriter::new(
+        tokio::fs::File::create(&dst_path)
+            .await
+            .context("couldn't create decompressed file")?,
+    );
+    let copied = async {
+        tokio::io::copy(&mut decoder, &mut destination).await?;
+        destination.flush().await
+    }
+    .await;
+    drop(destination);
+    if let Err(e) = copied {
+        tokio::fs::remove_file(&dst_path).await?;
+        return Err(anyhow::Error::new(e).context("couldn't decompress snapshot"));
+    }
+    tokio::fs::remove_file(path).await?;
+    Ok(dst_path)
+}
+
+/// Replaces the CARv1 file at `path` with a CARv2 file of the same blocks.
+async fn convert_to_car_v2(path: &Path) -> anyhow::Result<()> {
+    let path = path.to_owned();
+    tokio::task::spawn_blocking(move || {
+        let source = std::fs::File::open(&path).context("couldn't open downloaded file")?;
+        let destination = tempfile::NamedTempFile::new_in(
+            path.parent()
+                .context("snapshot path has no parent directory")?,
+        )?;
+        crate::db::car::plain::write_v2(source, std::io::BufWriter::new(destination.as_file()))
+            .context("couldn't convert snapshot to CARv2")?;
+        destination.persist(&path)?;
+        anyhow::Ok(())
+    })
+    .await?
+}
+
+/// Returns a reader of the file at `url`, whether it starts at byte `offset` rather than at the
+/// beginning of the file, and the size of the file, or zero if unknown. The reader starts at the
+/// beginning if `offset` is zero, or if the server doesn't support range requests.
+async fn download_reader(
+    client: &reqwest::Client,
+    url: &Url,
+    offset: u64,
+) -> anyhow::Result<(impl tokio::io::AsyncRead + Unpin, bool, u64)> {
+    let download = || async {
+        let response = reqwest_resume::Client::from(client.clone())
+            .get(url.clone())
+            .send()
+            .await?;
+        let response_ref = response.response().error_for_status_ref()?;
+        check_content_type(response_ref.headers())?;
+        let total = response_ref.content_length().unwrap_or_default();
+        let stream = Box::pin(response.bytes_stream().map_err(std::io::Error::other));
+        anyhow::Ok((
+            Left(tokio_util::io::StreamReader::new(stream)),
+            false,
+            total,
+        ))
+    };
+    if offset == 0 {
+        return download().await;
+    }
+    let response = client
+        .get(url.clone())
+        .header(reqwest::header::RANGE, format!("bytes={offset}-"))
+        .send()
+        .await?;
+    if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // the partial file is no prefix of the remote one, e.g. because the latter has changed
+        event!(target: "forest::snapshot", tracing::Level::WARN, %url, offset, "unsatisfiable range, restarting download");
+        return download().await;
+    }
+    let response = response.error_for_status()?;
+    check_content_type(response.headers())?;
+    let resumed = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    if resumed {
+        event!(target: "forest::snapshot", tracing::Level::INFO, %url, offset, "resuming download");
+    } else {
+        event!(target: "forest::snapshot", tracing::Level::WARN, %url, "server doesn't support range requests, restarting download");
+    }
+    let total = response
+        .content_length()
+        .map(|len| if resumed { len + offset } else { len })
+        .unwrap_or_default();
+    let stream = Box::pin(response.bytes_stream().map_err(std::io::Error::other));
+    Ok((
+        Right(tokio_util::io::StreamReader::new(stream)),
+        resumed,
+        total,
+    ))
+}
+
+/// Rejects responses whose `Content-Type` is a text document rather than binary data, e.g. an
+/// XML `AccessDenied` error page served with a success status, before it is written to disk.
+/// Other content types are accepted, as mirrors label snapshots inconsistently, e.g. S3 serves
+/// `binary/octet-stream` for objects uploaded without a content type. Responses without the
+/// header are accepted too.
+fn check_content_type(headers: &reqwest::header::HeaderMap) -> anyhow::Result<()> {
+    const DOCUMENT_SUBTYPES: [&str; 3] = ["xml", "html", "json"];
+    let Some(value) = headers.get(reqwest::header::CONTENT_TYPE) else {
+        return Ok(());
+    };
+    let content_type = value.to_str().unwrap_or_default();
+    // strip parameters, e.g. `text/html; charset=utf-8`
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let (kind, subtype) = media_type.split_once('/').unwrap_or((&media_type, ""));
+    // structured syntax suffixes, e.g. `application/problem+json`
+    let syntax = subtype.rsplit('+').next().unwrap_or_default();
+    anyhow::ensure!(
+        kind != "text" && !DOCUMENT_SUBTYPES.contains(&syntax),
+        "unexpected content type {content_type:?} of snapshot download, expected binary data"
+    );
+    Ok(())
+}
+
+async fn hash_reader(
+    reader: &mut (impl tokio::io::AsyncRead + Unpin),
+    hasher: &mut ChecksumHasher,
+) -> anyhow::Result<()> {
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        #[allow(clippy::indexing_slicing)]
+        let chunk = &buf[..n];
+        hasher.update(chunk);
+    }
+}
+
 /// Also defines an `ALL_URLS` constant for test purposes
 macro_rules! define_urls {
-    ($($vis:vis const $name:ident: &str = $value:literal;)* $(,)?) => {
+    ($($vis:vis const $name:ident: &str = $value:expr;)* $(,)?) => {
         $($vis const $name: &str = $value;)*
 
         #[cfg(test)]
@@ -176,7 +1073,12 @@ macro_rules! define_urls {
 }
 
 define_urls!(
-    const FOREST_MAINNET_COMPRESSED: &str = "https://forest-archive.chainsafe.dev/latest/mainnet/";
+    // Distributors can bake in their own default by setting `FOREST_DEFAULT_SNAPSHOT_URL` when
+    // building Forest.
+    const FOREST_MAINNET_COMPRESSED: &str = match option_env!("FOREST_DEFAULT_SNAPSHOT_URL") {
+        Some(url) => url,
+        None => "https://forest-archive.chainsafe.dev/latest/mainnet/",
+    };
     const FOREST_CALIBNET_COMPRESSED: &str =
         "https://forest-archive.chainsafe.dev/latest/calibnet/";
 );
@@ -195,7 +1097,8 @@ pub fn stable_url(vendor: TrustedVendor, chain: &NetworkChain) -> anyhow::Result
 #[test]
 fn parse_stable_urls() {
     for url in ALL_URLS {
-        let _did_not_panic = Url::from_str(url).unwrap();
+        // this includes a `FOREST_DEFAULT_SNAPSHOT_URL` set at build time
+        Url::from_str(url).unwrap_or_else(|e| panic!("invalid snapshot URL {url}: {e}"));
     }
 }
 
@@ -464,9 +1367,820 @@ mod parse {
 
 #[cfg(test)]
 mod tests {
-    use super::parse_content_disposition;
+    use super::*;
     use reqwest::header::HeaderValue;
 
+    #[test]
+    fn stable_url_calibnet() {
+        assert_eq!(
+            stable_url(TrustedVendor::Forest, &NetworkChain::Calibnet)
+                .unwrap()
+                .as_str(),
+            FOREST_CALIBNET_COMPRESSED
+        );
+        assert!(stable_url(TrustedVendor::Forest, &NetworkChain::Butterflynet).is_err());
+    }
+
+    #[test]
+    fn filename_from_url_calibnet() {
+        let url = Url::from_str(
+            "https://forest-archive.chainsafe.dev/calibnet/forest_snapshot_calibnet_2023-09-14_height_911888.forest.car.zst",
+        )
+        .unwrap();
+        assert_eq!(
+            filename_from_url(&url).unwrap(),
+            "forest_snapshot_calibnet_2023-09-14_height_911888.forest.car.zst"
+        );
+        assert_eq!(
+            filename_from_url(&Url::from_str(FOREST_CALIBNET_COMPRESSED).unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn checksum_sha256() {
+        let expected = checksum_from_file(
+            ChecksumAlgorithm::Sha256,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824 hello.car.zst\n",
+        )
+        .unwrap();
+        let mut hasher = ChecksumAlgorithm::Sha256.hasher();
+        hasher.update(b"hel");
+        hasher.update(b"lo");
+        validate_checksum(&expected, &hasher.finalize()).unwrap();
+    }
+
+    #[test]
+    fn checksum_blake3() {
+        let digest = blake3::hash(b"hello").to_hex().to_string();
+        let expected = checksum_from_file(
+            ChecksumAlgorithm::Blake3,
+            &format!("{digest} hello.car.zst"),
+        )
+        .unwrap();
+        let mut hasher = ChecksumAlgorithm::Blake3.hasher();
+        hasher.update(b"hello");
+        validate_checksum(&expected, &hasher.finalize()).unwrap();
+    }
+
+    #[test]
+    fn checksum_algorithm_mismatch() {
+        let expected = checksum_from_file(
+            ChecksumAlgorithm::Sha256,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824 hello.car.zst",
+        )
+        .unwrap();
+        let mut hasher = ChecksumAlgorithm::Blake3.hasher();
+        hasher.update(b"hello");
+        let error = validate_checksum(&expected, &hasher.finalize()).unwrap_err();
+        assert!(error.to_string().starts_with("sha256 checksum mismatch"));
+    }
+
+    #[test]
+    fn checksum_length_mismatch() {
+        let expected = checksum_from_file(
+            ChecksumAlgorithm::Sha256,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824 hello.car.zst",
+        )
+        .unwrap();
+        for actual in ["", "2cf24dba", format!("{}00", expected.digest).as_str()] {
+            let error = validate_checksum(&expected, actual).unwrap_err();
+            assert!(error
+                .to_string()
+                .starts_with("sha256 checksum length mismatch"));
+        }
+        // case doesn't matter
+        validate_checksum(&expected, &expected.digest.to_uppercase()).unwrap();
+    }
+
+    #[test]
+    fn checksum_malformed() {
+        assert!(checksum_from_file(ChecksumAlgorithm::Blake3, "").is_err());
+        assert!(checksum_from_file(ChecksumAlgorithm::Blake3, "cafe hello.car.zst").is_err());
+    }
+
+    #[test]
+    fn checksum_url_forest() {
+        let url = Url::from_str(
+            "https://forest-archive.chainsafe.dev/calibnet/forest_snapshot_calibnet_2023-09-14_height_911888.forest.car.zst",
+        )
+        .unwrap();
+        assert_eq!(
+            checksum_url(&url, ChecksumAlgorithm::Blake3).unwrap().as_str(),
+            "https://forest-archive.chainsafe.dev/calibnet/forest_snapshot_calibnet_2023-09-14_height_911888.forest.car.blake3sum"
+        );
+    }
+
+    #[test]
+    fn checksum_url_edge_cases() {
+        #[track_caller]
+        fn case(url: &str, expected: &str) {
+            let url = Url::from_str(url).unwrap();
+            assert_eq!(
+                checksum_url(&url, ChecksumAlgorithm::Sha256)
+                    .unwrap()
+                    .as_str(),
+                expected
+            );
+        }
+        // only the last extension is replaced
+        case(
+            "https://example.com/snapshot.v2.car",
+            "https://example.com/snapshot.v2.sha256sum",
+        );
+        // uppercase extensions are replaced as well
+        case(
+            "https://example.com/SNAPSHOT.V2.CAR",
+            "https://example.com/SNAPSHOT.V2.sha256sum",
+        );
+        case(
+            "http://localhost:8080/calibnet/snapshot.car.zst",
+            "http://localhost:8080/calibnet/snapshot.car.sha256sum",
+        );
+        case(
+            "https://example.com/snapshot.car?token=abc#fragment",
+            "https://example.com/snapshot.sha256sum",
+        );
+        // a stem that looks like a scheme stays a relative path
+        case(
+            "https://example.com/calibnet/forest:snapshot.car",
+            "https://example.com/calibnet/forest:snapshot.sha256sum",
+        );
+        assert!(checksum_url(
+            &Url::from_str("https://example.com/calibnet/").unwrap(),
+            ChecksumAlgorithm::Sha256
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn checksum_blake2b() {
+        let digest = blake2b_simd::blake2b(b"hello").to_hex().to_string();
+        let expected = checksum_from_file(
+            ChecksumAlgorithm::Blake2b,
+            &format!("{digest}  hello.car.zst"),
+        )
+        .unwrap();
+        let mut hasher = ChecksumAlgorithm::Blake2b.hasher();
+        hasher.update(b"hello");
+        validate_checksum(&expected, &hasher.finalize()).unwrap();
+        // a 32-byte digest isn't a valid BLAKE2b-512 digest
+        assert!(
+            checksum_from_file(ChecksumAlgorithm::Blake2b, &blake3::hash(b"hello").to_hex())
+                .is_err()
+        );
+    }
+
+    const SNAPSHOT: &[u8] = b"pretend this is a very large snapshot";
+
+    /// Serves [`SNAPSHOT`], honoring `Range: bytes=N-` headers if `ranges` is set.
+    async fn serve_snapshot(ranges: bool) -> Url {
+        serve_snapshot_with_sidecars(ranges, vec![]).await
+    }
+
+    /// Like [`serve_snapshot`], also serving the given `(filename, content)` checksum files.
+    async fn serve_snapshot_with_sidecars(
+        ranges: bool,
+        sidecars: Vec<(&'static str, String)>,
+    ) -> Url {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!(
+            "http://{}/snapshot.car.zst",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        let app = axum::Router::new().route(
+            "/snapshot.car.zst",
+            axum::routing::get(move |headers: http::HeaderMap| async move {
+                let offset = headers
+                    .get(http::header::RANGE)
+                    .filter(|_| ranges)
+                    .and_then(|range| {
+                        range
+                            .to_str()
+                            .ok()?
+                            .strip_prefix("bytes=")?
+                            .strip_suffix('-')?
+                            .parse::<usize>()
+                            .ok()
+                    });
+                match offset {
+                    Some(offset) => (
+                        http::StatusCode::PARTIAL_CONTENT,
+                        SNAPSHOT[offset..].to_vec(),
+                    ),
+                    None => (http::StatusCode::OK, SNAPSHOT.to_vec()),
+                }
+            }),
+        );
+        let app = sidecars.into_iter().fold(app, |app, (filename, content)| {
+            app.route(
+                &format!("/{filename}"),
+                axum::routing::get(move || async move { content }),
+            )
+        });
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    async fn download_interrupted(ranges: bool) {
+        let directory = tempfile::tempdir().unwrap();
+        let url = serve_snapshot(ranges).await;
+        // a previous download got interrupted part way
+        std::fs::write(
+            directory.path().join("snapshot.car.zst.frdownload"),
+            &SNAPSHOT[..10],
+        )
+        .unwrap();
+        let checksum = Checksum {
+            algorithm: ChecksumAlgorithm::Sha256,
+            digest: hex::encode(<sha2::Sha256 as sha2::Digest>::digest(SNAPSHOT)),
+        };
+        let path = download_http(
+            &crate::utils::net::global_http_client(),
+            &url,
+            directory.path(),
+            "snapshot.car.zst",
+            Some(&checksum),
+            &Mutex::new(Box::new(SilentProgress) as Box<dyn DownloadProgress + Send>),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), SNAPSHOT);
+    }
+
+    #[tokio::test]
+    async fn verify_local_snapshot() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("snapshot.car.zst");
+        std::fs::write(&path, SNAPSHOT).unwrap();
+        // no checksum file yet
+        assert!(verify(&path, None).await.is_err());
+
+        let digest = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(SNAPSHOT));
+        let sidecar = directory.path().join("snapshot.car.sha256sum");
+        std::fs::write(&sidecar, format!("{digest}  snapshot.car.zst")).unwrap();
+        assert_eq!(verify(&path, None).await.unwrap().digest, digest);
+
+        // the checksum file can be anywhere
+        let elsewhere = directory.path().join("elsewhere.sha256sum");
+        std::fs::rename(&sidecar, &elsewhere).unwrap();
+        let checksum_url = Url::from_file_path(&elsewhere).unwrap();
+        verify(&path, Some(&checksum_url)).await.unwrap();
+
+        std::fs::write(&path, b"corrupted").unwrap();
+        let error = verify(&path, Some(&checksum_url)).await.unwrap_err();
+        assert!(error.to_string().contains("mismatch"));
+    }
+
+    #[test]
+    fn snapshot_content_type() {
+        use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+
+        let headers = |content_type: &'static str| {
+            HeaderMap::from_iter([(CONTENT_TYPE, HeaderValue::from_static(content_type))])
+        };
+        check_content_type(&HeaderMap::new()).unwrap();
+        for content_type in [
+            "application/octet-stream",
+            // S3's default for objects uploaded without a content type
+            "binary/octet-stream",
+            "application/vnd.ipld.car; version=1",
+            "Application/Zstd",
+        ] {
+            check_content_type(&headers(content_type)).unwrap();
+        }
+        let error = check_content_type(&headers("application/xml"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unexpected content type \"application/xml\""));
+        for content_type in [
+            "text/html; charset=utf-8",
+            "text/plain",
+            "application/json",
+            "application/problem+json",
+            "application/xhtml+xml",
+        ] {
+            assert!(check_content_type(&headers(content_type)).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn download_resume() {
+        download_interrupted(true).await;
+    }
+
+    #[tokio::test]
+    async fn download_resume_unsupported() {
+        download_interrupted(false).await;
+    }
+
+    #[tokio::test]
+    async fn fetch_custom_url() {
+        let directory = tempfile::tempdir().unwrap();
+        let url = serve_snapshot(true).await;
+        let fetch_from = |skip_checksum| {
+            fetch(
+                directory.path(),
+                &NetworkChain::Calibnet,
+                TrustedVendor::Forest,
+                FetchOptions {
+                    url: Some(url.clone()),
+                    skip_checksum,
+                    progress: Some(Box::new(SilentProgress)),
+                    max_attempts: 1,
+                    dry_run: false,
+                    decompress: false,
+                    car_v2: false,
+                    metrics: None,
+                    http: SnapshotFetchConfig::default(),
+                },
+            )
+        };
+        // the test server publishes no checksum file
+        let err = fetch_from(false).await.unwrap_err();
+        assert!(err.to_string().contains("no checksum file found"));
+
+        let path = fetch_from(true).await.unwrap().path;
+        assert_eq!(path, directory.path().join("snapshot.car.zst"));
+        assert_eq!(std::fs::read(path).unwrap(), SNAPSHOT);
+    }
+
+    #[tokio::test]
+    async fn fetch_stalled_times_out() {
+        // accepts connections, but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!(
+            "http://{}/snapshot.car.zst",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        tokio::spawn(async move {
+            let mut connections = vec![];
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.push(stream);
+            }
+        });
+        let directory = tempfile::tempdir().unwrap();
+        let started = Instant::now();
+        let result = tokio::time::timeout(
+            Duration::from_secs(10),
+            fetch(
+                directory.path(),
+                &NetworkChain::Calibnet,
+                TrustedVendor::Forest,
+                FetchOptions {
+                    url: Some(url),
+                    skip_checksum: true,
+                    max_attempts: 1,
+                    http: SnapshotFetchConfig {
+                        read_timeout: Duration::from_millis(100),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            ),
+        )
+        .await
+        .expect("stalled download didn't time out");
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn fetch_dry_run() {
+        let directory = tempfile::tempdir().unwrap();
+        let url = serve_snapshot(true).await;
+        let meta = fetch(
+            directory.path(),
+            &NetworkChain::Calibnet,
+            TrustedVendor::Forest,
+            FetchOptions {
+                url: Some(url.clone()),
+                skip_checksum: true,
+                max_attempts: 1,
+                dry_run: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            meta,
+            SnapshotMeta {
+                url,
+                path: directory.path().join("snapshot.car.zst"),
+                total_size: SNAPSHOT.len() as u64,
+                checksum: None,
+            }
+        );
+        assert_eq!(std::fs::read_dir(directory.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn decompress_zstd_snapshot() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("snapshot.car.zst");
+        // two frames, like in a forest snapshot
+        let (head, tail) = SNAPSHOT.split_at(10);
+        let compressed = [
+            zstd::encode_all(head, 3).unwrap(),
+            zstd::encode_all(tail, 3).unwrap(),
+        ]
+        .concat();
+        std::fs::write(&path, compressed).unwrap();
+
+        let decompressed = decompress_zstd(&path).await.unwrap();
+        assert_eq!(decompressed, directory.path().join("snapshot.car"));
+        assert_eq!(std::fs::read(&decompressed).unwrap(), SNAPSHOT);
+        assert!(!path.exists());
+
+        // corrupted input leaves nothing half-written behind
+        std::fs::write(&path, SNAPSHOT).unwrap();
+        decompress_zstd(&path).await.unwrap_err();
+        assert!(path.exists());
+        assert!(!directory.path().join("snapshot.car").exists());
+
+        // the dry run reports the decompressed path
+        let url = serve_snapshot(true).await;
+        let meta = fetch(
+            directory.path(),
+            &NetworkChain::Calibnet,
+            TrustedVendor::Forest,
+            FetchOptions {
+                url: Some(url),
+                skip_checksum: true,
+                max_attempts: 1,
+                dry_run: true,
+                decompress: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(meta.path, directory.path().join("snapshot.car"));
+    }
+
+    #[tokio::test]
+    async fn backoff_attempts() {
+        let attempts = std::sync::atomic::AtomicUsize::new(0);
+        let fail_once = || async {
+            match attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+                0 => bail!("transient error"),
+                n => Ok(n),
+            }
+        };
+        assert_eq!(with_backoff(2, fail_once).await.unwrap(), 1);
+
+        attempts.store(0, std::sync::atomic::Ordering::SeqCst);
+        let always_fail = || async {
+            attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            anyhow::Result::<()>::Err(anyhow::anyhow!("permanent error"))
+        };
+        let error = with_backoff(2, always_fail).await.unwrap_err();
+        assert_eq!(error.to_string(), "permanent error");
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // zero still means one attempt
+        attempts.store(0, std::sync::atomic::Ordering::SeqCst);
+        with_backoff(0, always_fail).await.unwrap_err();
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn detect_blake2b_sidecar() {
+        let digest = blake2b_simd::blake2b(SNAPSHOT).to_hex().to_string();
+        let url = serve_snapshot_with_sidecars(
+            true,
+            vec![(
+                "snapshot.car.blake2bsum",
+                format!("{digest}  snapshot.car.zst"),
+            )],
+        )
+        .await;
+        let client = crate::utils::net::global_http_client();
+        let (algorithm, sidecar_url) = detect_sidecar_url(&client, &url).await.unwrap();
+        assert_eq!(algorithm, ChecksumAlgorithm::Blake2b);
+        assert_eq!(sidecar_url, url.join("snapshot.car.blake2bsum").unwrap());
+        assert_eq!(
+            probe_checksum(&client, &url).await,
+            Some(Checksum {
+                algorithm: ChecksumAlgorithm::Blake2b,
+                digest,
+            })
+        );
+
+        // the snapshot is verified against the detected checksum
+        let directory = tempfile::tempdir().unwrap();
+        let path = fetch(
+            directory.path(),
+            &NetworkChain::Calibnet,
+            TrustedVendor::Forest,
+            FetchOptions {
+                url: Some(url),
+                progress: Some(Box::new(SilentProgress)),
+                max_attempts: 1,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap()
+        .path;
+        assert_eq!(std::fs::read(path).unwrap(), SNAPSHOT);
+    }
+
+    /// The `(downloaded, total)` bytes reported for each chunk, and whether the download completed.
+    type Progress = (Vec<(u64, u64)>, bool);
+
+    #[derive(Default, Clone)]
+    struct RecordingProgress(Arc<Mutex<Progress>>);
+
+    impl DownloadProgress for RecordingProgress {
+        fn on_chunk(&mut self, downloaded: u64, total: u64) {
+            self.0.lock().0.push((downloaded, total));
+        }
+
+        fn on_complete(&mut self) {
+            self.0.lock().1 = true;
+        }
+    }
+
+    #[tokio::test]
+    async fn download_metrics() {
+        let directory = tempfile::tempdir().unwrap();
+        let url = serve_snapshot(true).await;
+        let metrics = SnapshotDownloadMetrics::default();
+        let mut registry = Registry::default();
+        metrics.register(&mut registry);
+        let (url, directory, metrics) = (&url, directory.path(), &metrics);
+        let download = |digest: String| async move {
+            download_http(
+                &crate::utils::net::global_http_client(),
+                url,
+                directory,
+                "snapshot.car.zst",
+                Some(&Checksum {
+                    algorithm: ChecksumAlgorithm::Sha256,
+                    digest,
+                }),
+                &Mutex::new(Box::new(SilentProgress) as Box<dyn DownloadProgress + Send>),
+                Some(metrics),
+            )
+            .await
+        };
+        let digest = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(SNAPSHOT));
+        download(digest).await.unwrap();
+        download("00".repeat(32)).await.unwrap_err();
+
+        let len = SNAPSHOT.len() as u64;
+        assert_eq!(metrics.bytes.get(), 2 * len);
+        assert_eq!(metrics.checksum_passed.get(), 1);
+        assert_eq!(metrics.checksum_failed.get(), 1);
+        let mut encoded = String::new();
+        prometheus_client::encoding::text::encode(&mut encoded, &registry).unwrap();
+        // only the successful download is timed
+        assert!(encoded.contains("snapshot_download_duration_seconds_count 1\n"));
+        assert!(encoded.contains(&format!("snapshot_download_bytes_total {}\n", 2 * len)));
+    }
+
+    /// The name of a span and its `(name, value)` fields.
+    type RecordedSpan = (String, Vec<(String, String)>);
+
+    /// Records the names and fields of the spans created while it is the default subscriber.
+    #[derive(Default, Clone)]
+    struct SpanRecorder(Arc<Mutex<Vec<RecordedSpan>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanRecorder {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _: &tracing::span::Id,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            #[derive(Default)]
+            struct Fields(Vec<(String, String)>);
+            impl tracing::field::Visit for Fields {
+                fn record_debug(
+                    &mut self,
+                    field: &tracing::field::Field,
+                    value: &dyn std::fmt::Debug,
+                ) {
+                    self.0.push((field.name().to_owned(), format!("{value:?}")));
+                }
+            }
+            let mut fields = Fields::default();
+            attrs.record(&mut fields);
+            self.0
+                .lock()
+                .push((attrs.metadata().name().to_owned(), fields.0));
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_spans() {
+        use tracing_subscriber::layer::SubscriberExt as _;
+
+        let recorder = SpanRecorder::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+        let directory = tempfile::tempdir().unwrap();
+        let url = serve_snapshot(true).await;
+        fetch(
+            directory.path(),
+            &NetworkChain::Calibnet,
+            TrustedVendor::Forest,
+            FetchOptions {
+                url: Some(url.clone()),
+                skip_checksum: true,
+                progress: Some(Box::new(SilentProgress)),
+                max_attempts: 1,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let spans = recorder.0.lock().clone();
+        let field = |name: &str, key: &str| {
+            spans
+                .iter()
+                .find(|(span, _)| span == name)
+                .and_then(|(_, fields)| fields.iter().find(|(field, _)| field == key))
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(
+            field("snapshot_fetch", "chain").as_deref(),
+            Some("calibnet")
+        );
+        assert_eq!(field("snapshot_fetch", "vendor").as_deref(), Some("forest"));
+        assert_eq!(
+            field("snapshot_download", "filename").as_deref(),
+            Some("snapshot.car.zst")
+        );
+        assert_eq!(field("snapshot_download", "url"), Some(url.to_string()));
+    }
+
+    #[test]
+    fn parse_url_or_path_schemes() {
+        for url in [
+            "https://forest-archive.chainsafe.dev/latest/calibnet/",
+            "http://127.0.0.1:8080/snapshot.car.zst",
+            "file:///tmp/snapshot.car.zst",
+        ] {
+            assert_eq!(parse_url_or_path(url).unwrap().as_str(), url);
+        }
+        assert_eq!(
+            parse_url_or_path("snapshot.car.zst").unwrap().scheme(),
+            "file"
+        );
+        let err = parse_url_or_path("magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056")
+            .unwrap_err();
+        assert!(err.to_string().contains("magnet links are not supported"));
+        assert!(parse_url_or_path("ftp://example.com/snapshot.car.zst").is_err());
+    }
+
+    #[tokio::test]
+    async fn fetch_local_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("snapshot.car.zst");
+        std::fs::write(&path, SNAPSHOT).unwrap();
+        let url = parse_url_or_path(path.to_str().unwrap()).unwrap();
+        assert_eq!(url.scheme(), "file");
+        let fetch_from = |skip_checksum| {
+            fetch(
+                directory.path(),
+                &NetworkChain::Calibnet,
+                TrustedVendor::Forest,
+                FetchOptions {
+                    url: Some(url.clone()),
+                    skip_checksum,
+                    ..Default::default()
+                },
+            )
+        };
+
+        // the checksum file is optional for local snapshots
+        let meta = fetch_from(false).await.unwrap();
+        assert_eq!(meta.path, path);
+        assert_eq!(meta.total_size, SNAPSHOT.len() as u64);
+        assert_eq!(meta.checksum, None);
+
+        let sidecar = directory.path().join("snapshot.car.sha256sum");
+        let digest = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(SNAPSHOT));
+        std::fs::write(&sidecar, format!("{digest}  snapshot.car.zst\n")).unwrap();
+        let meta = fetch_from(false).await.unwrap();
+        assert_eq!(
+            meta.checksum,
+            Some(Checksum {
+                algorithm: ChecksumAlgorithm::Sha256,
+                digest,
+            })
+        );
+
+        std::fs::write(&sidecar, format!("{}  snapshot.car.zst\n", "0".repeat(64))).unwrap();
+        let err = fetch_from(false).await.unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+        fetch_from(true).await.unwrap();
+
+        let missing = directory.path().join("missing.car.zst");
+        fetch_local(
+            Url::from_file_path(&missing).unwrap(),
+            &missing,
+            false,
+            false,
+        )
+        .await
+        .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn download_progress() {
+        let directory = tempfile::tempdir().unwrap();
+        let url = serve_snapshot(true).await;
+        let recording = RecordingProgress::default();
+        download_http(
+            &crate::utils::net::global_http_client(),
+            &url,
+            directory.path(),
+            "snapshot.car.zst",
+            None,
+            &Mutex::new(Box::new(recording.clone()) as Box<dyn DownloadProgress + Send>),
+            None,
+        )
+        .await
+        .unwrap();
+        let (chunks, completed) = recording.0.lock().clone();
+        let len = SNAPSHOT.len() as u64;
+        assert_eq!(chunks.last(), Some(&(len, len)));
+        assert!(chunks.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(completed);
+    }
+
+    #[tokio::test]
+    async fn http_client_sends_user_agent() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::get(|headers: http::HeaderMap| async move {
+                headers
+                    .get(http::header::USER_AGENT)
+                    .and_then(|user_agent| user_agent.to_str().ok())
+                    .unwrap_or_default()
+                    .to_owned()
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let user_agent = SnapshotFetchConfig::default()
+            .client_builder()
+            .build()
+            .unwrap()
+            .get(url)
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(user_agent, format!("forest/{}", env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    fn proxy_client_malformed() {
+        let config = SnapshotFetchConfig::default();
+        assert!(proxy_client("not a url", &config).is_err());
+        assert!(proxy_client("http://127.0.0.1:8080", &config).is_err());
+        assert!(proxy_client("socks5://127.0.0.1", &config).is_err());
+        assert!(proxy_client("socks5h://127.0.0.1:9050", &config).is_ok());
+    }
+
+    #[tokio::test]
+    async fn proxy_client_connects_through_socks5() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("socks5://{}", listener.local_addr().unwrap());
+        let greeting = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            // version and number of authentication methods
+            let mut header = [0; 2];
+            stream.read_exact(&mut header).await.unwrap();
+            let mut methods = vec![0; header[1] as usize];
+            stream.read_exact(&mut methods).await.unwrap();
+            // no acceptable authentication method, which aborts the handshake
+            stream.write_all(&[0x05, 0xFF]).await.unwrap();
+            header[0]
+        });
+
+        let result = proxy_client(&proxy, &SnapshotFetchConfig::default())
+            .unwrap()
+            .get("http://192.0.2.1/latest/calibnet/")
+            .send()
+            .await;
+        assert!(result.is_err());
+        assert_eq!(greeting.await.unwrap(), 0x05);
+    }
+
     #[test]
     fn content_disposition_forest() {
         assert_eq!(
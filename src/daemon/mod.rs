@@ -372,6 +372,7 @@ pub(super) async fn start(
             config: config.clone(),
             chain_config: chain_config.clone(),
             genesis_timestamp: genesis_header.timestamp,
+            beacon: state_manager.beacon_schedule().clone(),
             sync_state: sync_state.clone(),
             peer_manager,
             settings_store: chain_store.settings(),
@@ -402,6 +403,7 @@ pub(super) async fn start(
                     keystore: keystore_rpc,
                     mpool,
                     bad_blocks,
+                    message_cache: Default::default(),
                     sync_state,
                     eth_event_handler: Arc::new(EthEventHandler::new()),
                     sync_network_context,
@@ -463,6 +465,7 @@ pub(super) async fn start(
     // Sets proof parameter file download path early, the files will be checked and
     // downloaded later right after snapshot import step
     crate::utils::proofs_api::set_proofs_parameter_cache_dir_env(&config.client.data_dir);
+    crate::utils::proofs_api::set_proofs_gateways_env(&config.client.proofs_gateways);
 
     // Sets the latest snapshot if needed for downloading later
     let mut config = config;
@@ -493,7 +496,7 @@ pub(super) async fn start(
 
     if let Some(validate_from) = config.client.snapshot_height {
         // We've been provided a snapshot and asked to validate it
-        ensure_params_downloaded().await?;
+        ensure_params_downloaded(config.client.params_file.as_deref()).await?;
         // Use the specified HEAD, otherwise take the current HEAD.
         let current_height = config
             .client
@@ -516,6 +519,8 @@ pub(super) async fn start(
         return Ok(());
     }
 
+    // the config is moved into the populate task
+    let params_file = config.client.params_file.clone();
     // Populate task
     if !opts.stateless && !chain_config.is_devnet() {
         let state_manager = Arc::clone(&state_manager);
@@ -528,7 +533,7 @@ pub(super) async fn start(
     }
 
     if !opts.stateless {
-        ensure_params_downloaded().await?;
+        ensure_params_downloaded(params_file.as_deref()).await?;
     }
     services.spawn(p2p_service.run());
 
@@ -0,0 +1,131 @@
+// Copyright 2019-2024 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::num::NonZeroUsize;
+
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use lru::LruCache;
+use parking_lot::Mutex;
+
+/// Blockstore wrapper that keeps the most recently read blocks in an LRU cache, so that
+/// repeatedly loading the same actor state, e.g. in a watch loop, doesn't hit the backing store
+/// every time. Writes go straight to the backing store. Blocks are content-addressed, so cached
+/// blocks never go stale.
+pub struct CachingBlockstore<BS> {
+    inner: BS,
+    cache: Mutex<LruCache<Cid, Vec<u8>>>,
+}
+
+impl<BS: Blockstore> CachingBlockstore<BS> {
+    /// Wraps `inner`, caching up to `capacity` blocks.
+    pub fn new(inner: BS, capacity: NonZeroUsize) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Number of cached blocks.
+    #[cfg(test)]
+    pub fn cached(&self) -> usize {
+        self.cache.lock().len()
+    }
+}
+
+impl<BS: Blockstore> Blockstore for CachingBlockstore<BS> {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        if let Some(block) = self.cache.lock().get(k) {
+            return Ok(Some(block.clone()));
+        }
+        let block = self.inner.get(k)?;
+        if let Some(block) = &block {
+            self.cache.lock().put(*k, block.clone());
+        }
+        Ok(block)
+    }
+
+    fn has(&self, k: &Cid) -> anyhow::Result<bool> {
+        if self.cache.lock().contains(k) {
+            return Ok(true);
+        }
+        self.inner.has(k)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.inner.put_keyed(k, block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::utils::db::CborStoreExt as _;
+    use nonzero_ext::nonzero;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts the reads that reach the backing store.
+    #[derive(Default)]
+    struct CountingStore {
+        inner: MemoryDB,
+        gets: AtomicUsize,
+    }
+
+    impl Blockstore for CountingStore {
+        fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+            self.gets.fetch_add(1, Ordering::Relaxed);
+            self.inner.get(k)
+        }
+
+        fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+            self.inner.put_keyed(k, block)
+        }
+    }
+
+    fn gets(store: &CachingBlockstore<CountingStore>) -> usize {
+        store.inner.gets.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn hit_and_miss() {
+        let store = CachingBlockstore::new(CountingStore::default(), nonzero!(2usize));
+        let cid = store.put_cbor_default(&"block").unwrap();
+        assert_eq!(store.cached(), 0);
+
+        let block = store.get(&cid).unwrap().unwrap();
+        assert_eq!(gets(&store), 1);
+        assert_eq!(store.cached(), 1);
+        // served from the cache
+        assert_eq!(store.get(&cid).unwrap().unwrap(), block);
+        assert!(store.has(&cid).unwrap());
+        assert_eq!(gets(&store), 1);
+
+        // missing blocks aren't cached
+        let missing = Cid::default();
+        assert_eq!(store.get(&missing).unwrap(), None);
+        assert_eq!(store.get(&missing).unwrap(), None);
+        assert_eq!(gets(&store), 3);
+        assert_eq!(store.cached(), 1);
+    }
+
+    #[test]
+    fn eviction() {
+        let store = CachingBlockstore::new(CountingStore::default(), nonzero!(2usize));
+        let [a, b, c] = ["a", "b", "c"].map(|block| store.put_cbor_default(&block).unwrap());
+
+        store.get(&a).unwrap();
+        store.get(&b).unwrap();
+        // `a` is now the most recently used block, so `b` gets evicted
+        store.get(&a).unwrap();
+        store.get(&c).unwrap();
+        assert_eq!(gets(&store), 3);
+        assert_eq!(store.cached(), 2);
+
+        store.get(&a).unwrap();
+        store.get(&c).unwrap();
+        assert_eq!(gets(&store), 3);
+        store.get(&b).unwrap();
+        assert_eq!(gets(&store), 4);
+    }
+}
@@ -61,6 +61,16 @@ impl<ReaderT: RandomAccessFileReader> AnyCar<ReaderT> {
         }
     }
 
+    /// The root CIDs from the CAR header. For Filecoin snapshots, these are the
+    /// block headers of the heaviest tipset.
+    pub fn roots(&self) -> &nunny::Vec<Cid> {
+        match self {
+            AnyCar::Forest(forest) => forest.roots(),
+            AnyCar::Plain(plain) => plain.roots(),
+            AnyCar::Memory(mem) => mem.roots(),
+        }
+    }
+
     /// Return the identified CAR format variant. There are three variants:
     /// `CARv1`, `CARv1.zst` and `ForestCARv1.zst`.
     pub fn variant(&self) -> &'static str {
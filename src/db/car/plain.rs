@@ -57,7 +57,7 @@
 //! - Use safe arithmetic for all operations - a malicious frame shouldn't cause a crash.
 //! - Theoretically, file-backed blockstores should be clonable (or even [`Sync`]) with very low
 //!   overhead, so that multiple threads could perform operations concurrently.
-//! - CARv2 support
+//! - Reading CARv2 files, see [`write_v2`] for writing them
 //! - A wrapper that abstracts over car formats for reading.
 
 use crate::cid_collections::{hash_map::Entry as CidHashMapEntry, CidHashMap};
@@ -69,7 +69,7 @@ use crate::{
 use crate::utils::db::car_stream::CarHeader;
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
-use integer_encoding::VarIntReader;
+use integer_encoding::{VarInt as _, VarIntReader};
 
 use crate::db::PersistentStore;
 use nunny::Vec as NonEmpty;
@@ -78,10 +78,11 @@ use positioned_io::ReadAt;
 use std::ops::DerefMut;
 use std::{
     any::Any,
+    collections::BTreeMap,
     io::{
         self, BufReader,
         ErrorKind::{InvalidData, UnexpectedEof, Unsupported},
-        Read, Seek, SeekFrom,
+        Read, Seek, SeekFrom, Write,
     },
     iter,
 };
@@ -292,7 +293,7 @@ fn handle_write_cache(
     }
 }
 
-fn get_roots_from_v1_header(reader: impl Read) -> io::Result<NonEmpty<Cid>> {
+pub fn get_roots_from_v1_header(reader: impl Read) -> io::Result<NonEmpty<Cid>> {
     match read_header(reader)? {
         CarHeader { roots, version: 1 } => Ok(roots),
         other_version => Err(io::Error::new(
@@ -417,12 +418,199 @@ where
     }
 }
 
+/// The bytes every [CARv2 file](https://ipld.io/specs/transport/car/carv2/#pragma) starts with.
+pub const V2_PRAGMA: [u8; 11] = [
+    0x0a, 0xa1, 0x67, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x02,
+];
+
+/// Multicodec of the `IndexSorted` CARv2 index, which only go-car writes.
+const INDEX_SORTED: u64 = 0x0400;
+/// Multicodec of the `MultihashIndexSorted` CARv2 index.
+const MULTIHASH_INDEX_SORTED: u64 = 0x0401;
+
+/// The [CARv2 header](https://ipld.io/specs/transport/car/carv2/#header) that follows
+/// [`V2_PRAGMA`]. Offsets are from the start of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct V2Header {
+    pub data_offset: u64,
+    pub data_size: u64,
+    /// Zero if the file has no index
+    pub index_offset: u64,
+}
+
+impl V2Header {
+    /// Length of the header, including the characteristics bitfield that precedes the offsets.
+    const LEN: usize = 40;
+
+    /// Reads the pragma and header at the start of `reader`, or returns `None` if it isn't a
+    /// CARv2 file.
+    pub fn read(mut reader: impl Read) -> io::Result<Option<Self>> {
+        let mut pragma = [0; V2_PRAGMA.len()];
+        match reader.read_exact(&mut pragma) {
+            Err(e) if e.kind() == UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+        if pragma != V2_PRAGMA {
+            return Ok(None);
+        }
+        let mut characteristics = [0; 16];
+        reader.read_exact(&mut characteristics)?;
+        let mut field = || {
+            let mut bytes = [0; 8];
+            reader
+                .read_exact(&mut bytes)
+                .map(|()| u64::from_le_bytes(bytes))
+        };
+        Ok(Some(Self {
+            data_offset: field()?,
+            data_size: field()?,
+            index_offset: field()?,
+        }))
+    }
+
+    /// Writes the pragma and header, with no characteristics set.
+    fn write(&self, mut dest: impl Write) -> io::Result<()> {
+        dest.write_all(&V2_PRAGMA)?;
+        dest.write_all(&[0; 16])?;
+        for field in [self.data_offset, self.data_size, self.index_offset] {
+            dest.write_all(&field.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts the blocks of the CARv1 of `size` bytes starting at the current position of `reader`,
+/// seeking past their data rather than reading it.
+pub fn count_v1_blocks(mut reader: impl Read + Seek, size: u64) -> io::Result<u64> {
+    let end = reader.stream_position()? + size;
+    let mut reader = BufReader::with_capacity(1024, reader);
+    get_roots_from_v1_header(&mut reader)?;
+    let mut count = 0;
+    while reader.stream_position()? < end {
+        if read_block_data_location_and_skip(&mut reader)?.is_none() {
+            break;
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Counts the entries of the CARv2 index starting at the current position of `reader`, i.e. the
+/// number of indexed blocks, or returns `None` for index formats other than `IndexSorted` and
+/// `MultihashIndexSorted`.
+pub fn count_v2_index_entries(mut reader: impl Read + Seek) -> io::Result<Option<u64>> {
+    fn read_le<const N: usize>(mut reader: impl Read) -> io::Result<[u8; N]> {
+        let mut buffer = [0; N];
+        reader.read_exact(&mut buffer)?;
+        Ok(buffer)
+    }
+    fn len(n: i64) -> io::Result<u64> {
+        u64::try_from(n).map_err(|e| io::Error::new(InvalidData, e))
+    }
+    // entries of the buckets of each digest width
+    fn count_multi_width(mut reader: impl Read + Seek) -> io::Result<u64> {
+        let mut count = 0;
+        for _ in 0..len(i32::from_le_bytes(read_le(&mut reader)?).into())? {
+            let width = u32::from_le_bytes(read_le(&mut reader)?);
+            let length = len(i64::from_le_bytes(read_le(&mut reader)?))?;
+            if width == 0 || length % u64::from(width) != 0 {
+                return Err(io::Error::new(InvalidData, "invalid CARv2 index bucket"));
+            }
+            count += length / u64::from(width);
+            reader.seek(SeekFrom::Current(length as i64))?;
+        }
+        Ok(count)
+    }
+
+    let codec: u64 = reader.read_varint()?;
+    match codec {
+        INDEX_SORTED => count_multi_width(&mut reader).map(Some),
+        MULTIHASH_INDEX_SORTED => {
+            let mut count = 0;
+            for _ in 0..len(i32::from_le_bytes(read_le(&mut reader)?).into())? {
+                // multihash code
+                read_le::<8>(&mut reader)?;
+                count += count_multi_width(&mut reader)?;
+            }
+            Ok(Some(count))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Wraps the CARv1 in `source` in a [CARv2 file](https://ipld.io/specs/transport/car/carv2),
+/// followed by a `MultihashIndexSorted` index of the offsets of its blocks, so that readers can
+/// find a block without scanning the whole file.
+///
+/// `source` is read twice, once to build the index and once to copy the CARv1 payload.
+/// ```text
+/// ├──────┬──────────┬─────────────┬─────┐
+/// │pragma│v2 header │CARv1 payload│index│
+/// └──────┴──────────┴─────────────┴─────┘
+/// ```
+pub fn write_v2(mut source: impl Read + Seek, mut dest: impl Write) -> io::Result<()> {
+    // multihash code -> digest length -> (digest, offset of the block frame in the payload)
+    let mut index = BTreeMap::<u64, BTreeMap<usize, Vec<(Vec<u8>, u64)>>>::new();
+    source.rewind()?;
+    let mut reader = BufReader::new(&mut source);
+    get_roots_from_v1_header(&mut reader)?;
+    loop {
+        let frame_offset = reader.stream_position()?;
+        let Some((cid, _)) = read_block_data_location_and_skip(&mut reader)? else {
+            break;
+        };
+        let hash = cid.hash();
+        index
+            .entry(hash.code())
+            .or_default()
+            .entry(hash.digest().len())
+            .or_default()
+            .push((hash.digest().to_vec(), frame_offset));
+    }
+    let data_size = reader.stream_position()?;
+    drop(reader);
+
+    let data_offset = (V2_PRAGMA.len() + V2Header::LEN) as u64;
+    V2Header {
+        data_offset,
+        data_size,
+        index_offset: data_offset + data_size,
+    }
+    .write(&mut dest)?;
+    source.rewind()?;
+    if io::copy(&mut (&mut source).take(data_size), &mut dest)? != data_size {
+        return Err(UnexpectedEof.into());
+    }
+
+    let count = |len: usize| i32::try_from(len).map_err(|e| io::Error::new(InvalidData, e));
+    dest.write_all(&MULTIHASH_INDEX_SORTED.encode_var_vec())?;
+    dest.write_all(&count(index.len())?.to_le_bytes())?;
+    for (code, widths) in index {
+        dest.write_all(&code.to_le_bytes())?;
+        dest.write_all(&count(widths.len())?.to_le_bytes())?;
+        for (digest_len, mut entries) in widths {
+            entries.sort_unstable();
+            let width = digest_len + 8;
+            dest.write_all(&(width as u32).to_le_bytes())?;
+            dest.write_all(&((width * entries.len()) as i64).to_le_bytes())?;
+            for (digest, offset) in entries {
+                dest.write_all(&digest)?;
+                dest.write_all(&offset.to_le_bytes())?;
+            }
+        }
+    }
+    dest.flush()
+}
+
 #[cfg(test)]
 mod tests {
     use super::PlainCar;
-    use crate::utils::db::car_util::load_car;
+    use crate::utils::db::{car_stream::CarBlock, car_util::load_car};
+    use cid::Cid;
     use futures::executor::block_on;
     use fvm_ipld_blockstore::{Blockstore as _, MemoryBlockstore};
+    use integer_encoding::VarInt as _;
+    use std::io::{self, Read as _};
     use tokio::io::AsyncBufRead;
 
     #[test]
@@ -441,6 +629,86 @@ mod tests {
         }
     }
 
+    #[test]
+    fn write_v2_index_finds_blocks() {
+        let car = chain4_car();
+        let reference = reference(car);
+        let mut v2 = vec![];
+        super::write_v2(io::Cursor::new(car), &mut v2).unwrap();
+
+        assert_eq!(v2[..11], super::V2_PRAGMA);
+        let field = |at: usize| u64::from_le_bytes(v2[at..at + 8].try_into().unwrap()) as usize;
+        let (data_offset, data_size, index_offset) = (field(27), field(35), field(43));
+        assert_eq!(&v2[data_offset..][..data_size], car);
+
+        for cid in PlainCar::new(car).unwrap().cids() {
+            let frame = &v2[data_offset + lookup(&v2[index_offset..], &cid).unwrap()..];
+            let (body_length, varint_length) = usize::decode_var(frame).unwrap();
+            let block =
+                CarBlock::from_bytes(frame[varint_length..][..body_length].to_vec()).unwrap();
+            assert_eq!(block.cid, cid);
+            assert_eq!(block.data, reference.get(&cid).unwrap().unwrap());
+        }
+    }
+
+    #[test]
+    fn count_blocks_v1_and_v2() {
+        let car = chain4_car();
+        let blocks = super::count_v1_blocks(io::Cursor::new(car), car.len() as u64).unwrap();
+        // duplicate blocks are counted too
+        assert!(blocks >= PlainCar::new(car).unwrap().cids().len() as u64);
+        assert_eq!(super::V2Header::read(car).unwrap(), None);
+
+        let mut v2 = vec![];
+        super::write_v2(io::Cursor::new(car), &mut v2).unwrap();
+        let header = super::V2Header::read(v2.as_slice()).unwrap().unwrap();
+        let mut reader = io::Cursor::new(&v2);
+        reader.set_position(header.data_offset);
+        assert_eq!(
+            super::count_v1_blocks(&mut reader, header.data_size).unwrap(),
+            blocks
+        );
+        reader.set_position(header.index_offset);
+        assert_eq!(
+            super::count_v2_index_entries(&mut reader).unwrap(),
+            Some(blocks)
+        );
+    }
+
+    /// Returns the offset of the frame of `cid` in the payload, from a `MultihashIndexSorted`.
+    fn lookup(mut index: &[u8], cid: &Cid) -> Option<usize> {
+        fn read<const N: usize>(reader: &mut &[u8]) -> [u8; N] {
+            let mut buffer = [0; N];
+            reader.read_exact(&mut buffer).unwrap();
+            buffer
+        }
+        let (codec, varint_length) = u64::decode_var(index).unwrap();
+        assert_eq!(codec, 0x0401);
+        index = &index[varint_length..];
+        let (code, digest) = (cid.hash().code(), cid.hash().digest());
+        for _ in 0..i32::from_le_bytes(read(&mut index)) {
+            let entry_code = u64::from_le_bytes(read(&mut index));
+            for _ in 0..i32::from_le_bytes(read(&mut index)) {
+                let width = u32::from_le_bytes(read(&mut index)) as usize;
+                let length = i64::from_le_bytes(read(&mut index)) as usize;
+                let (entries, rest) = index.split_at(length);
+                index = rest;
+                if entry_code != code || width != digest.len() + 8 {
+                    continue;
+                }
+                // entries are sorted by digest
+                let entries = entries.chunks_exact(width).collect::<Vec<_>>();
+                let i = entries
+                    .binary_search_by(|entry| entry[..digest.len()].cmp(digest))
+                    .ok()?;
+                return Some(
+                    u64::from_le_bytes(entries[i][digest.len()..].try_into().unwrap()) as usize,
+                );
+            }
+        }
+        None
+    }
+
     fn reference(reader: impl AsyncBufRead + Unpin) -> MemoryBlockstore {
         let blockstore = MemoryBlockstore::new();
         block_on(load_car(&blockstore, reader)).unwrap();
@@ -1,6 +1,7 @@
 // Copyright 2019-2024 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+mod caching;
 pub mod car;
 mod memory;
 pub mod parity_db;
@@ -8,6 +9,7 @@ pub mod parity_db_config;
 
 mod gc;
 pub mod ttl;
+pub use caching::CachingBlockstore;
 pub use gc::MarkAndSweep;
 pub use memory::MemoryDB;
 use setting_keys::ETH_MAPPING_UP_TO_DATE_KEY;
@@ -4,9 +4,14 @@ use std::sync::Arc;
 
 use ahash::HashMap;
 use axum::extract::{self, Query};
+use axum::Json;
+use http::StatusCode;
 
 use crate::db::SettingsExt;
-use crate::{chain_sync::SyncStage, networks::calculate_expected_epoch};
+use crate::{
+    chain_sync::{SyncHealthStatus, SyncStage},
+    networks::calculate_expected_epoch,
+};
 
 use super::{AppError, ForestState};
 
@@ -85,6 +90,29 @@ pub(crate) async fn healthz(
     }
 }
 
+/// Reports the sync progress of the node as JSON, comparing its head against the epoch expected
+/// from the wall clock and the drand round that epoch corresponds to. Responds with
+/// `503 Service Unavailable` if the node lags more than [`SyncHealthStatus::MAX_LAG_EPOCHS`]
+/// behind.
+pub(crate) async fn sync_status(
+    extract::State(state): extract::State<Arc<ForestState>>,
+) -> Result<(StatusCode, Json<SyncHealthStatus>), AppError> {
+    let status = SyncHealthStatus::new(
+        state.sync_state.read().epoch(),
+        chrono::Utc::now().timestamp() as u64,
+        state.genesis_timestamp,
+        &state.chain_config,
+        &state.beacon,
+    )
+    .map_err(AppError)?;
+    let code = if status.synced {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok((code, Json(status)))
+}
+
 fn check_sync_state_complete(state: &ForestState, acc: &mut MessageAccumulator) -> bool {
     // Forest must be in sync with the network
     if state.sync_state.read().stage() == SyncStage::Complete {
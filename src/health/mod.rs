@@ -10,7 +10,8 @@ use axum::{
 use parking_lot::RwLock;
 
 use crate::{
-    chain_sync::SyncState, db::SettingsStore, libp2p::PeerManager, networks::ChainConfig, Config,
+    beacon::BeaconSchedule, chain_sync::SyncState, db::SettingsStore, libp2p::PeerManager,
+    networks::ChainConfig, Config,
 };
 
 mod endpoints;
@@ -23,6 +24,7 @@ pub(crate) struct ForestState {
     pub config: Config,
     pub chain_config: Arc<ChainConfig>,
     pub genesis_timestamp: u64,
+    pub beacon: Arc<BeaconSchedule>,
     pub sync_state: Arc<RwLock<SyncState>>,
     pub peer_manager: Arc<PeerManager>,
     pub settings_store: Arc<dyn SettingsStore + Sync + Send>,
@@ -33,8 +35,9 @@ pub(crate) struct ForestState {
 /// - `[endpoints::healthz]`
 /// - `[endpoints::readyz]`
 /// - `[endpoints::livez]`
+/// - `[endpoints::sync_status]`
 ///
-/// All endpoints accept an optional `verbose` query parameter. If present, the response will include detailed information about the checks performed.
+/// The probe endpoints accept an optional `verbose` query parameter. If present, the response will include detailed information about the checks performed.
 pub(crate) async fn init_healthcheck_server(
     forest_state: ForestState,
     tcp_listener: tokio::net::TcpListener,
@@ -43,6 +46,7 @@ pub(crate) async fn init_healthcheck_server(
         .route("/healthz", get(endpoints::healthz))
         .route("/readyz", get(endpoints::readyz))
         .route("/livez", get(endpoints::livez))
+        .route("/health/sync", get(endpoints::sync_status))
         .with_state(forest_state.into());
 
     axum::serve(tcp_listener, healthcheck_service).await?;
@@ -62,13 +66,24 @@ impl IntoResponse for AppError {
 mod test {
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
+    use crate::beacon::{mock_beacon::MockBeacon, BeaconPoint};
     use crate::db::SettingsExt;
-    use crate::{chain_sync::SyncStage, Client};
+    use crate::{
+        chain_sync::{SyncHealthStatus, SyncStage},
+        Client,
+    };
 
     use reqwest::StatusCode;
 
     use super::*;
 
+    fn mock_beacon_schedule() -> Arc<BeaconSchedule> {
+        Arc::new(BeaconSchedule(vec![BeaconPoint {
+            height: 0,
+            beacon: Box::<MockBeacon>::default(),
+        }]))
+    }
+
     #[tokio::test]
     async fn test_check_readyz() {
         let healthcheck_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
@@ -89,6 +104,7 @@ mod test {
             },
             chain_config: Arc::new(ChainConfig::default()),
             genesis_timestamp: 0,
+            beacon: mock_beacon_schedule(),
             sync_state: sync_state.clone(),
             peer_manager: Arc::new(PeerManager::default()),
             settings_store: db.clone(),
@@ -168,6 +184,7 @@ mod test {
             },
             chain_config: Arc::new(ChainConfig::default()),
             genesis_timestamp: 0,
+            beacon: mock_beacon_schedule(),
             sync_state: sync_state.clone(),
             peer_manager: peer_manager.clone(),
             settings_store: db,
@@ -244,6 +261,7 @@ mod test {
             },
             chain_config: Arc::new(ChainConfig::default()),
             genesis_timestamp: 0,
+            beacon: mock_beacon_schedule(),
             sync_state: sync_state.clone(),
             peer_manager: peer_manager.clone(),
             settings_store: db,
@@ -307,6 +325,59 @@ mod test {
         assert!(text.contains("[!] no peers connected"));
     }
 
+    #[tokio::test]
+    async fn test_sync_status() {
+        let healthcheck_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+        let sync_state = Arc::new(RwLock::new(SyncState::default()));
+        let forest_state = ForestState {
+            config: Config {
+                client: Client {
+                    healthcheck_address,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            chain_config: Arc::new(ChainConfig::default()),
+            genesis_timestamp: 0,
+            beacon: mock_beacon_schedule(),
+            sync_state: sync_state.clone(),
+            peer_manager: Arc::default(),
+            settings_store: Arc::new(crate::db::MemoryDB::default()),
+        };
+
+        let listener =
+            tokio::net::TcpListener::bind(forest_state.config.client.healthcheck_address)
+                .await
+                .unwrap();
+        let healthcheck_port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            init_healthcheck_server(forest_state, listener)
+                .await
+                .unwrap();
+        });
+
+        let call_sync_status =
+            || reqwest::get(format!("http://localhost:{}/health/sync", healthcheck_port));
+
+        // the node is still at genesis, far behind the wall clock
+        let response = call_sync_status().await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let status: SyncHealthStatus =
+            serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        assert!(!status.synced);
+        assert_eq!(status.actual_epoch, 0);
+
+        // instrument the state so that the node is caught up
+        sync_state.write().set_epoch(status.expected_epoch);
+
+        let response = call_sync_status().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let status: SyncHealthStatus =
+            serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        assert!(status.synced);
+    }
+
     #[tokio::test]
     async fn test_check_unknown_healthcheck_endpoint() {
         let healthcheck_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
@@ -320,6 +391,7 @@ mod test {
             },
             chain_config: Arc::default(),
             genesis_timestamp: 0,
+            beacon: mock_beacon_schedule(),
             sync_state: Arc::default(),
             peer_manager: Arc::default(),
             settings_store: Arc::new(crate::db::MemoryDB::default()),
@@ -103,7 +103,13 @@ pub mod doctest_private {
 #[doc(hidden)]
 pub mod benchmark_private {
     pub use crate::db::car::forest;
+    pub use crate::shim::actors::market;
     pub use crate::utils::cid;
+    pub use crate::utils::proofs_api;
+    pub mod gas {
+        pub use crate::rpc::gas::{gas_premium_from_samples, GasMeta, GasPremiumConfig};
+        pub use crate::shim::econ::TokenAmount;
+    }
 }
 
 /// These items are semver-exempt, and exist for forest author use only
@@ -117,6 +123,14 @@ pub mod interop_tests_private {
     pub mod libp2p_bitswap {
         pub use crate::libp2p_bitswap::*;
     }
+    pub mod lotus_json {
+        pub use crate::lotus_json::*;
+    }
+    pub mod rpc {
+        pub mod types {
+            pub use crate::rpc::types::*;
+        }
+    }
 }
 
 // These should be made private in https://github.com/ChainSafe/forest/issues/3013
@@ -15,11 +15,15 @@ const SIZE_LIMIT_HIGH: i64 = 30000;
 const PRUNE_COOLDOWN: Duration = Duration::from_secs(60); // 1 minute
 const REPLACE_BY_FEE_RATIO: f64 = 1.25;
 const GAS_LIMIT_OVERESTIMATION: f64 = 1.25;
+const BASE_FEE_EMA_ALPHA: f64 = 0.2;
+const GAS_PREMIUM_LOOKBACK_MULTIPLIER: u64 = 2;
+const MIN_GAS_PREMIUM: u64 = 100_000;
 
 /// Configuration available for the [`crate::message_pool::MessagePool`].
 ///
 /// [MessagePool]: crate::message_pool::MessagePool
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MpoolConfig {
     pub priority_addrs: Vec<Address>,
     pub size_limit_high: i64,
@@ -27,6 +31,16 @@ pub struct MpoolConfig {
     pub replace_by_fee_ratio: f64,
     pub prune_cooldown: Duration,
     pub gas_limit_overestimation: f64,
+    /// Decay factor of the moving average of the base fee that fee caps are estimated from, see
+    /// [`crate::rpc::gas::GasFeeCapConfig::alpha`].
+    pub base_fee_ema_alpha: f64,
+    /// Number of tipsets gas premiums are sampled from, as a multiple of the number of blocks
+    /// the message is expected to be included within, see
+    /// [`crate::rpc::gas::GasPremiumConfig::lookback_multiplier`].
+    pub gas_premium_lookback_multiplier: u64,
+    /// Gas premium, in attoFIL, estimated when there are no recent messages to sample premiums
+    /// from, see [`crate::rpc::gas::GasPremiumConfig::min_premium_atto_fil`].
+    pub min_gas_premium: u64,
 }
 
 impl Default for MpoolConfig {
@@ -38,6 +52,9 @@ impl Default for MpoolConfig {
             replace_by_fee_ratio: REPLACE_BY_FEE_RATIO,
             prune_cooldown: PRUNE_COOLDOWN,
             gas_limit_overestimation: GAS_LIMIT_OVERESTIMATION,
+            base_fee_ema_alpha: BASE_FEE_EMA_ALPHA,
+            gas_premium_lookback_multiplier: GAS_PREMIUM_LOOKBACK_MULTIPLIER,
+            min_gas_premium: MIN_GAS_PREMIUM,
         }
     }
 }
@@ -66,6 +66,35 @@ pub static RPC_METHOD_TIME: Lazy<Family<RpcMethodLabel, Histogram>> = Lazy::new(
     metric
 });
 
+pub static GAS_ESTIMATE_TIME: Lazy<Histogram> = Lazy::new(|| {
+    let metric = default_histogram();
+    default_registry().register(
+        "gas_estimate_histogram",
+        "Duration of gas estimations for a message, as in GasEstimateMessageGas",
+        metric.clone(),
+    );
+    metric
+});
+// Exposed as `gas_estimate_errors_total`, the encoder appends the suffix to counters.
+pub static GAS_ESTIMATE_ERRORS: Lazy<Counter> = Lazy::new(|| {
+    let metric = Counter::default();
+    default_registry().register(
+        "gas_estimate_errors",
+        "Number of failed gas estimations for a message",
+        metric.clone(),
+    );
+    metric
+});
+pub static GAS_ESTIMATE_TASKS_TIME: Lazy<Family<TypeLabel, Histogram>> = Lazy::new(|| {
+    let metric = Family::new_with_constructor(default_histogram as _);
+    default_registry().register(
+        "gas_estimate_tasks_time",
+        "Duration of the gas limit, gas premium and fee cap estimations",
+        metric.clone(),
+    );
+    metric
+});
+
 pub async fn init_prometheus<DB>(
     prometheus_listener: TcpListener,
     db_directory: PathBuf,
@@ -160,12 +189,17 @@ impl TypeLabel {
 }
 
 pub mod values {
-    use super::KindLabel;
+    use super::{KindLabel, TypeLabel};
 
     /// `TipsetCache`.
     pub const TIPSET: KindLabel = KindLabel::new("tipset");
     /// tipset cache in state manager
     pub const STATE_MANAGER_TIPSET: KindLabel = KindLabel::new("sm_tipset");
+
+    // gas estimation tasks
+    pub const ESTIMATE_GAS_LIMIT: TypeLabel = TypeLabel::new("estimate_gas_limit");
+    pub const ESTIMATE_GAS_PREMIUM: TypeLabel = TypeLabel::new("estimate_gas_premium");
+    pub const ESTIMATE_FEE_CAP: TypeLabel = TypeLabel::new("estimate_fee_cap");
 }
 
 pub fn default_histogram() -> Histogram {
@@ -71,7 +71,8 @@ mod tests {
     use url::Url;
 
     use super::*;
-    use crate::utils::{net::global_http_client, retry, RetryArgs};
+    use crate::utils::net::global_http_client;
+    use backon::{ExponentialBuilder, Retryable as _};
     use std::time::Duration;
 
     #[tokio::test]
@@ -92,23 +93,21 @@ mod tests {
 
     async fn test_drand<'a>(config: &'a DrandConfig<'a>) {
         let get_remote_chain_info = |server: &'a Url| async move {
-            retry(
-                RetryArgs {
-                    timeout: Some(Duration::from_secs(15)),
-                    ..Default::default()
-                },
-                || async {
-                    let remote_chain_info: ChainInfo = global_http_client()
-                        .get(server.join(&format!("{}/info", config.chain_info.hash))?)
-                        .send()
-                        .await?
-                        .error_for_status()?
-                        .json()
-                        .await?;
-                    anyhow::Ok(remote_chain_info)
-                },
+            let fetch = || async {
+                let remote_chain_info: ChainInfo = global_http_client()
+                    .get(server.join(&format!("{}/info", config.chain_info.hash))?)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                anyhow::Ok(remote_chain_info)
+            };
+            tokio::time::timeout(
+                Duration::from_secs(15),
+                fetch.retry(ExponentialBuilder::default()),
             )
-            .await
+            .await?
         };
 
         for server in &config.servers {
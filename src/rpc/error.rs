@@ -23,6 +23,9 @@ pub(crate) mod implementation_defined_errors {
     /// node. Note that it's not the same as not found, as we are explicitly not supporting it,
     /// e.g., because it's deprecated or Lotus is doing the same.
     pub(crate) const UNSUPPORTED_METHOD: i32 = -32001;
+    /// This error indicates that the message whose gas is being estimated failed to execute, see
+    /// [`crate::rpc::gas::GasEstimationError::MessageReverted`].
+    pub(crate) const MESSAGE_REVERTED: i32 = -32002;
 }
 
 impl ServerError {
@@ -1,29 +1,50 @@
 // Copyright 2019-2024 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Instant;
 
 use crate::blocks::Tipset;
-use crate::chain::{BASE_FEE_MAX_CHANGE_DENOM, BLOCK_GAS_TARGET};
+use crate::chain::index::ChainIndex;
+use crate::chain::{BASE_FEE_MAX_CHANGE_DENOM, BLOCK_GAS_TARGET, MINIMUM_BASE_FEE};
 use crate::interpreter::VMTrace;
+use crate::lotus_json::{lotus_json_with_self, LotusJson};
 use crate::message::{ChainMessage, Message as MessageTrait, SignedMessage};
-use crate::rpc::{error::ServerError, types::*, ApiPaths, Ctx, Permission, RpcMethod};
-use crate::shim::executor::ApplyRet;
+use crate::message_pool::MpoolConfig;
+use crate::metrics::{self, HistogramTimerExt as _, TypeLabel};
+use crate::rpc::{
+    error::{implementation_defined_errors, ServerError},
+    types::*,
+    ApiPaths, Ctx, Permission, RpcMethod,
+};
+use crate::shim::executor::{ApplyRet, Receipt};
 use crate::shim::{
-    address::{Address, Protocol},
+    address::{Address, Protocol, ZERO_ADDRESS},
     crypto::{Signature, SignatureType, SECP_SIG_LEN},
-    econ::{TokenAmount, BLOCK_GAS_LIMIT},
+    econ::{TokenAmount, BLOCK_GAS_LIMIT, TOTAL_FILECOIN},
+    error::ExitCode,
     message::Message,
 };
+use ahash::HashMap;
 use anyhow::{Context, Result};
+use futures::StreamExt as _;
 use fvm_ipld_blockstore::Blockstore;
 use num::BigInt;
-use num_traits::{FromPrimitive, Zero};
+use num_traits::{FromPrimitive, ToPrimitive as _, Zero};
 use rand_distr::{Distribution, Normal};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 use super::state::InvocResult;
 
-const MIN_GAS_PREMIUM: f64 = 100000.0;
+/// Number of blocks the fee cap allows the base fee to rise over at most. The base fee is
+/// unlikely to rise at its maximum rate for longer, so estimates beyond it are speculative.
+const MAX_QUEUE_BLKS: i64 = 20;
+/// Upper bound on the number of tipsets the base fee average is taken over.
+const MAX_BASE_FEE_SAMPLES: i64 = 900;
+/// Upper bound on the number of tipsets walked to sample gas premiums, empty ones included.
+const MAX_GAS_PREMIUM_LOOKBACK: usize = 900;
 
 /// Estimate the fee cap
 pub enum GasEstimateFeeCap {}
@@ -40,28 +61,246 @@ impl RpcMethod<3> for GasEstimateFeeCap {
         ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
         (msg, max_queue_blks, tsk): Self::Params,
     ) -> Result<Self::Ok, ServerError> {
-        estimate_fee_cap(&ctx, msg, max_queue_blks, tsk).map(|n| TokenAmount::to_string(&n))
+        estimate_fee_cap(
+            &ctx,
+            msg,
+            max_queue_blks,
+            None,
+            &GasFeeCapConfig::from(&ctx.mpool.config),
+            tsk,
+        )
+        .map(|n| TokenAmount::to_string(&n))
+    }
+}
+
+/// Like [`GasEstimateFeeCap`], but `max_queue_blocks` is capped at [`MAX_QUEUE_BLKS`], see
+/// [`capped_queue_blks`].
+pub enum ForestGasEstimateFeeCap {}
+impl RpcMethod<3> for ForestGasEstimateFeeCap {
+    const NAME: &'static str = "Forest.GasEstimateFeeCap";
+    const PARAM_NAMES: [&'static str; 3] = ["message", "max_queue_blocks", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Message, i64, ApiTipsetKey);
+    type Ok = String;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (msg, max_queue_blks, tsk): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let config = GasFeeCapConfig::from(&ctx.mpool.config);
+        let max_queue_blks = capped_queue_blks(max_queue_blks, &config)?;
+        estimate_fee_cap(&ctx, msg, max_queue_blks, None, &config, tsk)
+            .map(|n| TokenAmount::to_string(&n))
+    }
+}
+
+/// Returns the base fee of a tipset, and the highest it can be a few blocks later.
+pub enum GasGetBaseFee {}
+impl RpcMethod<1> for GasGetBaseFee {
+    const NAME: &'static str = "Forest.GasGetBaseFee";
+    const PARAM_NAMES: [&'static str; 1] = ["tsk"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (ApiTipsetKey,);
+    type Ok = GasBaseFee;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (tsk,): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk.0)?;
+        Ok(GasBaseFee::new(
+            ts.block_headers().first().parent_base_fee.clone(),
+        )?)
+    }
+}
+
+/// Result of [`GasGetBaseFee`]: the base fee of a tipset, and the highest it can be after 1, 5
+/// and 10 more blocks, as projected by [`estimate_fee_cap`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct GasBaseFee {
+    #[schemars(with = "LotusJson<TokenAmount>")]
+    #[serde(with = "crate::lotus_json")]
+    pub current: TokenAmount,
+    #[schemars(with = "LotusJson<TokenAmount>")]
+    #[serde(with = "crate::lotus_json")]
+    pub projected_1: TokenAmount,
+    #[schemars(with = "LotusJson<TokenAmount>")]
+    #[serde(with = "crate::lotus_json")]
+    pub projected_5: TokenAmount,
+    #[schemars(with = "LotusJson<TokenAmount>")]
+    #[serde(with = "crate::lotus_json")]
+    pub projected_10: TokenAmount,
+}
+
+lotus_json_with_self!(GasBaseFee);
+
+impl GasBaseFee {
+    fn new(current: TokenAmount) -> anyhow::Result<Self> {
+        let project =
+            |blocks| project_base_fee(&current, blocks).context("base fee projection overflows");
+        Ok(Self {
+            projected_1: project(1)?,
+            projected_5: project(5)?,
+            projected_10: project(10)?,
+            current,
+        })
+    }
+}
+
+/// Parameters of [`estimate_fee_cap`], taken from the [`MpoolConfig`] of the node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasFeeCapConfig {
+    /// Decay factor, in `0..=1`, of the exponential moving average of the base fee the fee cap
+    /// starts from. Higher values follow the latest base fee more closely.
+    pub alpha: f64,
+    /// Largest `max_queue_blks` accepted by [`capped_queue_blks`].
+    pub max_queue_blks_limit: i64,
+}
+
+impl Default for GasFeeCapConfig {
+    fn default() -> Self {
+        Self::from(&MpoolConfig::default())
+    }
+}
+
+impl From<&MpoolConfig> for GasFeeCapConfig {
+    fn from(config: &MpoolConfig) -> Self {
+        Self {
+            alpha: config.base_fee_ema_alpha,
+            max_queue_blks_limit: 100,
+        }
     }
 }
 
+/// Estimates the fee cap of `msg`. Like in Lotus, `max_queue_blks` is used as is, callers
+/// wanting to bound it go through [`capped_queue_blks`] first.
 fn estimate_fee_cap<DB: Blockstore>(
     data: &Ctx<DB>,
     msg: Message,
     max_queue_blks: i64,
-    _: ApiTipsetKey,
+    max_fee_cap: Option<&TokenAmount>,
+    config: &GasFeeCapConfig,
+    tsk: ApiTipsetKey,
 ) -> Result<TokenAmount, ServerError> {
-    let ts = data.chain_store().heaviest_tipset();
+    let _timer = TaskTimer::start(metrics::values::ESTIMATE_FEE_CAP);
+    let ts = data
+        .chain_store()
+        .load_required_tipset_or_heaviest(&tsk.0)?;
+    let base_fee = base_fee_estimate(data.chain_index(), ts, max_queue_blks, config)?;
+    Ok(compute_fee_cap(
+        &base_fee,
+        &msg.gas_premium(),
+        max_queue_blks,
+        max_fee_cap,
+    )?)
+}
+
+/// The base fee fee caps are projected from: the moving average of the base fees of the last
+/// `max_queue_blks` tipsets up to `ts`, see [`base_fee_ema`].
+fn base_fee_estimate<DB: Blockstore>(
+    chain_index: &ChainIndex<DB>,
+    ts: Arc<Tipset>,
+    max_queue_blks: i64,
+    config: &GasFeeCapConfig,
+) -> anyhow::Result<TokenAmount> {
+    let base_fees = recent_base_fees(chain_index, ts, max_queue_blks)?;
+    base_fee_ema(&base_fees, config.alpha).context("no base fee to estimate the fee cap from")
+}
+
+/// Caps `max_queue_blks` at [`MAX_QUEUE_BLKS`], with a warning as the caller asked for a
+/// speculative estimate. Fails above [`GasFeeCapConfig::max_queue_blks_limit`].
+fn capped_queue_blks(max_queue_blks: i64, config: &GasFeeCapConfig) -> anyhow::Result<i64> {
+    anyhow::ensure!(
+        max_queue_blks <= config.max_queue_blks_limit,
+        "max queue blocks {max_queue_blks} exceeds the limit of {}",
+        config.max_queue_blks_limit
+    );
+    if max_queue_blks > MAX_QUEUE_BLKS {
+        tracing::warn!("fee cap estimates over {max_queue_blks} blocks are speculative, using {MAX_QUEUE_BLKS} blocks instead");
+    }
+    Ok(max_queue_blks.min(MAX_QUEUE_BLKS))
+}
+
+/// Collects the base fees of the last `max_queue_blks` tipsets up to `ts`, oldest first.
+fn recent_base_fees<DB: Blockstore>(
+    chain_index: &ChainIndex<DB>,
+    mut ts: Arc<Tipset>,
+    max_queue_blks: i64,
+) -> anyhow::Result<Vec<TokenAmount>> {
+    let mut base_fees = vec![ts.block_headers().first().parent_base_fee.clone()];
+    for _ in 1..max_queue_blks.clamp(1, MAX_BASE_FEE_SAMPLES) {
+        if ts.epoch() == 0 {
+            break;
+        }
+        ts = chain_index.load_required_tipset(ts.parents())?;
+        base_fees.push(ts.block_headers().first().parent_base_fee.clone());
+    }
+    base_fees.reverse();
+    Ok(base_fees)
+}
 
-    let parent_base_fee = &ts.block_headers().first().parent_base_fee;
-    let increase_factor =
-        (1.0 + (BASE_FEE_MAX_CHANGE_DENOM as f64).recip()).powf(max_queue_blks as f64);
+/// Exponential moving average of `base_fees`, oldest first, rounded to the nearest attoFIL.
+fn base_fee_ema(base_fees: &[TokenAmount], alpha: f64) -> Option<TokenAmount> {
+    let alpha = alpha.clamp(0.0, 1.0);
+    let (first, rest) = base_fees.split_first()?;
+    let ema = rest.iter().fold(first.atto().to_f64()?, |ema, base_fee| {
+        let base_fee = base_fee.atto().to_f64().unwrap_or(ema);
+        alpha * base_fee + (1.0 - alpha) * ema
+    });
+    BigInt::from_f64(ema.round()).map(TokenAmount::from_atto)
+}
 
-    let fee_in_future = parent_base_fee
-        * BigInt::from_f64(increase_factor * (1 << 8) as f64)
-            .context("failed to convert fee_in_future f64 to bigint")?;
-    let mut out: crate::shim::econ::TokenAmount = fee_in_future.div_floor(1 << 8);
-    out += msg.gas_premium();
-    Ok(out)
+/// The base fee after it increased at its maximum rate for `blocks` blocks, or `None` if the
+/// increase factor overflows.
+fn project_base_fee(base_fee: &TokenAmount, blocks: i64) -> Option<TokenAmount> {
+    let increase_factor = (1.0 + (BASE_FEE_MAX_CHANGE_DENOM as f64).recip()).powf(blocks as f64);
+    BigInt::from_f64(increase_factor * (1 << 8) as f64)
+        .map(|factor| (base_fee * factor).div_floor(1 << 8))
+}
+
+/// Computes the fee cap needed for a message to still be includable after the base fee
+/// increased at its maximum rate for `max_queue_blks` blocks, clamped to `max_fee_cap`. Fails if
+/// `max_fee_cap` is below `gas_premium`, as the message would then be rejected. An unclamped fee
+/// cap below the premium is raised to the premium plus the current base fee.
+fn compute_fee_cap(
+    parent_base_fee: &TokenAmount,
+    gas_premium: &TokenAmount,
+    max_queue_blks: i64,
+    max_fee_cap: Option<&TokenAmount>,
+) -> anyhow::Result<TokenAmount> {
+    if let Some(max_fee_cap) = max_fee_cap {
+        anyhow::ensure!(
+            max_fee_cap >= gas_premium,
+            "maximum fee cap {max_fee_cap} is below the gas premium {gas_premium}"
+        );
+    }
+    // the factor overflows for extreme `max_queue_blks`, in which case only the clamp is usable
+    let fee_cap = project_base_fee(parent_base_fee, max_queue_blks).map(|mut out| {
+        out += gas_premium.clone();
+        out
+    });
+    let fee_cap = match (fee_cap, max_fee_cap) {
+        (Some(fee_cap), Some(max_fee_cap)) if &fee_cap > max_fee_cap => {
+            tracing::warn!("estimated fee cap {fee_cap} exceeds the maximum of {max_fee_cap}, clamping (max queue blocks: {max_queue_blks})");
+            return Ok(max_fee_cap.clone());
+        }
+        (Some(fee_cap), _) => fee_cap,
+        (None, Some(max_fee_cap)) => {
+            tracing::warn!("estimated fee cap overflows, clamping to the maximum of {max_fee_cap} (max queue blocks: {max_queue_blks})");
+            return Ok(max_fee_cap.clone());
+        }
+        (None, None) => anyhow::bail!("failed to convert fee_in_future f64 to bigint"),
+    };
+    if &fee_cap < gas_premium {
+        let corrected = gas_premium + parent_base_fee;
+        tracing::debug!("estimated fee cap {fee_cap} is below the gas premium {gas_premium}, raising it to {corrected}");
+        return Ok(corrected);
+    }
+    Ok(fee_cap)
 }
 
 /// Estimate the fee cap
@@ -85,82 +324,350 @@ impl RpcMethod<4> for GasEstimateGasPremium {
     }
 }
 
-pub async fn estimate_gas_premium<DB: Blockstore>(
-    data: &Ctx<DB>,
-    mut nblocksincl: u64,
-) -> Result<TokenAmount, ServerError> {
-    if nblocksincl == 0 {
-        nblocksincl = 1;
-    }
+/// Estimates the gas premium as a weighted percentile of the recently paid premiums, see
+/// [`estimate_gas_premium_percentile`].
+pub enum GasEstimateGasPremiumPercentile {}
+impl RpcMethod<3> for GasEstimateGasPremiumPercentile {
+    const NAME: &'static str = "Forest.GasEstimateGasPremiumPercentile";
+    const PARAM_NAMES: [&'static str; 3] = ["nblocksincl", "percentile", "weighting"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
 
-    struct GasMeta {
-        pub price: TokenAmount,
-        pub limit: u64,
+    type Params = (u64, u64, GasPremiumWeighting);
+    type Ok = String;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (nblocksincl, percentile, weighting): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let percentile = u8::try_from(percentile)
+            .ok()
+            .filter(|percentile| *percentile <= 100)
+            .with_context(|| format!("percentile {percentile} is not in 0..=100"))?;
+        let config = GasPremiumConfig {
+            nblocksincl,
+            percentile,
+            weighting,
+            ..GasPremiumConfig::from(&ctx.mpool.config)
+        };
+        estimate_gas_premium_percentile(&ctx, &config)
+            .await
+            .map(|n| TokenAmount::to_string(&n))
     }
+}
 
-    let mut prices: Vec<GasMeta> = Vec::new();
-    let mut blocks = 0;
+/// Premium and gas limit of a message sampled for gas premium estimation.
+pub struct GasMeta {
+    pub price: TokenAmount,
+    pub limit: u64,
+    /// Fill ratio of the blocks the message was included in, see [`block_fill_ratio`].
+    pub fill_ratio: f64,
+}
 
-    let mut ts = data.chain_store().heaviest_tipset();
+/// Share of the gas limit of `blocks` blocks taken by messages with the given total gas limit,
+/// in `0.0..=1.0`. The gas limits are used as the gas actually used isn't known without the
+/// receipts.
+fn block_fill_ratio(total_gas_limit: u64, blocks: usize) -> f64 {
+    let capacity = BLOCK_GAS_LIMIT as f64 * blocks as f64;
+    if capacity > 0.0 {
+        (total_gas_limit as f64 / capacity).clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
 
-    for _ in 0..(nblocksincl * 2) {
+/// Collects the premiums and gas limits of the messages included in the last
+/// `nblocksincl * lookback_multiplier` tipsets with messages, along with the number of blocks
+/// they span. Empty tipsets are skipped, up to [`MAX_GAS_PREMIUM_LOOKBACK`] tipsets in total.
+fn recent_gas_premiums<DB: Blockstore>(
+    data: &Ctx<DB>,
+    nblocksincl: u64,
+    lookback_multiplier: u64,
+) -> anyhow::Result<(Vec<GasMeta>, usize)> {
+    let mut ts = data.chain_store().heaviest_tipset();
+    let tipsets = std::iter::from_fn(|| {
         if ts.epoch() == 0 {
-            break;
+            return None;
         }
-        let pts = data.chain_index().load_required_tipset(ts.parents())?;
-        blocks += pts.block_headers().len();
-        let msgs = crate::chain::messages_for_tipset(data.store_owned(), &pts)?;
-
-        prices.append(
-            &mut msgs
+        let sampled = (|| {
+            let pts = data.chain_index().load_required_tipset(ts.parents())?;
+            let msgs = data
+                .message_cache
+                .messages_for_tipset(data.store_owned(), &pts)?;
+            let fill_ratio = block_fill_ratio(
+                msgs.iter().map(|msg| msg.message().gas_limit()).sum(),
+                pts.block_headers().len(),
+            );
+            let prices = msgs
                 .iter()
                 .map(|msg| GasMeta {
                     price: msg.message().gas_premium(),
                     limit: msg.message().gas_limit(),
+                    fill_ratio,
                 })
-                .collect(),
-        );
-        ts = pts;
+                .collect();
+            let blocks = pts.block_headers().len();
+            ts = pts;
+            anyhow::Ok((blocks, prices))
+        })();
+        Some(sampled)
+    });
+    sample_gas_premiums(tipsets, nblocksincl.saturating_mul(lookback_multiplier))
+}
+
+/// Collects the samples of up to `lookback` non-empty `(blocks, samples)` tipsets, walking at
+/// most [`MAX_GAS_PREMIUM_LOOKBACK`] tipsets.
+fn sample_gas_premiums(
+    tipsets: impl IntoIterator<Item = anyhow::Result<(usize, Vec<GasMeta>)>>,
+    lookback: u64,
+) -> anyhow::Result<(Vec<GasMeta>, usize)> {
+    let mut prices = Vec::new();
+    let mut blocks = 0;
+    let mut sampled = 0;
+    for tipset in tipsets.into_iter().take(MAX_GAS_PREMIUM_LOOKBACK) {
+        if sampled >= lookback {
+            break;
+        }
+        let (tipset_blocks, mut tipset_prices) = tipset?;
+        if tipset_prices.is_empty() {
+            continue;
+        }
+        prices.append(&mut tipset_prices);
+        blocks += tipset_blocks;
+        sampled += 1;
     }
+    Ok((prices, blocks))
+}
 
-    prices.sort_by(|a, b| b.price.cmp(&a.price));
-    let mut at = BLOCK_GAS_TARGET * blocks as u64 / 2;
-    let mut prev = TokenAmount::zero();
-    let mut premium = TokenAmount::zero();
+/// Premium to fall back to when there are no samples, scaled up for urgent inclusion.
+fn min_gas_premium(nblocksincl: u64, min_premium_atto_fil: u64) -> TokenAmount {
+    let min_premium = min_premium_atto_fil as f64;
+    TokenAmount::from_atto(match nblocksincl {
+        1 => (min_premium * 2.0) as u64,
+        2 => (min_premium * 1.5) as u64,
+        _ => min_premium as u64,
+    })
+}
 
-    for price in prices {
-        at -= price.limit;
-        if at > 0 {
-            prev = price.price;
-            continue;
+/// Multiplies the premium by normally distributed noise with mean 1, so that estimates made at
+/// the same time don't collide.
+fn apply_gas_premium_noise(premium: TokenAmount, stddev: f64) -> anyhow::Result<TokenAmount> {
+    let precision = 32;
+
+    let noise: f64 = Normal::new(1.0, stddev)?.sample(&mut rand::thread_rng());
+
+    let premium = premium
+        * BigInt::from_f64(noise * (1i64 << precision) as f64)
+            .context("failed to convert gas premium f64 to bigint")?;
+    Ok(premium.div_floor(1i64 << precision))
+}
+
+pub async fn estimate_gas_premium<DB: Blockstore>(
+    data: &Ctx<DB>,
+    mut nblocksincl: u64,
+) -> Result<TokenAmount, ServerError> {
+    let _timer = TaskTimer::start(metrics::values::ESTIMATE_GAS_PREMIUM);
+    if nblocksincl == 0 {
+        nblocksincl = 1;
+    }
+
+    let config = GasPremiumConfig::from(&data.mpool.config);
+    let (prices, blocks) = recent_gas_premiums(data, nblocksincl, config.lookback_multiplier)?;
+    Ok(gas_premium_from_samples(
+        &prices,
+        blocks,
+        nblocksincl,
+        &config,
+    )?)
+}
+
+/// Computes the premium of [`estimate_gas_premium`] from the samples returned by
+/// [`recent_gas_premiums`]. Only the noise and the minimum premium are taken from `config`.
+pub fn gas_premium_from_samples(
+    prices: &[GasMeta],
+    blocks: usize,
+    nblocksincl: u64,
+    config: &GasPremiumConfig,
+) -> anyhow::Result<TokenAmount> {
+    let premium = match gas_premium_crossing(prices, BLOCK_GAS_TARGET * blocks as u64 / 2) {
+        Some((price, Some(prev))) if !prev.price.is_zero() => {
+            (&price.price + &prev.price).div_floor(2) + TokenAmount::from_atto(1)
         }
-        if prev == TokenAmount::zero() {
-            let ret: TokenAmount = price.price + TokenAmount::from_atto(1);
-            return Ok(ret);
+        Some((price, _)) => return Ok(&price.price + TokenAmount::from_atto(1)),
+        None => {
+            if prices.is_empty() {
+                tracing::warn!(
+                    "No recent messages to sample gas premiums from, estimating the minimum premium"
+                );
+            }
+            min_gas_premium(nblocksincl, config.min_premium_atto_fil)
+        }
+    };
+
+    apply_gas_premium_noise(premium, config.noise_stddev)
+}
+
+/// Finds the message at which the gas limits of the samples, ordered by decreasing premium,
+/// add up to `target`, along with the message right before it in that order. Ties are ordered
+/// as in `prices`, as a stable sort would.
+///
+/// This is a weighted quickselect, running in linear time on average instead of sorting all
+/// samples.
+fn gas_premium_crossing(
+    prices: &[GasMeta],
+    mut target: u64,
+) -> Option<(&GasMeta, Option<&GasMeta>)> {
+    let by_premium = |(a_idx, a): &(usize, &GasMeta), (b_idx, b): &(usize, &GasMeta)| {
+        b.price.cmp(&a.price).then_with(|| a_idx.cmp(b_idx))
+    };
+    let mut samples: Vec<_> = prices.iter().enumerate().collect();
+    // the samples left to search, preceded in the order by `prev` and the samples before it
+    let mut window = samples.as_mut_slice();
+    let mut prev = None;
+    while !window.is_empty() {
+        let mid = window.len() / 2;
+        let (before, nth, after) =
+            std::mem::take(&mut window).select_nth_unstable_by(mid, by_premium);
+        let before_limit: u64 = before.iter().map(|(_, price)| price.limit).sum();
+        let through = before_limit + nth.1.limit;
+        if !before.is_empty() && before_limit >= target {
+            window = before;
+        } else if through >= target {
+            let prev = before
+                .iter()
+                .max_by(|a, b| by_premium(a, b))
+                .or(prev.as_ref());
+            return Some((nth.1, prev.map(|(_, price)| *price)));
+        } else {
+            target -= through;
+            prev = Some(*nth);
+            window = after;
         }
-        premium = (&price.price + &prev).div_floor(2) + TokenAmount::from_atto(1)
     }
+    None
+}
+
+/// How the premiums sampled by [`estimate_gas_premium_percentile`] are weighted. Except with
+/// [`GasPremiumWeighting::MessageCount`], this is in addition to the gas limits of their messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub enum GasPremiumWeighting {
+    /// All blocks count the same.
+    #[default]
+    Uniform,
+    /// Premiums paid in congested blocks count more, by a factor of `1 / (1 - fill_ratio)`, as
+    /// the messages of nearly empty blocks aren't representative of the demand for block space.
+    FillWeighted,
+    /// Every message counts the same, whatever its gas limit, for callers who care about the odds
+    /// of inclusion of their message rather than about the share of block space paid for.
+    MessageCount,
+}
+
+lotus_json_with_self!(GasPremiumWeighting);
 
-    if premium == TokenAmount::zero() {
-        premium = TokenAmount::from_atto(match nblocksincl {
-            1 => (MIN_GAS_PREMIUM * 2.0) as u64,
-            2 => (MIN_GAS_PREMIUM * 1.5) as u64,
-            _ => MIN_GAS_PREMIUM as u64,
-        });
+impl GasPremiumWeighting {
+    /// Upper bound of the fill ratio used for weighting, so that the weight of full blocks stays
+    /// finite.
+    const MAX_FILL_RATIO: f64 = 0.99;
+
+    fn weight(self, fill_ratio: f64) -> f64 {
+        match self {
+            Self::Uniform | Self::MessageCount => 1.0,
+            Self::FillWeighted => 1.0 / (1.0 - fill_ratio.clamp(0.0, Self::MAX_FILL_RATIO)),
+        }
     }
+}
 
-    let precision = 32;
+/// Parameters of [`estimate_gas_premium_percentile`]. `lookback_multiplier` and
+/// `min_premium_atto_fil` are taken from the [`MpoolConfig`] of the node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasPremiumConfig {
+    /// Number of blocks the message is expected to be included within.
+    pub nblocksincl: u64,
+    /// Premiums are sampled from `nblocksincl` times this many tipsets. Tipsets without
+    /// messages don't count.
+    pub lookback_multiplier: u64,
+    /// Percentile, in `0..=100`, of the sampled premiums, weighted as set by `weighting`.
+    pub percentile: u8,
+    /// Standard deviation of the noise the premium is multiplied by.
+    pub noise_stddev: f64,
+    /// Weighting of the sampled premiums.
+    pub weighting: GasPremiumWeighting,
+    /// Premium, in attoFIL, estimated when there are no samples, e.g. on a freshly started node.
+    /// Doubled for `nblocksincl == 1` and increased by half for `nblocksincl == 2`.
+    pub min_premium_atto_fil: u64,
+}
+
+impl Default for GasPremiumConfig {
+    fn default() -> Self {
+        Self::from(&MpoolConfig::default())
+    }
+}
 
-    // mean 1, stddev 0.005 => 95% within +-1%
-    let noise: f64 = Normal::new(1.0, 0.005)
-        .unwrap()
-        .sample(&mut rand::thread_rng());
+impl From<&MpoolConfig> for GasPremiumConfig {
+    fn from(config: &MpoolConfig) -> Self {
+        Self {
+            nblocksincl: 10,
+            lookback_multiplier: config.gas_premium_lookback_multiplier,
+            percentile: 60,
+            // mean 1, stddev 0.005 => 95% within +-1%
+            noise_stddev: 0.005,
+            weighting: GasPremiumWeighting::Uniform,
+            min_premium_atto_fil: config.min_gas_premium,
+        }
+    }
+}
 
-    premium *= BigInt::from_f64(noise * (1i64 << precision) as f64)
-        .context("failed to convert gas premium f64 to bigint")?;
-    premium = premium.div_floor(1i64 << precision);
+/// Estimates the gas premium as a percentile of the recently paid premiums, weighing each one by
+/// the gas limit of its message. Unlike [`estimate_gas_premium`], this does not depend on how
+/// full the sampled blocks were.
+pub async fn estimate_gas_premium_percentile<DB: Blockstore>(
+    data: &Ctx<DB>,
+    config: &GasPremiumConfig,
+) -> Result<TokenAmount, ServerError> {
+    let nblocksincl = config.nblocksincl.max(1);
+    let (prices, _) = recent_gas_premiums(data, nblocksincl, config.lookback_multiplier)?;
+    let prices = apply_weighting(prices, config.weighting);
+    let premium = weighted_percentile(prices, config.percentile).unwrap_or_else(|| {
+        tracing::warn!(
+            "No recent messages to sample gas premiums from, estimating the minimum premium"
+        );
+        min_gas_premium(nblocksincl, config.min_premium_atto_fil)
+    });
+    Ok(apply_gas_premium_noise(premium, config.noise_stddev)?)
+}
 
-    Ok(premium)
+/// Scales the gas limit of each sample by its [`GasPremiumWeighting::weight`], or sets it to 1 to
+/// count messages.
+fn apply_weighting(prices: Vec<GasMeta>, weighting: GasPremiumWeighting) -> Vec<GasMeta> {
+    prices
+        .into_iter()
+        .map(|price| GasMeta {
+            limit: match weighting {
+                GasPremiumWeighting::MessageCount => 1,
+                _ => (price.limit as f64 * weighting.weight(price.fill_ratio)) as u64,
+            },
+            ..price
+        })
+        .collect()
+}
+
+/// Returns the smallest price such that at least `percentile` percent of the total gas limit is
+/// priced at or below it, or `None` if there is no gas at all.
+fn weighted_percentile(mut prices: Vec<GasMeta>, percentile: u8) -> Option<TokenAmount> {
+    let total: u128 = prices.iter().map(|price| price.limit as u128).sum();
+    if total == 0 {
+        return None;
+    }
+    let target = total * u128::from(percentile.min(100));
+    prices.sort_by(|a, b| a.price.cmp(&b.price));
+    let mut cumulative = 0u128;
+    for price in prices {
+        cumulative += price.limit as u128;
+        if cumulative * 100 >= target {
+            return Some(price.price);
+        }
+    }
+    None
 }
 
 pub enum GasEstimateGasLimit {}
@@ -184,33 +691,73 @@ impl RpcMethod<2> for GasEstimateGasLimit {
 impl GasEstimateGasLimit {
     pub async fn estimate_call_with_gas<DB>(
         data: &Ctx<DB>,
-        mut msg: Message,
+        msg: Message,
         ApiTipsetKey(tsk): &ApiTipsetKey,
         trace_config: VMTrace,
     ) -> anyhow::Result<(InvocResult, ApplyRet, Vec<ChainMessage>, Arc<Tipset>)>
     where
         DB: Blockstore + Send + Sync + 'static,
     {
-        msg.set_gas_limit(BLOCK_GAS_LIMIT);
-        msg.set_gas_fee_cap(TokenAmount::from_atto(0));
-        msg.set_gas_premium(TokenAmount::from_atto(0));
-
         let curr_ts = data.chain_store().load_required_tipset_or_heaviest(tsk)?;
         let from_a = data
             .state_manager
             .resolve_to_key_addr(&msg.from, &curr_ts)
             .await?;
+        let prior_messages = Self::prior_messages(data, &from_a);
+        let (invoc_res, apply_ret, ts) =
+            Self::call_with_gas_after(data, msg, &from_a, &prior_messages, trace_config).await?;
+        Ok((invoc_res, apply_ret, prior_messages, ts))
+    }
 
-        let pending = data.mpool.pending_for(&from_a);
-        let prior_messages: Vec<ChainMessage> = pending
+    /// Pending messages of `from_a` in the message pool, which are applied before the estimated
+    /// message.
+    fn prior_messages<DB>(data: &Ctx<DB>, from_a: &Address) -> Vec<ChainMessage>
+    where
+        DB: Blockstore + Send + Sync + 'static,
+    {
+        data.mpool
+            .pending_for(from_a)
             .map(|s| s.into_iter().map(ChainMessage::Signed).collect::<Vec<_>>())
-            .unwrap_or_default();
+            .unwrap_or_default()
+    }
+
+    /// Executes `msg`, sent from the key address `from_a`, with the maximum gas limit on top of
+    /// `prior_messages` and the current message pool tipset.
+    async fn call_with_gas_after<DB>(
+        data: &Ctx<DB>,
+        mut msg: Message,
+        from_a: &Address,
+        prior_messages: &[ChainMessage],
+        trace_config: VMTrace,
+    ) -> anyhow::Result<(InvocResult, ApplyRet, Arc<Tipset>)>
+    where
+        DB: Blockstore + Send + Sync + 'static,
+    {
+        msg.set_gas_limit(BLOCK_GAS_LIMIT);
+        msg.set_gas_fee_cap(TokenAmount::from_atto(0));
+        msg.set_gas_premium(TokenAmount::from_atto(0));
 
         let ts = data.mpool.cur_tipset.lock().clone();
+        let mut chain_msg = Self::pretend_signed(msg, from_a);
+        let (invoc_res, apply_ret) = data
+            .state_manager
+            .call_with_gas(
+                &mut chain_msg,
+                prior_messages,
+                Some(ts.clone()),
+                trace_config,
+            )
+            .await?;
+        Ok((invoc_res, apply_ret, ts))
+    }
+
+    /// Wraps `msg`, sent from the key address `from_a`, in a [`ChainMessage`] as it would be
+    /// signed.
+    fn pretend_signed(msg: Message, from_a: &Address) -> ChainMessage {
         // Pretend that the message is signed. This has an influence on the gas
         // cost. We obviously can't generate a valid signature. Instead, we just
         // fill the signature with zeros. The validity is not checked.
-        let mut chain_msg = match from_a.protocol() {
+        match from_a.protocol() {
             Protocol::Secp256k1 => ChainMessage::Signed(SignedMessage::new_unchecked(
                 msg,
                 Signature::new_secp256k1(vec![0; SECP_SIG_LEN]),
@@ -222,18 +769,7 @@ impl GasEstimateGasLimit {
                 Signature::new(SignatureType::Delegated, vec![0; SECP_SIG_LEN]),
             )),
             _ => ChainMessage::Unsigned(msg),
-        };
-
-        let (invoc_res, apply_ret) = data
-            .state_manager
-            .call_with_gas(
-                &mut chain_msg,
-                &prior_messages,
-                Some(ts.clone()),
-                trace_config,
-            )
-            .await?;
-        Ok((invoc_res, apply_ret, prior_messages, ts))
+        }
     }
 
     pub async fn estimate_gas_limit<DB>(
@@ -244,19 +780,83 @@ impl GasEstimateGasLimit {
     where
         DB: Blockstore + Send + Sync + 'static,
     {
+        let _timer = TaskTimer::start(metrics::values::ESTIMATE_GAS_LIMIT);
         let (res, ..) = Self::estimate_call_with_gas(data, msg, tsk, VMTrace::NotTraced).await?;
-        match res.msg_rct {
-            Some(rct) => {
-                if rct.exit_code().value() != 0 {
-                    return Ok(-1);
-                }
-                Ok(rct.gas_used() as i64)
-            }
-            None => Ok(-1),
+        Ok(gas_used(res.msg_rct.as_ref()).map_or(-1, |gas_used| gas_used as i64))
+    }
+}
+
+/// Gas estimation failures that are caused by the message itself rather than by the node.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum GasEstimationError {
+    /// The message execution failed, so its gas usage can't be estimated.
+    #[error("message execution failed: exit {}", exit_code.value())]
+    MessageReverted { exit_code: ExitCode },
+    #[error("message execution produced no receipt")]
+    MissingReceipt,
+    /// The message is malformed, see [`validate_message`].
+    #[error("invalid message: {0}")]
+    InvalidMessage(String),
+}
+
+impl From<GasEstimationError> for ServerError {
+    fn from(e: GasEstimationError) -> Self {
+        match &e {
+            GasEstimationError::MessageReverted { exit_code } => ServerError::new(
+                implementation_defined_errors::MESSAGE_REVERTED,
+                &e,
+                serde_json::json!({ "ExitCode": exit_code.value() }),
+            ),
+            GasEstimationError::MissingReceipt => ServerError::internal_error(e, None),
+            GasEstimationError::InvalidMessage(_) => ServerError::invalid_params(e, None),
         }
     }
 }
 
+/// Returns the gas used by a successfully executed message.
+fn gas_used(receipt: Option<&Receipt>) -> Result<u64, GasEstimationError> {
+    let receipt = receipt.ok_or(GasEstimationError::MissingReceipt)?;
+    let exit_code = ExitCode::from(receipt.exit_code());
+    if !exit_code.is_success() {
+        return Err(GasEstimationError::MessageReverted { exit_code });
+    }
+    Ok(receipt.gas_used())
+}
+
+/// Checks the fields of a message to be estimated that don't depend on the chain state, so that
+/// malformed messages are rejected before they reach the VM.
+fn validate_message(msg: &Message) -> Result<(), GasEstimationError> {
+    let invalid = |reason: String| Err(GasEstimationError::InvalidMessage(reason));
+    if msg.version != 0 {
+        return invalid(format!("message version {} not supported", msg.version));
+    }
+    if msg.from == *ZERO_ADDRESS {
+        return invalid("'from' address is the zero address".into());
+    }
+    if msg.to == *ZERO_ADDRESS {
+        return invalid("'to' address is the zero address".into());
+    }
+    if msg.value.is_negative() {
+        return invalid("value cannot be negative".into());
+    }
+    if msg.value > *TOTAL_FILECOIN {
+        return invalid("value cannot be greater than total FIL supply".into());
+    }
+    if msg.gas_fee_cap.is_negative() {
+        return invalid("gas_fee_cap cannot be negative".into());
+    }
+    if msg.gas_premium.is_negative() {
+        return invalid("gas_premium cannot be negative".into());
+    }
+    if msg.gas_limit > BLOCK_GAS_LIMIT {
+        return invalid(format!(
+            "gas_limit {} cannot be greater than block gas limit",
+            msg.gas_limit
+        ));
+    }
+    Ok(())
+}
+
 /// Estimates the gas parameters for a given message
 pub enum GasEstimateMessageGas {}
 impl RpcMethod<3> for GasEstimateMessageGas {
@@ -277,26 +877,952 @@ impl RpcMethod<3> for GasEstimateMessageGas {
 }
 
 pub async fn estimate_message_gas<DB>(
+    data: &Ctx<DB>,
+    msg: Message,
+    spec: Option<MessageSendSpec>,
+    tsk: ApiTipsetKey,
+) -> Result<Message, ServerError>
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    record_gas_estimate(estimate_message_gas_inner(data, msg, spec, tsk)).await
+}
+
+async fn estimate_message_gas_inner<DB>(
     data: &Ctx<DB>,
     mut msg: Message,
-    _spec: Option<MessageSendSpec>,
+    spec: Option<MessageSendSpec>,
     tsk: ApiTipsetKey,
 ) -> Result<Message, ServerError>
 where
     DB: Blockstore + Send + Sync + 'static,
 {
+    validate_message(&msg)?;
     if msg.gas_limit == 0 {
-        let gl = GasEstimateGasLimit::estimate_gas_limit(data, msg.clone(), &tsk).await?;
-        let gl = gl as f64 * data.mpool.config.gas_limit_overestimation;
-        msg.set_gas_limit((gl as u64).min(BLOCK_GAS_LIMIT));
+        let _timer = TaskTimer::start(metrics::values::ESTIMATE_GAS_LIMIT);
+        let (res, ..) = GasEstimateGasLimit::estimate_call_with_gas(
+            data,
+            msg.clone(),
+            &tsk,
+            VMTrace::NotTraced,
+        )
+        .await?;
+        let gl = gas_used(res.msg_rct.as_ref())?;
+        msg.set_gas_limit(overestimate_gas_limit(
+            gl as i64,
+            data.mpool.config.gas_limit_overestimation,
+        ));
     }
     if msg.gas_premium.is_zero() {
         let gp = estimate_gas_premium(data, 10).await?;
         msg.set_gas_premium(gp);
     }
     if msg.gas_fee_cap.is_zero() {
-        let gfp = estimate_fee_cap(data, msg.clone(), 20, tsk)?;
+        let max_fee_cap = spec.as_ref().and_then(|spec| spec.max_fee_cap.as_ref());
+        let gfp = estimate_fee_cap(
+            data,
+            msg.clone(),
+            MAX_QUEUE_BLKS,
+            max_fee_cap,
+            &GasFeeCapConfig::from(&data.mpool.config),
+            tsk,
+        )?;
+        msg.set_gas_fee_cap(gfp);
+    }
+    if let Some(spec) = &spec {
+        cap_gas_fee(&mut msg, &spec.max_fee)?;
+    }
+    Ok(msg)
+}
+
+/// Records the duration of a gas estimation for a message in [`metrics::GAS_ESTIMATE_TIME`], and
+/// its failure in [`metrics::GAS_ESTIMATE_ERRORS`].
+async fn record_gas_estimate<T>(
+    estimate: impl Future<Output = Result<T, ServerError>>,
+) -> Result<T, ServerError> {
+    let _timer = metrics::GAS_ESTIMATE_TIME.start_timer();
+    let result = estimate.await;
+    if result.is_err() {
+        metrics::GAS_ESTIMATE_ERRORS.inc();
+    }
+    result
+}
+
+/// Records the duration of a gas estimation task in [`metrics::GAS_ESTIMATE_TASKS_TIME`] when
+/// dropped. Unlike [`metrics::HistogramTimer`], the histogram is only looked up then, as holding
+/// it would lock the metric family, possibly across `.await`s.
+struct TaskTimer {
+    task: TypeLabel,
+    start: Instant,
+}
+
+impl TaskTimer {
+    fn start(task: TypeLabel) -> Self {
+        Self {
+            task,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for TaskTimer {
+    fn drop(&mut self) {
+        metrics::GAS_ESTIMATE_TASKS_TIME
+            .get_or_create(&self.task)
+            .observe(self.start.elapsed().as_secs_f64());
+    }
+}
+
+/// Lowers the fee cap of `msg` so that the most it can pay, `gas_fee_cap * gas_limit`, doesn't
+/// exceed `max_fee`, like Lotus' `capGasFee`. The premium is lowered along with the fee cap if
+/// needed. A zero `max_fee` means no limit.
+fn cap_gas_fee(msg: &mut Message, max_fee: &TokenAmount) -> anyhow::Result<()> {
+    if max_fee.is_zero() || msg.gas_limit == 0 || &msg.gas_fee_cap * msg.gas_limit <= *max_fee {
+        return Ok(());
+    }
+    let fee_cap = max_fee.div_floor(msg.gas_limit);
+    anyhow::ensure!(
+        fee_cap.atto() >= &MINIMUM_BASE_FEE.into(),
+        "max fee {max_fee} is too low for a gas limit of {}: the fee cap would be {fee_cap}, below the minimum base fee of {MINIMUM_BASE_FEE} attoFIL",
+        msg.gas_limit
+    );
+    if msg.gas_premium > fee_cap {
+        msg.set_gas_premium(fee_cap.clone());
+    }
+    msg.set_gas_fee_cap(fee_cap);
+    Ok(())
+}
+
+/// Maximum number of senders of a [`GasEstimateMessageGasBatch`] estimated at the same time.
+const GAS_ESTIMATE_BATCH_CONCURRENCY: usize = 8;
+
+/// Estimates the gas parameters for many messages at once, see [`estimate_messages_gas`].
+pub enum GasEstimateMessageGasBatch {}
+impl RpcMethod<3> for GasEstimateMessageGasBatch {
+    const NAME: &'static str = "Forest.GasEstimateMessageGasBatch";
+    const PARAM_NAMES: [&'static str; 3] = ["msgs", "spec", "tsk"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Vec<Message>, Option<MessageSendSpec>, ApiTipsetKey);
+    type Ok = Vec<GasEstimateBatchResult>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (msgs, spec, tsk): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        Ok(estimate_messages_gas(&ctx, msgs, spec, tsk)
+            .await?
+            .into_iter()
+            .map(GasEstimateBatchResult::from)
+            .collect())
+    }
+}
+
+/// Outcome of the gas estimation of one message of a [`GasEstimateMessageGasBatch`]. Exactly one
+/// of the fields is set.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct GasEstimateBatchResult {
+    #[schemars(with = "LotusJson<Option<Message>>")]
+    #[serde(
+        with = "crate::lotus_json",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub message: Option<Message>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+lotus_json_with_self!(GasEstimateBatchResult);
+
+impl From<Result<Message, ServerError>> for GasEstimateBatchResult {
+    fn from(result: Result<Message, ServerError>) -> Self {
+        match result {
+            Ok(message) => Self {
+                message: Some(message),
+                error: None,
+            },
+            Err(e) => Self {
+                message: None,
+                error: Some(e.message().to_owned()),
+            },
+        }
+    }
+}
+
+/// Like [`estimate_message_gas`] for each of `msgs`, but the tipset, the pending messages of
+/// each sender, the recent base fees and the recently paid gas premiums are only looked up once
+/// for the whole batch. Like in Lotus' `GasBatchEstimateMessageGas`, messages of the same sender
+/// are estimated in order, each one executed after the previous ones. Up to
+/// [`GAS_ESTIMATE_BATCH_CONCURRENCY`] senders are estimated at a time. A failed estimate doesn't
+/// fail the other ones, the results are in the order of `msgs`.
+pub async fn estimate_messages_gas<DB>(
+    data: &Ctx<DB>,
+    msgs: Vec<Message>,
+    spec: Option<MessageSendSpec>,
+    tsk: ApiTipsetKey,
+) -> Result<Vec<Result<Message, ServerError>>, ServerError>
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    let curr_ts = data
+        .chain_store()
+        .load_required_tipset_or_heaviest(&tsk.0)?;
+
+    let base_fee = if msgs.iter().any(|msg| msg.gas_fee_cap.is_zero()) {
+        Some(base_fee_estimate(
+            data.chain_index(),
+            curr_ts.clone(),
+            MAX_QUEUE_BLKS,
+            &GasFeeCapConfig::from(&data.mpool.config),
+        )?)
+    } else {
+        None
+    };
+
+    let premium_config = GasPremiumConfig::from(&data.mpool.config);
+    let premium_samples = if msgs.iter().any(|msg| msg.gas_premium.is_zero()) {
+        Some(recent_gas_premiums(
+            data,
+            10,
+            premium_config.lookback_multiplier,
+        )?)
+    } else {
+        None
+    };
+    // premiums are computed upfront, from the samples shared by all messages
+    let premiums = msgs
+        .iter()
+        .map(|msg| match &premium_samples {
+            Some((prices, blocks)) if msg.gas_premium.is_zero() => {
+                gas_premium_from_samples(prices, *blocks, 10, &premium_config).map(Some)
+            }
+            _ => Ok(None),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut by_sender: HashMap<_, Vec<_>> = HashMap::default();
+    for (index, (msg, premium)) in msgs.into_iter().zip(premiums).enumerate() {
+        by_sender
+            .entry(msg.from)
+            .or_default()
+            .push((index, msg, premium));
+    }
+    let estimates = by_sender.into_values().map(|msgs| {
+        estimate_sender_messages_gas(data, msgs, &curr_ts, base_fee.as_ref(), spec.as_ref())
+    });
+    let mut results: Vec<_> = futures::stream::iter(estimates)
+        .buffer_unordered(GAS_ESTIMATE_BATCH_CONCURRENCY)
+        .flat_map(futures::stream::iter)
+        .collect()
+        .await;
+    results.sort_by_key(|(index, _)| *index);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+/// Sender of messages of a [`GasEstimateMessageGasBatch`] that need to be executed.
+struct BatchSender {
+    key_addr: Address,
+    /// Messages the next message of the sender is executed after: its pending messages, followed
+    /// by its messages estimated so far.
+    prior_messages: Vec<ChainMessage>,
+    next_sequence: u64,
+}
+
+impl BatchSender {
+    async fn resolve<DB>(data: &Ctx<DB>, from: &Address, ts: &Arc<Tipset>) -> anyhow::Result<Self>
+    where
+        DB: Blockstore + Send + Sync + 'static,
+    {
+        let key_addr = data.state_manager.resolve_to_key_addr(from, ts).await?;
+        Ok(Self {
+            prior_messages: GasEstimateGasLimit::prior_messages(data, &key_addr),
+            next_sequence: data.mpool.get_sequence(&key_addr)?,
+            key_addr,
+        })
+    }
+
+    /// Appends an estimated message, for the next ones to be executed after it.
+    fn push(&mut self, mut msg: Message) {
+        msg.sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.prior_messages
+            .push(GasEstimateGasLimit::pretend_signed(msg, &self.key_addr));
+    }
+}
+
+/// Estimates the messages of [`estimate_messages_gas`] sent by the same sender, in order. The
+/// results are tagged with the index of their message.
+async fn estimate_sender_messages_gas<DB>(
+    data: &Ctx<DB>,
+    msgs: Vec<(usize, Message, Option<TokenAmount>)>,
+    ts: &Arc<Tipset>,
+    base_fee: Option<&TokenAmount>,
+    spec: Option<&MessageSendSpec>,
+) -> Vec<(usize, Result<Message, ServerError>)>
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    let mut sender = match msgs.iter().find(|(_, msg, _)| msg.gas_limit == 0) {
+        Some((_, msg, _)) => Some(
+            BatchSender::resolve(data, &msg.from, ts)
+                .await
+                .map_err(|e| e.to_string()),
+        ),
+        None => None,
+    };
+    let mut results = Vec::with_capacity(msgs.len());
+    for (index, msg, premium) in msgs {
+        let result =
+            estimate_batched_message_gas(data, msg, sender.as_ref(), premium, base_fee, spec).await;
+        if let (Ok(msg), Some(Ok(sender))) = (&result, &mut sender) {
+            sender.push(msg.clone());
+        }
+        results.push((index, result));
+    }
+    results
+}
+
+/// Estimates a message of [`estimate_messages_gas`], given its sender, the premium computed for
+/// it and the base fee its fee cap is projected from.
+async fn estimate_batched_message_gas<DB>(
+    data: &Ctx<DB>,
+    mut msg: Message,
+    sender: Option<&Result<BatchSender, String>>,
+    premium: Option<TokenAmount>,
+    base_fee: Option<&TokenAmount>,
+    spec: Option<&MessageSendSpec>,
+) -> Result<Message, ServerError>
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    validate_message(&msg)?;
+    if msg.gas_limit == 0 {
+        let sender = sender
+            .context("sender wasn't resolved")?
+            .as_ref()
+            .map_err(|e| ServerError::internal_error(e, None))?;
+        let (res, ..) = GasEstimateGasLimit::call_with_gas_after(
+            data,
+            msg.clone(),
+            &sender.key_addr,
+            &sender.prior_messages,
+            VMTrace::NotTraced,
+        )
+        .await?;
+        let gl = gas_used(res.msg_rct.as_ref())?;
+        msg.set_gas_limit(overestimate_gas_limit(
+            gl as i64,
+            data.mpool.config.gas_limit_overestimation,
+        ));
+    }
+    if let Some(premium) = premium {
+        msg.set_gas_premium(premium);
+    }
+    if msg.gas_fee_cap.is_zero() {
+        let _timer = TaskTimer::start(metrics::values::ESTIMATE_FEE_CAP);
+        let base_fee = base_fee.context("base fee wasn't estimated")?;
+        let max_fee_cap = spec.and_then(|spec| spec.max_fee_cap.as_ref());
+        let gfp = compute_fee_cap(base_fee, &msg.gas_premium, MAX_QUEUE_BLKS, max_fee_cap)?;
         msg.set_gas_fee_cap(gfp);
     }
+    if let Some(spec) = spec {
+        cap_gas_fee(&mut msg, &spec.max_fee)?;
+    }
     Ok(msg)
 }
+
+/// Pads the estimated `gas_used` of a message by `overestimation_rate` (see
+/// [`crate::message_pool::MpoolConfig::gas_limit_overestimation`]), as the actual gas usage may
+/// differ once the message lands on chain.
+pub fn overestimate_gas_limit(gas_used: i64, overestimation_rate: f64) -> u64 {
+    ((gas_used as f64 * overestimation_rate) as u64).min(BLOCK_GAS_LIMIT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    /// Reads the value of the sample `name` from the default registry.
+    fn metric_value(name: &str) -> f64 {
+        let mut encoded = String::new();
+        prometheus_client::encoding::text::encode(&mut encoded, &metrics::default_registry())
+            .unwrap();
+        encoded
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn gas_estimate_metrics() {
+        // the metrics are global, other tests may record estimates concurrently
+        let before = metric_value("gas_estimate_histogram_count");
+        let errors_before = metric_value("gas_estimate_errors_total");
+        for i in 0..5 {
+            let estimate = async move {
+                if i % 2 == 0 {
+                    Ok(Message::default())
+                } else {
+                    Err(ServerError::internal_error("estimate failed", None))
+                }
+            };
+            let _ = record_gas_estimate(estimate).await;
+        }
+        assert!(metric_value("gas_estimate_histogram_count") >= before + 5.0);
+        assert!(metric_value(r#"gas_estimate_histogram_bucket{le="+Inf"}"#) >= before + 5.0);
+        assert!(metric_value("gas_estimate_errors_total") >= errors_before + 2.0);
+    }
+
+    #[test]
+    fn gas_premium_without_samples() {
+        let config = GasPremiumConfig {
+            noise_stddev: 0.0,
+            min_premium_atto_fil: 1_000,
+            ..Default::default()
+        };
+        for (nblocksincl, expected) in [(1, 2_000), (2, 1_500), (10, 1_000)] {
+            let premium = gas_premium_from_samples(&[], 0, nblocksincl, &config).unwrap();
+            assert_eq!(premium, TokenAmount::from_atto(expected));
+        }
+        assert_eq!(GasPremiumConfig::default().min_premium_atto_fil, 100_000);
+        let mpool_config = MpoolConfig {
+            min_gas_premium: 1_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            GasPremiumConfig::from(&mpool_config).min_premium_atto_fil,
+            1_000
+        );
+    }
+
+    #[test]
+    fn fee_cap_unclamped() {
+        let base_fee = TokenAmount::from_atto(100);
+        let premium = TokenAmount::from_atto(1);
+        let fee_cap = compute_fee_cap(&base_fee, &premium, 0, None).unwrap();
+        assert_eq!(fee_cap, TokenAmount::from_atto(101));
+        let max_fee_cap = TokenAmount::from_atto(10_000);
+        let fee_cap = compute_fee_cap(&base_fee, &premium, 20, Some(&max_fee_cap)).unwrap();
+        assert!(fee_cap > TokenAmount::from_atto(101));
+        assert!(fee_cap < max_fee_cap);
+    }
+
+    #[test]
+    fn base_fee_projection() {
+        let fees = GasBaseFee::new(TokenAmount::from_atto(1_000_000)).unwrap();
+        assert_eq!(fees.current, TokenAmount::from_atto(1_000_000));
+        // the base fee rises by at most 1/8th per block
+        assert_eq!(fees.projected_1, TokenAmount::from_atto(1_125_000));
+        assert!(fees.projected_1 < fees.projected_5);
+        assert!(fees.projected_5 < fees.projected_10);
+        let premium = TokenAmount::from_atto(1);
+        assert_eq!(
+            compute_fee_cap(&fees.current, &premium, 10, None).unwrap(),
+            &fees.projected_10 + &premium
+        );
+
+        let json = serde_json::to_value(&fees).unwrap();
+        assert_eq!(json["current"], "1000000");
+        assert_eq!(json["projected_1"], "1125000");
+        assert_eq!(serde_json::from_value::<GasBaseFee>(json).unwrap(), fees);
+    }
+
+    #[test]
+    fn queue_blks_capped() {
+        let config = GasFeeCapConfig::default();
+        for (max_queue_blks, expected) in [(0, 0), (1, 1), (20, 20), (21, 20), (100, 20)] {
+            assert_eq!(
+                capped_queue_blks(max_queue_blks, &config).unwrap(),
+                expected
+            );
+        }
+        assert!(capped_queue_blks(101, &config).is_err());
+        let config = GasFeeCapConfig {
+            max_queue_blks_limit: 10,
+            ..config
+        };
+        assert_eq!(capped_queue_blks(10, &config).unwrap(), 10);
+        assert!(capped_queue_blks(11, &config).is_err());
+    }
+
+    #[test]
+    fn fee_cap_clamped() {
+        let base_fee = TokenAmount::from_atto(100);
+        let premium = TokenAmount::from_atto(1);
+        let max_fee_cap = TokenAmount::from_nano(1);
+        let fee_cap = compute_fee_cap(&base_fee, &premium, 300, Some(&max_fee_cap)).unwrap();
+        assert_eq!(fee_cap, max_fee_cap);
+        // the increase factor overflows
+        let fee_cap = compute_fee_cap(&base_fee, &premium, 10_000, Some(&max_fee_cap)).unwrap();
+        assert_eq!(fee_cap, max_fee_cap);
+        assert!(compute_fee_cap(&base_fee, &premium, 10_000, None).is_err());
+    }
+
+    #[test]
+    fn fee_cap_not_below_premium() {
+        let base_fee = TokenAmount::from_atto(100);
+        let premium = TokenAmount::from_nano(2);
+        // a maximum below the premium can't be honoured
+        let max_fee_cap = TokenAmount::from_nano(1);
+        let error = compute_fee_cap(&base_fee, &premium, 20, Some(&max_fee_cap)).unwrap_err();
+        assert!(error.to_string().contains("below the gas premium"));
+        assert!(compute_fee_cap(&base_fee, &premium, 10_000, Some(&max_fee_cap)).is_err());
+        // clamping to exactly the premium is fine
+        let fee_cap = compute_fee_cap(&base_fee, &premium, 20, Some(&premium)).unwrap();
+        assert_eq!(fee_cap, premium);
+        // the unclamped fee cap includes the premium
+        let fee_cap = compute_fee_cap(&base_fee, &premium, 0, None).unwrap();
+        assert_eq!(fee_cap, &premium + &base_fee);
+        let fee_cap = compute_fee_cap(&base_fee, &premium, 20, None).unwrap();
+        assert!(fee_cap > &premium + &base_fee);
+    }
+
+    #[test]
+    fn gas_fee_capped() {
+        let msg = Message {
+            gas_limit: 1_000,
+            gas_fee_cap: TokenAmount::from_atto(1_000),
+            gas_premium: TokenAmount::from_atto(900),
+            ..Default::default()
+        };
+        let capped = |max_fee: u64| {
+            let mut msg = msg.clone();
+            cap_gas_fee(&mut msg, &TokenAmount::from_atto(max_fee)).map(|()| msg)
+        };
+
+        // no limit, or within it
+        assert_eq!(capped(0).unwrap(), msg);
+        assert_eq!(capped(1_000_000).unwrap(), msg);
+
+        let msg_capped = capped(500_999).unwrap();
+        assert_eq!(msg_capped.gas_fee_cap, TokenAmount::from_atto(500));
+        assert_eq!(msg_capped.gas_premium, TokenAmount::from_atto(500));
+        let msg_capped = capped(950_000).unwrap();
+        assert_eq!(msg_capped.gas_fee_cap, TokenAmount::from_atto(950));
+        assert_eq!(msg_capped.gas_premium, TokenAmount::from_atto(900));
+    }
+
+    #[test]
+    fn gas_fee_cap_below_minimum_base_fee() {
+        let mut msg = Message {
+            gas_limit: 1_000,
+            gas_fee_cap: TokenAmount::from_atto(1_000),
+            ..Default::default()
+        };
+        let error = cap_gas_fee(&mut msg, &TokenAmount::from_atto(99_999)).unwrap_err();
+        assert!(error.to_string().contains("too low"));
+        assert_eq!(msg.gas_fee_cap, TokenAmount::from_atto(1_000));
+        // exactly the minimum base fee is fine
+        cap_gas_fee(&mut msg, &TokenAmount::from_atto(100_000)).unwrap();
+        assert_eq!(msg.gas_fee_cap, TokenAmount::from_atto(MINIMUM_BASE_FEE));
+    }
+
+    #[test]
+    fn recent_base_fees_up_to_tipset() {
+        use crate::blocks::{chain4u, Chain4U, HeaderBuilder};
+
+        let c4u = Chain4U::new();
+        let fee = |atto: u64| HeaderBuilder {
+            parent_base_fee: TokenAmount::from_atto(atto).into(),
+            ..Default::default()
+        };
+        chain4u! {
+            in c4u;
+            [_genesis = fee(100)]
+            -> [_a = fee(200)]
+            -> b @ [_b = fee(400)]
+            -> head @ [_head = fee(800)]
+        };
+        let index = ChainIndex::new(&c4u);
+        let fees = |tipset: &Tipset, max_queue_blks| {
+            recent_base_fees(&index, Arc::new(tipset.clone()), max_queue_blks).unwrap()
+        };
+
+        assert_eq!(
+            fees(head, 20),
+            [100, 200, 400, 800].map(TokenAmount::from_atto)
+        );
+        assert_eq!(fees(head, 2), [400, 800].map(TokenAmount::from_atto));
+        // a historical tipset only sees the base fees up to it
+        assert_eq!(fees(b, 20), [100, 200, 400].map(TokenAmount::from_atto));
+        assert!(base_fee_ema(&fees(b, 20), 0.2) < base_fee_ema(&fees(head, 20), 0.2));
+    }
+
+    #[test]
+    fn base_fee_ema_reference() {
+        let fees = |atto: &[u64]| {
+            atto.iter()
+                .map(|&a| TokenAmount::from_atto(a))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(base_fee_ema(&[], 0.2), None);
+        assert_eq!(
+            base_fee_ema(&fees(&[100]), 0.2),
+            Some(TokenAmount::from_atto(100))
+        );
+        // 100 -> 0.2 * 200 + 0.8 * 100 = 120
+        //     -> 0.2 * 100 + 0.8 * 120 = 116
+        //     -> 0.2 * 600 + 0.8 * 116 = 212.8
+        let alpha = GasFeeCapConfig::default().alpha;
+        assert_eq!(
+            base_fee_ema(&fees(&[100, 200, 100, 600]), alpha),
+            Some(TokenAmount::from_atto(213))
+        );
+        // a single spike moves the average by `alpha` of its size
+        assert_eq!(
+            base_fee_ema(&fees(&[1_000, 1_000, 1_000, 11_000]), alpha),
+            Some(TokenAmount::from_atto(3_000))
+        );
+        assert_eq!(
+            base_fee_ema(&fees(&[100, 200, 100, 600]), 1.0),
+            Some(TokenAmount::from_atto(600))
+        );
+        assert_eq!(
+            base_fee_ema(&fees(&[100, 200, 100, 600]), 0.0),
+            Some(TokenAmount::from_atto(100))
+        );
+    }
+
+    #[test]
+    fn sparse_chain_lookback() {
+        // every third tipset has a message, starting with the head
+        let chain = || {
+            (0..usize::MAX).map(|height| {
+                let prices = if height % 3 == 0 {
+                    vec![gas_meta(height as u64, 1_000)]
+                } else {
+                    vec![]
+                };
+                anyhow::Ok((2, prices))
+            })
+        };
+        let (prices, blocks) = sample_gas_premiums(chain(), 4).unwrap();
+        assert_eq!(
+            prices
+                .iter()
+                .map(|meta| meta.price.clone())
+                .collect::<Vec<_>>(),
+            [0, 3, 6, 9].map(TokenAmount::from_atto)
+        );
+        // the blocks of empty tipsets aren't counted either
+        assert_eq!(blocks, 8);
+
+        // the walk is bounded on (almost) empty chains
+        let (prices, _) = sample_gas_premiums(chain(), u64::MAX).unwrap();
+        assert_eq!(prices.len(), MAX_GAS_PREMIUM_LOOKBACK / 3);
+        let empty = std::iter::repeat_with(|| anyhow::Ok((1, vec![])));
+        let (prices, blocks) = sample_gas_premiums(empty, 10).unwrap();
+        assert!(prices.is_empty());
+        assert_eq!(blocks, 0);
+
+        // errors are propagated
+        let broken = [
+            anyhow::Ok((1, vec![gas_meta(1, 1)])),
+            Err(anyhow::anyhow!("missing tipset")),
+        ];
+        assert!(sample_gas_premiums(broken, 10).is_err());
+        let (prices, _) = sample_gas_premiums(
+            [
+                anyhow::Ok((1, vec![gas_meta(1, 1)])),
+                Err(anyhow::anyhow!("missing tipset")),
+            ],
+            1,
+        )
+        .unwrap();
+        assert_eq!(prices.len(), 1);
+    }
+
+    fn gas_meta(atto: u64, limit: u64) -> GasMeta {
+        GasMeta {
+            price: TokenAmount::from_atto(atto),
+            limit,
+            fill_ratio: 0.0,
+        }
+    }
+
+    type Sample = (TokenAmount, u64);
+
+    /// Reference for [`gas_premium_crossing`], sorting all samples.
+    fn sorted_gas_premium_crossing(
+        prices: &[GasMeta],
+        target: u64,
+    ) -> Option<(Sample, Option<Sample>)> {
+        let mut sorted: Vec<_> = prices.iter().collect();
+        sorted.sort_by(|a, b| b.price.cmp(&a.price));
+        let mut at = i128::from(target);
+        let mut prev = None;
+        for price in sorted {
+            let sample = (price.price.clone(), price.limit);
+            at -= i128::from(price.limit);
+            if at <= 0 {
+                return Some((sample, prev));
+            }
+            prev = Some(sample);
+        }
+        None
+    }
+
+    #[quickcheck]
+    fn gas_premium_crossing_matches_sort(samples: Vec<(u8, u16)>, target: u32) {
+        // few distinct premiums, so that ties have to be ordered as in the input
+        let prices: Vec<_> = samples
+            .into_iter()
+            .map(|(atto, limit)| gas_meta(u64::from(atto % 8), u64::from(limit)))
+            .collect();
+        let sample = |price: &GasMeta| (price.price.clone(), price.limit);
+        let crossing = gas_premium_crossing(&prices, u64::from(target))
+            .map(|(price, prev)| (sample(price), prev.map(sample)));
+        assert_eq!(
+            crossing,
+            sorted_gas_premium_crossing(&prices, u64::from(target))
+        );
+    }
+
+    #[test]
+    fn gas_premium_from_samples_crossing() {
+        let config = GasPremiumConfig {
+            noise_stddev: 0.0,
+            ..Default::default()
+        };
+        // two blocks target `BLOCK_GAS_TARGET` gas, reached by the third most expensive message
+        let prices = [
+            gas_meta(100, BLOCK_GAS_TARGET / 2),
+            gas_meta(300, BLOCK_GAS_TARGET / 4),
+            gas_meta(500, BLOCK_GAS_TARGET / 4),
+            gas_meta(200, BLOCK_GAS_TARGET / 2),
+        ];
+        assert_eq!(
+            gas_premium_from_samples(&prices, 2, 1, &config).unwrap(),
+            TokenAmount::from_atto(251)
+        );
+        // the most expensive message alone reaches the target
+        let prices = [
+            gas_meta(100, BLOCK_GAS_TARGET),
+            gas_meta(500, BLOCK_GAS_TARGET),
+        ];
+        assert_eq!(
+            gas_premium_from_samples(&prices, 2, 1, &config).unwrap(),
+            TokenAmount::from_atto(501)
+        );
+        // the target isn't reached
+        assert_eq!(
+            gas_premium_from_samples(&prices, 5, 10, &config).unwrap(),
+            min_gas_premium(10, config.min_premium_atto_fil)
+        );
+    }
+
+    #[test]
+    fn fill_weighted_premium() {
+        assert_eq!(block_fill_ratio(0, 0), 0.0);
+        assert_eq!(block_fill_ratio(BLOCK_GAS_LIMIT, 2), 0.5);
+        assert_eq!(block_fill_ratio(3 * BLOCK_GAS_LIMIT, 2), 1.0);
+
+        // cheap messages in a nearly empty block, expensive ones in a congested block
+        let prices = || {
+            vec![
+                GasMeta {
+                    fill_ratio: 0.1,
+                    ..gas_meta(100, 1_000)
+                },
+                GasMeta {
+                    fill_ratio: 0.9,
+                    ..gas_meta(500, 500)
+                },
+            ]
+        };
+        let uniform = apply_weighting(prices(), GasPremiumWeighting::Uniform);
+        assert_eq!(
+            weighted_percentile(uniform, 60),
+            Some(TokenAmount::from_atto(100))
+        );
+        let weighted = apply_weighting(prices(), GasPremiumWeighting::FillWeighted);
+        assert_eq!(
+            weighted.iter().map(|price| price.limit).collect::<Vec<_>>(),
+            vec![1_111, 5_000]
+        );
+        assert_eq!(
+            weighted_percentile(weighted, 60),
+            Some(TokenAmount::from_atto(500))
+        );
+
+        // full blocks get a finite weight
+        assert!((GasPremiumWeighting::FillWeighted.weight(1.0) - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn message_count_weighted_premium() {
+        // many small messages with high premiums, a few large ones with low premiums
+        let prices = || {
+            (0..2)
+                .map(|_| gas_meta(100, 1_000_000))
+                .chain((0..8).map(|_| gas_meta(1_000, 10_000)))
+                .collect::<Vec<_>>()
+        };
+        let by_gas = apply_weighting(prices(), GasPremiumWeighting::Uniform);
+        assert_eq!(
+            weighted_percentile(by_gas, 60),
+            Some(TokenAmount::from_atto(100))
+        );
+        let by_count = apply_weighting(prices(), GasPremiumWeighting::MessageCount);
+        assert!(by_count.iter().all(|price| price.limit == 1));
+        assert_eq!(
+            weighted_percentile(by_count, 60),
+            Some(TokenAmount::from_atto(1_000))
+        );
+    }
+
+    #[test]
+    fn weighted_percentile_premium() {
+        // a single large message outweighs many small, expensive ones
+        let prices = || {
+            vec![
+                gas_meta(500, 100),
+                gas_meta(100, 10_000),
+                gas_meta(400, 100),
+                gas_meta(300, 100),
+            ]
+        };
+        assert_eq!(
+            weighted_percentile(prices(), 60),
+            Some(TokenAmount::from_atto(100))
+        );
+        assert_eq!(
+            weighted_percentile(prices(), 99),
+            Some(TokenAmount::from_atto(400))
+        );
+        assert_eq!(
+            weighted_percentile(prices(), 100),
+            Some(TokenAmount::from_atto(500))
+        );
+        assert_eq!(weighted_percentile(vec![], 60), None);
+        assert_eq!(weighted_percentile(vec![gas_meta(100, 0)], 60), None);
+    }
+
+    #[test]
+    fn gas_premium_noise() {
+        let premium = TokenAmount::from_atto(1_000_000);
+        assert_eq!(
+            apply_gas_premium_noise(premium.clone(), 0.0).unwrap(),
+            premium
+        );
+        assert!(apply_gas_premium_noise(premium, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn validate_message_fields() {
+        let valid = Message {
+            from: Address::new_id(1000),
+            to: Address::new_id(1001),
+            value: TokenAmount::from_whole(1),
+            ..Default::default()
+        };
+        validate_message(&valid).unwrap();
+
+        type Mutation = fn(&mut Message);
+        let cases: [(Mutation, &str); 8] = [
+            (|m| m.version = 1, "message version 1 not supported"),
+            (
+                |m| m.from = *ZERO_ADDRESS,
+                "'from' address is the zero address",
+            ),
+            (|m| m.to = *ZERO_ADDRESS, "'to' address is the zero address"),
+            (
+                |m| m.value = TokenAmount::from_atto(-1),
+                "value cannot be negative",
+            ),
+            (
+                |m| m.value = TOTAL_FILECOIN.clone() + TokenAmount::from_atto(1),
+                "value cannot be greater than total FIL supply",
+            ),
+            (
+                |m| m.gas_fee_cap = TokenAmount::from_atto(-1),
+                "gas_fee_cap cannot be negative",
+            ),
+            (
+                |m| m.gas_premium = TokenAmount::from_atto(-1),
+                "gas_premium cannot be negative",
+            ),
+            (
+                |m| m.gas_limit = BLOCK_GAS_LIMIT + 1,
+                "cannot be greater than block gas limit",
+            ),
+        ];
+        for (invalidate, reason) in cases {
+            let mut msg = valid.clone();
+            invalidate(&mut msg);
+            match validate_message(&msg) {
+                Err(GasEstimationError::InvalidMessage(e)) => {
+                    assert!(e.contains(reason), "{e} doesn't contain {reason}")
+                }
+                other => panic!("unexpected result {other:?} for {reason}"),
+            }
+        }
+    }
+
+    #[test]
+    fn batch_result_json() {
+        let ok = GasEstimateBatchResult::from(Ok(Message::default()));
+        let json = serde_json::to_value(&ok).unwrap();
+        assert!(json.get("Message").is_some() && json.get("Error").is_none());
+        assert_eq!(
+            serde_json::from_value::<GasEstimateBatchResult>(json).unwrap(),
+            ok
+        );
+
+        let err = GasEstimateBatchResult::from(Err(GasEstimationError::MissingReceipt.into()));
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "Error": "message execution produced no receipt" })
+        );
+    }
+
+    fn receipt(exit_code: u32, gas_used: u64) -> Receipt {
+        Receipt::V4(fvm_shared4::receipt::Receipt {
+            exit_code: fvm_shared4::error::ExitCode::new(exit_code),
+            return_data: Default::default(),
+            gas_used,
+            events_root: None,
+        })
+    }
+
+    #[test]
+    fn gas_used_success() {
+        assert_eq!(gas_used(Some(&receipt(0, 1_000))), Ok(1_000));
+    }
+
+    #[test]
+    fn gas_used_reverted() {
+        let err = gas_used(Some(&receipt(16, 1_000))).unwrap_err();
+        assert_eq!(
+            err,
+            GasEstimationError::MessageReverted {
+                exit_code: ExitCode::from(16)
+            }
+        );
+        let err = ServerError::from(err);
+        assert_eq!(
+            err.known_code(),
+            implementation_defined_errors::MESSAGE_REVERTED.into()
+        );
+        assert_eq!(err.message(), "message execution failed: exit 16");
+
+        assert_eq!(gas_used(None), Err(GasEstimationError::MissingReceipt));
+    }
+
+    #[test]
+    fn gas_limit_overestimation() {
+        assert_eq!(overestimate_gas_limit(1_000_000, 1.25), 1_250_000);
+        assert_eq!(
+            overestimate_gas_limit(BLOCK_GAS_LIMIT as i64, 1.25),
+            BLOCK_GAS_LIMIT
+        );
+        // failed estimations are reported as -1
+        assert_eq!(overestimate_gas_limit(-1, 1.25), 0);
+    }
+}
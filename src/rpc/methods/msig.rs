@@ -133,3 +133,35 @@ impl RpcMethod<2> for MsigGetVestingSchedule {
         Ok(ms.get_vesting_schedule()?)
     }
 }
+
+pub enum MsigGetState {}
+impl RpcMethod<2> for MsigGetState {
+    const NAME: &'static str = "Forest.MsigGetState";
+    const PARAM_NAMES: [&'static str; 2] = ["address", "tsk"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, ApiTipsetKey);
+    type Ok = MsigState;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (addr, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        let ms: multisig::State = ctx.state_manager.get_actor_state_from_address(&ts, &addr)?;
+        let MsigVesting {
+            initial_balance,
+            start_epoch,
+            unlock_duration,
+        } = ms.get_vesting_schedule()?;
+        Ok(MsigState {
+            signers: ms.signers().into_iter().map(Address::from).collect(),
+            num_approvals_threshold: ms.threshold(),
+            next_tx_id: ms.next_tx_id(),
+            initial_balance: TokenAmount::from_atto(initial_balance),
+            start_epoch,
+            unlock_duration,
+        })
+    }
+}
@@ -42,7 +42,7 @@ use crate::shim::{
     state_tree::ActorState, version::NetworkVersion,
 };
 use crate::state_manager::circulating_supply::GenesisInfo;
-use crate::state_manager::{MarketBalance, StateOutput};
+use crate::state_manager::{MarketBalance, StateManager, StateOutput};
 use crate::utils::db::{
     car_stream::{CarBlock, CarWriter},
     BlockstoreExt as _,
@@ -875,6 +875,52 @@ impl RpcMethod<2> for StateMinerAvailableBalance {
     }
 }
 
+pub enum StateGetMinerPenalties {}
+
+impl RpcMethod<2> for StateGetMinerPenalties {
+    const NAME: &'static str = "Forest.StateGetMinerPenalties";
+    const PARAM_NAMES: [&'static str; 2] = ["address", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, ApiTipsetKey);
+    type Ok = MinerPenalties;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        let state: miner::State = ctx
+            .state_manager
+            .get_actor_state_from_address(&ts, &address)?;
+        Ok(MinerPenalties::new(&state))
+    }
+}
+
+pub enum StateGetMinerFeeDebt {}
+
+impl RpcMethod<2> for StateGetMinerFeeDebt {
+    const NAME: &'static str = "Forest.StateGetMinerFeeDebt";
+    const PARAM_NAMES: [&'static str; 2] = ["address", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, ApiTipsetKey);
+    type Ok = TokenAmount;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        let state: miner::State = ctx
+            .state_manager
+            .get_actor_state_from_address(&ts, &address)?;
+        Ok(state.fee_debt().into())
+    }
+}
+
 pub enum StateMinerInitialPledgeCollateral {}
 
 impl RpcMethod<3> for StateMinerInitialPledgeCollateral {
@@ -891,33 +937,56 @@ impl RpcMethod<3> for StateMinerInitialPledgeCollateral {
         (address, pci, ApiTipsetKey(tsk)): Self::Params,
     ) -> Result<Self::Ok, ServerError> {
         let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(Self::initial_pledge_collateral(
+            &ctx.state_manager,
+            &ts,
+            &address,
+            pci.seal_proof,
+            pci.deal_ids,
+            pci.expiration,
+        )?)
+    }
+}
 
-        let sector_size = pci
-            .seal_proof
+impl StateMinerInitialPledgeCollateral {
+    /// Computes the initial pledge required for a sector with the given seal
+    /// proof, deals and expiration, as of the given tipset.
+    pub fn initial_pledge_collateral<DB>(
+        state_manager: &StateManager<DB>,
+        ts: &Tipset,
+        address: &Address,
+        seal_proof: crate::shim::sector::RegisteredSealProof,
+        deal_ids: Vec<DealID>,
+        expiration: ChainEpoch,
+    ) -> anyhow::Result<TokenAmount>
+    where
+        DB: Blockstore + Send + Sync + 'static,
+    {
+        let sector_size = seal_proof
             .sector_size()
             .map_err(|e| anyhow::anyhow!("failed to get resolve size: {e}"))?;
 
-        let market_state: market::State = ctx.state_manager.get_actor_state(&ts)?;
+        let market_state: market::State = state_manager.get_actor_state(ts)?;
         let (w, vw) = market_state.verify_deals_for_activation(
-            ctx.store(),
-            address.into(),
-            pci.deal_ids,
+            state_manager.blockstore(),
+            (*address).into(),
+            deal_ids,
             ts.epoch(),
-            pci.expiration,
+            expiration,
         )?;
-        let duration = pci.expiration - ts.epoch();
+        let duration = expiration - ts.epoch();
         let sector_weight =
             qa_power_for_weight(SectorSize::from(sector_size).into(), duration, &w, &vw);
 
-        let power_state: power::State = ctx.state_manager.get_actor_state(&ts)?;
+        let power_state: power::State = state_manager.get_actor_state(ts)?;
         let power_smoothed = power_state.total_power_smoothed();
         let pledge_collateral = power_state.total_locked();
 
-        let reward_state: reward::State = ctx.state_manager.get_actor_state(&ts)?;
-        let genesis_info = GenesisInfo::from_chain_config(ctx.chain_config().clone());
+        let reward_state: reward::State = state_manager.get_actor_state(ts)?;
+        let genesis_info = GenesisInfo::from_chain_config(state_manager.chain_config().clone());
         let circ_supply = genesis_info.get_vm_circulating_supply_detailed(
             ts.epoch(),
-            &Arc::new(ctx.store()),
+            &state_manager.blockstore_owned(),
             ts.parent_state(),
         )?;
         let initial_pledge: TokenAmount = reward_state
@@ -936,6 +1005,74 @@ impl RpcMethod<3> for StateMinerInitialPledgeCollateral {
     }
 }
 
+/// Returns the net change in pledge when the deals of an existing sector are
+/// replaced (see FIP-0019). The result is the initial pledge of the replacement
+/// sector minus the pledge already locked for the old sector, and may be negative.
+pub enum StatePledgeCollateralForReplacedSector {}
+
+impl RpcMethod<3> for StatePledgeCollateralForReplacedSector {
+    const NAME: &'static str = "Forest.StatePledgeCollateralForReplacedSector";
+    const PARAM_NAMES: [&'static str; 3] = ["address", "replacement", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, SectorReplacement, ApiTipsetKey);
+    type Ok = TokenAmount;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, replacement, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(Self::pledge_delta(
+            &ctx.state_manager,
+            &ts,
+            &address,
+            replacement,
+        )?)
+    }
+}
+
+impl StatePledgeCollateralForReplacedSector {
+    fn pledge_delta<DB>(
+        state_manager: &StateManager<DB>,
+        ts: &Tipset,
+        address: &Address,
+        SectorReplacement {
+            old_sector,
+            new_deal_ids,
+            new_expiration,
+        }: SectorReplacement,
+    ) -> anyhow::Result<TokenAmount>
+    where
+        DB: Blockstore + Send + Sync + 'static,
+    {
+        let miner_state: miner::State = state_manager.get_actor_state_from_address(ts, address)?;
+        let mut sectors = BitField::new();
+        sectors.set(old_sector);
+        let old = miner_state
+            .load_sectors_ext(state_manager.blockstore(), Some(&sectors))?
+            .into_iter()
+            .next()
+            .with_context(|| format!("sector {old_sector} not found for miner {address}"))?;
+        anyhow::ensure!(
+            new_expiration > ts.epoch(),
+            "new expiration {new_expiration} must be after the current epoch {}",
+            ts.epoch()
+        );
+
+        let new_pledge = StateMinerInitialPledgeCollateral::initial_pledge_collateral(
+            state_manager,
+            ts,
+            address,
+            old.seal_proof,
+            new_deal_ids,
+            new_expiration,
+        )?;
+        Ok(new_pledge - &old.initial_pledge)
+    }
+}
+
 pub enum StateMinerPreCommitDepositForPower {}
 
 impl RpcMethod<3> for StateMinerPreCommitDepositForPower {
@@ -2148,7 +2285,7 @@ impl StateSectorGetInfo {
         let state_tree = StateTree::new_from_root(store.clone(), tipset.parent_state())?;
         let state: miner::State = state_tree.get_actor_state_from_address(miner_address)?;
         Ok(state
-            .load_sectors(store, None)?
+            .sectors(store)?
             .into_iter()
             .map(|s| s.sector_number)
             .collect())
@@ -2800,3 +2937,182 @@ impl TryFrom<&ChainConfig> for ForkUpgradeParams {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, RawBlockHeader};
+    use crate::chain::ChainStore;
+    use crate::chain_sync::SyncConfig;
+    use crate::db::MemoryDB;
+    use crate::shim::actors::actor_code;
+    use crate::shim::state_tree::StateTreeVersion;
+    use crate::utils::db::CborStoreExt as _;
+    use fil_actor_miner_state::v16::{SectorOnChainInfo, State as MinerStateV16};
+    use fil_actors_shared::v11::runtime::builtins::Type;
+    use fvm_shared4::sector::RegisteredSealProof as RegisteredSealProofV4;
+
+    const MINER: Address = Address::new_id(1000);
+
+    /// Returns a state manager over a mainnet genesis tipset whose state has the v16 market,
+    /// power and reward actors, and the miner returned by `miner` as the [`MINER`] actor, along
+    /// with that tipset.
+    fn state_manager_with_miner(
+        miner: impl FnOnce(&MemoryDB) -> MinerStateV16,
+    ) -> (StateManager<MemoryDB>, Tipset) {
+        let db = Arc::new(MemoryDB::default());
+        let actors = [
+            (
+                MINER,
+                Type::Miner,
+                db.put_cbor_default(&miner(&db)).unwrap(),
+            ),
+            (
+                Address::MARKET_ACTOR,
+                Type::Market,
+                db.put_cbor_default(&fil_actor_market_state::v16::State::new(&*db).unwrap())
+                    .unwrap(),
+            ),
+            (
+                Address::POWER_ACTOR,
+                Type::Power,
+                db.put_cbor_default(&fil_actor_power_state::v16::State::new(&*db).unwrap())
+                    .unwrap(),
+            ),
+            (
+                Address::REWARD_ACTOR,
+                Type::Reward,
+                db.put_cbor_default(&fil_actor_reward_state::v16::State::new(Default::default()))
+                    .unwrap(),
+            ),
+            (Address::BURNT_FUNDS_ACTOR, Type::Account, Cid::default()),
+        ];
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        for (address, actor, state) in actors {
+            state_tree
+                .set_actor(
+                    &address,
+                    ActorState::new(actor_code(16, actor), state, Default::default(), 0, None),
+                )
+                .unwrap();
+        }
+        let genesis_header = CachingBlockHeader::new(RawBlockHeader {
+            state_root: state_tree.flush().unwrap(),
+            timestamp: 7777,
+            ..Default::default()
+        });
+
+        let chain_config = Arc::new(ChainConfig::default());
+        let chain_store = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db,
+            chain_config.clone(),
+            genesis_header.clone(),
+        )
+        .unwrap();
+        let state_manager = StateManager::new(
+            Arc::new(chain_store),
+            chain_config,
+            Arc::new(SyncConfig::default()),
+        )
+        .unwrap();
+        (state_manager, Tipset::from(genesis_header))
+    }
+
+    fn new_miner(store: &MemoryDB) -> MinerStateV16 {
+        let policy = fil_actors_shared::v16::runtime::Policy::default();
+        MinerStateV16::new(&policy, store, Cid::default(), 0, 0).unwrap()
+    }
+
+    #[test]
+    fn pledge_collateral_for_replaced_sector() {
+        let old_pledge = TokenAmount::from_whole(1);
+        let (state_manager, ts) = state_manager_with_miner(|store| {
+            let mut miner = new_miner(store);
+            miner
+                .put_sectors(
+                    store,
+                    vec![SectorOnChainInfo {
+                        sector_number: 7,
+                        seal_proof: RegisteredSealProofV4::StackedDRG32GiBV1P1,
+                        initial_pledge: old_pledge.clone().into(),
+                        expiration: 1_000,
+                        ..Default::default()
+                    }],
+                )
+                .unwrap();
+            miner
+        });
+        let replacement = |old_sector, new_expiration| SectorReplacement {
+            old_sector,
+            new_deal_ids: vec![],
+            new_expiration,
+        };
+
+        let new_pledge = StateMinerInitialPledgeCollateral::initial_pledge_collateral(
+            &state_manager,
+            &ts,
+            &MINER,
+            RegisteredSealProofV4::StackedDRG32GiBV1P1.into(),
+            vec![],
+            2_000,
+        )
+        .unwrap();
+        assert!(new_pledge.is_positive());
+        let delta = StatePledgeCollateralForReplacedSector::pledge_delta(
+            &state_manager,
+            &ts,
+            &MINER,
+            replacement(7, 2_000),
+        )
+        .unwrap();
+        assert_eq!(delta, new_pledge - &old_pledge);
+
+        // unknown sector
+        StatePledgeCollateralForReplacedSector::pledge_delta(
+            &state_manager,
+            &ts,
+            &MINER,
+            replacement(8, 2_000),
+        )
+        .unwrap_err();
+        // the new expiration must be in the future
+        StatePledgeCollateralForReplacedSector::pledge_delta(
+            &state_manager,
+            &ts,
+            &MINER,
+            replacement(7, ts.epoch()),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn miner_penalties() {
+        let (state_manager, ts) = state_manager_with_miner(new_miner);
+        let state: miner::State = state_manager
+            .get_actor_state_from_address(&ts, &MINER)
+            .unwrap();
+        assert_eq!(
+            MinerPenalties::new(&state),
+            MinerPenalties {
+                fee_debt: TokenAmount::default(),
+                initial_pledge: TokenAmount::default(),
+                pre_commit_deposits: TokenAmount::default(),
+                vesting_funds_total: TokenAmount::default(),
+            }
+        );
+
+        let (state_manager, ts) = state_manager_with_miner(|store| {
+            let mut miner = new_miner(store);
+            miner.fee_debt = TokenAmount::from_atto(1_000).into();
+            miner
+        });
+        let state: miner::State = state_manager
+            .get_actor_state_from_address(&ts, &MINER)
+            .unwrap();
+        let fee_debt = TokenAmount::from(state.fee_debt());
+        assert_eq!(fee_debt, TokenAmount::from_atto(1_000));
+        assert_eq!(MinerPenalties::new(&state).fee_debt, fee_debt);
+    }
+}
@@ -3,14 +3,17 @@
 
 use crate::lotus_json::{lotus_json_with_self, LotusJson};
 use crate::message::Message as _;
+use crate::shim::actors::miner;
 use crate::shim::executor::ApplyRet;
 use crate::shim::{
     address::Address,
     clock::ChainEpoch,
+    deal::DealID,
     econ::TokenAmount,
     error::ExitCode,
     executor::Receipt,
     message::Message,
+    sector::SectorNumber,
     state_tree::{ActorID, ActorState},
 };
 use cid::Cid;
@@ -220,3 +223,49 @@ pub struct SectorLocation {
     pub partition: u64,
 }
 lotus_json_with_self!(SectorLocation);
+
+/// Replacement of the deals of an existing sector, see
+/// `Forest.StatePledgeCollateralForReplacedSector`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct SectorReplacement {
+    /// The sector whose deals are replaced
+    pub old_sector: SectorNumber,
+    #[schemars(with = "LotusJson<Vec<DealID>>")]
+    #[serde(with = "crate::lotus_json", rename = "NewDealIDs")]
+    pub new_deal_ids: Vec<DealID>,
+    /// Epoch during which the replacement sector expires
+    pub new_expiration: ChainEpoch,
+}
+lotus_json_with_self!(SectorReplacement);
+
+/// Penalty-related balances of a miner actor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct MinerPenalties {
+    /// Accumulated slashing penalties the miner has not been able to pay yet.
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<TokenAmount>")]
+    pub fee_debt: TokenAmount,
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<TokenAmount>")]
+    pub initial_pledge: TokenAmount,
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<TokenAmount>")]
+    pub pre_commit_deposits: TokenAmount,
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<TokenAmount>")]
+    pub vesting_funds_total: TokenAmount,
+}
+lotus_json_with_self!(MinerPenalties);
+
+impl MinerPenalties {
+    pub fn new(state: &miner::State) -> Self {
+        Self {
+            fee_debt: state.fee_debt().into(),
+            initial_pledge: state.initial_pledge().into(),
+            pre_commit_deposits: state.pre_commit_deposits().into(),
+            vesting_funds_total: state.locked_funds().into(),
+        }
+    }
+}
@@ -15,7 +15,7 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::chain;
-use crate::chain_sync::{SyncStage, TipsetValidator};
+use crate::chain_sync::{SyncHealthStatus, SyncStage, TipsetValidator};
 
 pub enum SyncCheckBad {}
 impl RpcMethod<1> for SyncCheckBad {
@@ -71,6 +71,30 @@ impl RpcMethod<0> for SyncState {
     }
 }
 
+/// Reports how far the node lags behind the epoch the network is expected to
+/// be at, according to the wall clock and the genesis timestamp.
+pub enum ChainSyncStatus {}
+impl RpcMethod<0> for ChainSyncStatus {
+    const NAME: &'static str = "Forest.ChainSyncStatus";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = ();
+    type Ok = SyncHealthStatus;
+
+    async fn handle(ctx: Ctx<impl Blockstore>, (): Self::Params) -> Result<Self::Ok, ServerError> {
+        let actual_epoch = ctx.sync_state.read().epoch();
+        Ok(SyncHealthStatus::new(
+            actual_epoch,
+            chrono::Utc::now().timestamp() as u64,
+            ctx.chain_store().genesis_block_header().timestamp,
+            ctx.chain_config(),
+            ctx.state_manager.beacon_schedule(),
+        )?)
+    }
+}
+
 pub enum SyncSubmitBlock {}
 impl RpcMethod<1> for SyncSubmitBlock {
     const NAME: &'static str = "Filecoin.SyncSubmitBlock";
@@ -224,6 +248,7 @@ mod tests {
             keystore: Arc::new(RwLock::new(KeyStore::new(KeyStoreConfig::Memory).unwrap())),
             mpool: Arc::new(pool),
             bad_blocks: Default::default(),
+            message_cache: Default::default(),
             sync_state: Arc::new(parking_lot::RwLock::new(Default::default())),
             eth_event_handler: Arc::new(EthEventHandler::new()),
             sync_network_context,
@@ -253,6 +278,23 @@ mod tests {
         assert_eq!(reason, "Marked bad manually through RPC API");
     }
 
+    #[tokio::test]
+    async fn chain_sync_status_test() {
+        let (ctx, _) = ctx();
+
+        let status = ChainSyncStatus::handle(ctx.clone(), ()).await.unwrap();
+        assert!(!status.synced);
+        assert_eq!(status.actual_epoch, 0);
+        assert_eq!(status.lag_epochs, status.expected_epoch);
+
+        // pretend the node has caught up with the network
+        ctx.sync_state.write().set_epoch(status.expected_epoch);
+
+        let status = ChainSyncStatus::handle(ctx.clone(), ()).await.unwrap();
+        assert!(status.synced);
+        assert!(status.lag_epochs <= SyncHealthStatus::MAX_LAG_EPOCHS);
+    }
+
     #[tokio::test]
     async fn sync_state_test() {
         let (ctx, _) = ctx();
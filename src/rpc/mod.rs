@@ -108,9 +108,13 @@ macro_rules! for_each_method {
 
         // gas vertical
         $callback!(crate::rpc::gas::GasEstimateFeeCap);
+        $callback!(crate::rpc::gas::ForestGasEstimateFeeCap);
         $callback!(crate::rpc::gas::GasEstimateGasLimit);
         $callback!(crate::rpc::gas::GasEstimateGasPremium);
+        $callback!(crate::rpc::gas::GasEstimateGasPremiumPercentile);
         $callback!(crate::rpc::gas::GasEstimateMessageGas);
+        $callback!(crate::rpc::gas::GasEstimateMessageGasBatch);
+        $callback!(crate::rpc::gas::GasGetBaseFee);
 
         // market vertical
         $callback!(crate::rpc::market::MarketAddBalance);
@@ -134,6 +138,7 @@ macro_rules! for_each_method {
         $callback!(crate::rpc::msig::MsigGetPending);
         $callback!(crate::rpc::msig::MsigGetVested);
         $callback!(crate::rpc::msig::MsigGetVestingSchedule);
+        $callback!(crate::rpc::msig::MsigGetState);
 
         // net vertical
         $callback!(crate::rpc::net::NetAddrsListen);
@@ -170,6 +175,8 @@ macro_rules! for_each_method {
         $callback!(crate::rpc::state::StateGetBeaconEntry);
         $callback!(crate::rpc::state::StateGetClaim);
         $callback!(crate::rpc::state::StateGetClaims);
+        $callback!(crate::rpc::state::StateGetMinerFeeDebt);
+        $callback!(crate::rpc::state::StateGetMinerPenalties);
         $callback!(crate::rpc::state::StateGetNetworkParams);
         $callback!(crate::rpc::state::StateGetRandomnessDigestFromBeacon);
         $callback!(crate::rpc::state::StateGetRandomnessDigestFromTickets);
@@ -202,6 +209,7 @@ macro_rules! for_each_method {
         $callback!(crate::rpc::state::StateMinerSectors);
         $callback!(crate::rpc::state::StateNetworkName);
         $callback!(crate::rpc::state::StateNetworkVersion);
+        $callback!(crate::rpc::state::StatePledgeCollateralForReplacedSector);
         $callback!(crate::rpc::state::StateReadState);
         $callback!(crate::rpc::state::StateReplay);
         $callback!(crate::rpc::state::StateSearchMsg);
@@ -219,6 +227,7 @@ macro_rules! for_each_method {
         $callback!(crate::rpc::state::StateWaitMsgV0);
 
         // sync vertical
+        $callback!(crate::rpc::sync::ChainSyncStatus);
         $callback!(crate::rpc::sync::SyncCheckBad);
         $callback!(crate::rpc::sync::SyncMarkBad);
         $callback!(crate::rpc::sync::SyncState);
@@ -392,6 +401,8 @@ pub struct RPCState<DB> {
     pub state_manager: Arc<crate::state_manager::StateManager<DB>>,
     pub mpool: Arc<crate::message_pool::MessagePool<crate::message_pool::MpoolRpcProvider<DB>>>,
     pub bad_blocks: Arc<crate::chain_sync::BadBlockCache>,
+    /// Messages of recently sampled tipsets, see [`crate::chain::MessageCache`].
+    pub message_cache: crate::chain::MessageCache,
     pub sync_state: Arc<parking_lot::RwLock<crate::chain_sync::SyncState>>,
     pub eth_event_handler: Arc<EthEventHandler>,
     pub sync_network_context: SyncNetworkContext<DB>,
@@ -49,7 +49,15 @@ use std::str::FromStr;
 pub struct MessageSendSpec {
     #[schemars(with = "LotusJson<TokenAmount>")]
     #[serde(with = "crate::lotus_json")]
-    max_fee: TokenAmount,
+    pub max_fee: TokenAmount,
+    /// Upper bound for the estimated fee cap. Forest-specific, ignored by Lotus.
+    #[schemars(with = "LotusJson<Option<TokenAmount>>")]
+    #[serde(
+        with = "crate::lotus_json",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_fee_cap: Option<TokenAmount>,
 }
 
 lotus_json_with_self!(MessageSendSpec);
@@ -79,6 +87,24 @@ pub struct MsigVesting {
 
 lotus_json_with_self!(MsigVesting);
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct MsigState {
+    #[schemars(with = "LotusJson<Vec<Address>>")]
+    #[serde(with = "crate::lotus_json")]
+    pub signers: Vec<Address>,
+    pub num_approvals_threshold: u64,
+    #[serde(rename = "NextTxnID")]
+    pub next_tx_id: i64,
+    #[schemars(with = "LotusJson<TokenAmount>")]
+    #[serde(with = "crate::lotus_json")]
+    pub initial_balance: TokenAmount,
+    pub start_epoch: ChainEpoch,
+    pub unlock_duration: ChainEpoch,
+}
+
+lotus_json_with_self!(MsigState);
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "PascalCase")]
 pub struct ApiDealProposal {
@@ -37,3 +37,71 @@ fn test_api_tipset_key_inner(cids: Vec<Cid>) {
         .unwrap_or_default();
     assert_eq!(cids_from_api_ts, cids);
 }
+
+#[quickcheck]
+fn test_api_tipset_key_json_roundtrip(cids: Vec<Cid>) {
+    assert_api_tipset_key_json_roundtrip(cids)
+}
+
+#[test]
+fn test_api_tipset_key_json_edge_cases() {
+    use crate::utils::multihash::prelude::*;
+
+    // identity multihashes embed raw, non-UTF-8 bytes in the CID
+    let identity = Cid::new_v1(
+        fvm_ipld_encoding::IPLD_RAW,
+        MultihashCode::Identity.digest(&[0xff, 0x00, 0x80, 0xc3, 0x28]),
+    );
+    // CIDv0 is base58 rather than multibase encoded
+    let v0 = Cid::new_v0(MultihashCode::Sha2_256.digest(b"block")).unwrap();
+    for cids in [
+        vec![],
+        vec![Cid::default()],
+        vec![identity],
+        vec![v0],
+        vec![identity, v0, Cid::default()],
+    ] {
+        assert_api_tipset_key_json_roundtrip(cids);
+    }
+}
+
+/// Serializes the tipset key to JSON and back, checking that the CIDs are unchanged, down to the
+/// bytes.
+fn assert_api_tipset_key_json_roundtrip(cids: Vec<Cid>) {
+    let api_ts = ApiTipsetKey(NonEmpty::new(cids.clone()).ok().map(TipsetKey::from));
+    let json = serde_json::to_string(&LotusJson(api_ts.clone())).unwrap();
+    let roundtripped: LotusJson<ApiTipsetKey> = serde_json::from_str(&json).unwrap();
+    let roundtripped = roundtripped.into_inner();
+    assert_eq!(roundtripped, api_ts);
+    let roundtripped_cids = roundtripped
+        .0
+        .map(|ts| ts.into_cids().into_iter().collect::<Vec<Cid>>())
+        .unwrap_or_default();
+    assert_eq!(
+        roundtripped_cids
+            .iter()
+            .map(Cid::to_bytes)
+            .collect::<Vec<_>>(),
+        cids.iter().map(Cid::to_bytes).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_msig_state_lotus_json() {
+    let state = MsigState {
+        signers: vec![Address::new_id(100), Address::new_id(101)],
+        num_approvals_threshold: 2,
+        next_tx_id: 8,
+        initial_balance: TokenAmount::from_atto(1_000),
+        start_epoch: 10,
+        unlock_duration: 100,
+    };
+    let json = serde_json::to_value(state.clone().into_lotus_json()).unwrap();
+    assert_eq!(json["NumApprovalsThreshold"], 2);
+    assert_eq!(json["NextTxnID"], 8);
+    assert_eq!(json["InitialBalance"], "1000");
+    assert_eq!(json["StartEpoch"], 10);
+    assert_eq!(json["UnlockDuration"], 100);
+    assert_eq!(json["Signers"].as_array().map(Vec::len), Some(2));
+    assert_eq!(serde_json::from_value::<MsigState>(json).unwrap(), state);
+}
@@ -47,13 +47,13 @@ impl State {
 
         let int = match self {
             State::V9(state) => state.token.get_balance_opt(store, id),
+            State::V10(state) => state.token.get_balance_opt(store, id),
             State::V11(state) => state.token.get_balance_opt(store, id),
             State::V12(state) => state.token.get_balance_opt(store, id),
             State::V13(state) => state.token.get_balance_opt(store, id),
             State::V14(state) => state.token.get_balance_opt(store, id),
             State::V15(state) => state.token.get_balance_opt(store, id),
             State::V16(state) => state.token.get_balance_opt(store, id),
-            _ => return Err(anyhow!("not supported in actors > v8")),
         }?;
         Ok(int
             .map(|amount| amount.atto().to_owned())
@@ -1,7 +1,9 @@
 // Copyright 2019-2024 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use fvm_shared2::address::Address;
+use crate::shim::actors::convert::{from_address_v2_to_v3, from_address_v2_to_v4};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared2::address::{Address, Protocol};
 use serde::Serialize;
 
 /// Init actor address.
@@ -41,4 +43,99 @@ impl State {
             State::V16(st) => st.network_name,
         }
     }
+
+    /// Resolves `addr` to its ID address using the address map of the init actor, without
+    /// loading the state tree. ID addresses resolve to themselves. Returns `None` if the address
+    /// isn't mapped.
+    pub fn resolve_address<BS: Blockstore>(
+        &self,
+        store: &BS,
+        addr: &Address,
+    ) -> anyhow::Result<Option<Address>> {
+        if addr.protocol() == Protocol::ID {
+            return Ok(Some(*addr));
+        }
+        let id = match self {
+            // the address map of `V0` isn't exposed
+            State::V0(_) => {
+                anyhow::bail!("resolving addresses is not supported for init actor v0")
+            }
+            State::V8(st) => st
+                .resolve_address(store, addr)?
+                .map(|a| a.id())
+                .transpose()?,
+            State::V9(st) => st
+                .resolve_address(store, addr)?
+                .map(|a| a.id())
+                .transpose()?,
+            State::V10(st) => st
+                .resolve_address(store, &from_address_v2_to_v3(*addr))?
+                .map(|a| a.id())
+                .transpose()?,
+            State::V11(st) => st
+                .resolve_address(store, &from_address_v2_to_v3(*addr))?
+                .map(|a| a.id())
+                .transpose()?,
+            State::V12(st) => st
+                .resolve_address(store, &from_address_v2_to_v4(*addr))?
+                .map(|a| a.id())
+                .transpose()?,
+            State::V13(st) => st
+                .resolve_address(store, &from_address_v2_to_v4(*addr))?
+                .map(|a| a.id())
+                .transpose()?,
+            State::V14(st) => st
+                .resolve_address(store, &from_address_v2_to_v4(*addr))?
+                .map(|a| a.id())
+                .transpose()?,
+            State::V15(st) => st
+                .resolve_address(store, &from_address_v2_to_v4(*addr))?
+                .map(|a| a.id())
+                .transpose()?,
+            State::V16(st) => st
+                .resolve_address(store, &from_address_v2_to_v4(*addr))?
+                .map(|a| a.id())
+                .transpose()?,
+        };
+        Ok(id.map(Address::new_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::shim::actors::actor_code;
+    use crate::shim::actors::state_load::InitActorStateLoad as _;
+    use crate::utils::db::CborStoreExt as _;
+    use cid::Cid;
+    use fil_actors_shared::v11::runtime::builtins::Type;
+
+    #[test]
+    fn resolve_address_v16() {
+        let store = MemoryDB::default();
+        let mut st = fil_actor_init_state::v16::State::new(&store, "testnet".into()).unwrap();
+        let robust = Address::new_actor(b"robust");
+        let (id, _) = st
+            .map_addresses_to_id(&store, &from_address_v2_to_v4(robust), None)
+            .unwrap();
+        let state_cid = store.put_cbor_default(&st).unwrap();
+
+        let state = State::load(&store, actor_code(16, Type::Init), state_cid).unwrap();
+        assert!(matches!(state, State::V16(_)));
+        assert_eq!(
+            state.resolve_address(&store, &robust).unwrap(),
+            Some(Address::new_id(id))
+        );
+        assert_eq!(
+            state
+                .resolve_address(&store, &Address::new_actor(b"unknown"))
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            state.resolve_address(&store, &Address::new_id(42)).unwrap(),
+            Some(Address::new_id(42))
+        );
+    }
 }
@@ -3,7 +3,10 @@
 
 use super::*;
 use crate::shim::actors::market::BalanceTable;
+use anyhow::ensure;
+use fil_actors_shared::fvm_ipld_hamt::BytesKey;
 use fvm_ipld_blockstore::Blockstore;
+use num_traits::Zero as _;
 
 impl<BS: Blockstore> BalanceTableExt for BalanceTable<'_, BS> {
     fn for_each<F>(&self, mut f: F) -> anyhow::Result<()>
@@ -46,4 +49,434 @@ impl<BS: Blockstore> BalanceTableExt for BalanceTable<'_, BS> {
         };
         Ok(())
     }
+
+    fn get_entry(&self, address: &Address) -> anyhow::Result<Option<TokenAmount>> {
+        let key = BytesKey(address.to_bytes());
+        let address = fvm_shared4::address::Address::from(address);
+        Ok(match self {
+            Self::V8(t) => t.0.get(&key)?.map(Into::into),
+            Self::V9(t) => t.0.get(&key)?.map(Into::into),
+            Self::V10(t) => t.0.get(&key)?.map(Into::into),
+            Self::V11(t) => t.0.get(&key)?.map(Into::into),
+            Self::V12(t) => t.0.get(&address)?.map(Into::into),
+            Self::V13(t) => t.0.get(&address)?.map(Into::into),
+            Self::V14(t) => t.0.get(&address)?.map(Into::into),
+            Self::V15(t) => t.0.get(&address)?.map(Into::into),
+            Self::V16(t) => t.0.get(&address)?.map(Into::into),
+        })
+    }
+
+    fn add(&mut self, address: &Address, amount: &TokenAmount) -> anyhow::Result<()> {
+        ensure!(
+            !amount.is_negative(),
+            "cannot add negative amount {amount} to the balance of {address}"
+        );
+        let balance = self.get_entry(address)?.unwrap_or_default() + amount;
+        set_balance(self, address, &balance)
+    }
+
+    fn remove(&mut self, address: &Address, amount: &TokenAmount) -> anyhow::Result<()> {
+        ensure!(
+            !amount.is_negative(),
+            "cannot remove negative amount {amount} from the balance of {address}"
+        );
+        let balance = self.get_entry(address)?.unwrap_or_default();
+        ensure!(
+            &balance >= amount,
+            "insufficient balance for {address}: cannot remove {amount} from {balance}"
+        );
+        set_balance(self, address, &(balance - amount))
+    }
+
+    fn retain<F>(&mut self, mut f: F) -> anyhow::Result<()>
+    where
+        F: FnMut(&Address, &TokenAmount) -> bool,
+    {
+        let mut removed = vec![];
+        self.for_each(|address, balance| {
+            if !f(address, balance) {
+                removed.push(*address);
+            }
+            Ok(())
+        })?;
+        for address in &removed {
+            delete_entry(self, address)?;
+        }
+        Ok(())
+    }
+
+    fn for_each_mut<F>(&mut self, mut f: F) -> anyhow::Result<()>
+    where
+        F: FnMut(&Address, &mut TokenAmount) -> anyhow::Result<()>,
+    {
+        let mut updated = vec![];
+        self.for_each(|address, balance| {
+            let mut new_balance = balance.clone();
+            f(address, &mut new_balance)?;
+            ensure!(
+                !new_balance.is_negative(),
+                "cannot set the balance of {address} to negative amount {new_balance}"
+            );
+            if &new_balance != balance {
+                updated.push((*address, new_balance));
+            }
+            Ok(())
+        })?;
+        for (address, balance) in &updated {
+            set_balance(self, address, balance)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<Cid> {
+        Ok(match self {
+            Self::V8(t) => t.0.flush()?,
+            Self::V9(t) => t.0.flush()?,
+            Self::V10(t) => t.0.flush()?,
+            Self::V11(t) => t.0.flush()?,
+            Self::V12(t) => t.0.flush()?,
+            Self::V13(t) => t.0.flush()?,
+            Self::V14(t) => t.0.flush()?,
+            Self::V15(t) => t.0.flush()?,
+            Self::V16(t) => t.0.flush()?,
+        })
+    }
+}
+
+/// Sets the balance of `address`, removing the entry if the balance is zero, like the market
+/// actor does.
+fn set_balance<BS: Blockstore>(
+    table: &mut BalanceTable<'_, BS>,
+    address: &Address,
+    balance: &TokenAmount,
+) -> anyhow::Result<()> {
+    if balance.is_zero() {
+        return delete_entry(table, address);
+    }
+    let key = BytesKey(address.to_bytes());
+    let address = fvm_shared4::address::Address::from(address);
+    match table {
+        BalanceTable::V8(t) => {
+            t.0.set(key, balance.into())?;
+        }
+        BalanceTable::V9(t) => {
+            t.0.set(key, balance.into())?;
+        }
+        BalanceTable::V10(t) => {
+            t.0.set(key, balance.into())?;
+        }
+        BalanceTable::V11(t) => {
+            t.0.set(key, balance.into())?;
+        }
+        BalanceTable::V12(t) => {
+            t.0.set(&address, balance.into())?;
+        }
+        BalanceTable::V13(t) => {
+            t.0.set(&address, balance.into())?;
+        }
+        BalanceTable::V14(t) => {
+            t.0.set(&address, balance.into())?;
+        }
+        BalanceTable::V15(t) => {
+            t.0.set(&address, balance.into())?;
+        }
+        BalanceTable::V16(t) => {
+            t.0.set(&address, balance.into())?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes the entry of `address`, if any.
+fn delete_entry<BS: Blockstore>(
+    table: &mut BalanceTable<'_, BS>,
+    address: &Address,
+) -> anyhow::Result<()> {
+    let key = BytesKey(address.to_bytes());
+    let address = fvm_shared4::address::Address::from(address);
+    match table {
+        BalanceTable::V8(t) => {
+            t.0.delete(&key)?;
+        }
+        BalanceTable::V9(t) => {
+            t.0.delete(&key)?;
+        }
+        BalanceTable::V10(t) => {
+            t.0.delete(&key)?;
+        }
+        BalanceTable::V11(t) => {
+            t.0.delete(&key)?;
+        }
+        BalanceTable::V12(t) => {
+            t.0.delete(&address)?;
+        }
+        BalanceTable::V13(t) => {
+            t.0.delete(&address)?;
+        }
+        BalanceTable::V14(t) => {
+            t.0.delete(&address)?;
+        }
+        BalanceTable::V15(t) => {
+            t.0.delete(&address)?;
+        }
+        BalanceTable::V16(t) => {
+            t.0.delete(&address)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use cid::Cid;
+    use fil_actors_shared::v11::make_empty_map;
+    use quickcheck_macros::quickcheck;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    const BALANCE_TABLE_BITWIDTH: u32 = 6;
+
+    fn balance_tables<'bs>(
+        store: &'bs MemoryDB,
+        root: &Cid,
+    ) -> anyhow::Result<Vec<BalanceTable<'bs, MemoryDB>>> {
+        use fil_actor_market_state::*;
+        Ok(vec![
+            v8::balance_table::BalanceTable::from_root(store, root)?.into(),
+            v9::balance_table::BalanceTable::from_root(store, root)?.into(),
+            v10::balance_table::BalanceTable::from_root(store, root)?.into(),
+            v11::balance_table::BalanceTable::from_root(store, root)?.into(),
+            v12::balance_table::BalanceTable::from_root(store, root, "test")?.into(),
+            v13::balance_table::BalanceTable::from_root(store, root, "test")?.into(),
+            v14::balance_table::BalanceTable::from_root(store, root, "test")?.into(),
+            v15::balance_table::BalanceTable::from_root(store, root, "test")?.into(),
+            v16::balance_table::BalanceTable::from_root(store, root, "test")?.into(),
+        ])
+    }
+
+    #[quickcheck]
+    fn total_matches_for_each(balances: BTreeMap<u64, u64>) -> anyhow::Result<()> {
+        let store = MemoryDB::default();
+        let mut map = make_empty_map(&store, BALANCE_TABLE_BITWIDTH);
+        for (&id, &atto) in &balances {
+            map.set(
+                BytesKey(Address::new_id(id).to_bytes()),
+                fvm_shared3::econ::TokenAmount::from_atto(atto),
+            )?;
+        }
+        let root = map.flush()?;
+        let expected = balances
+            .values()
+            .fold(TokenAmount::default(), |acc, &atto| {
+                acc + TokenAmount::from_atto(atto)
+            });
+
+        for table in balance_tables(&store, &root)? {
+            let mut summed = TokenAmount::default();
+            table.for_each(|_, balance| {
+                summed += balance.clone();
+                Ok(())
+            })?;
+            anyhow::ensure!(summed == expected);
+            anyhow::ensure!(table.total()? == expected);
+        }
+        Ok(())
+    }
+
+    #[quickcheck]
+    fn keys_match_for_each(ids: BTreeSet<u64>) -> anyhow::Result<()> {
+        let store = MemoryDB::default();
+        let mut map = make_empty_map(&store, BALANCE_TABLE_BITWIDTH);
+        for &id in &ids {
+            map.set(
+                BytesKey(Address::new_id(id).to_bytes()),
+                fvm_shared3::econ::TokenAmount::from_atto(id),
+            )?;
+        }
+        let root = map.flush()?;
+
+        for table in balance_tables(&store, &root)? {
+            let mut expected = BTreeSet::new();
+            table.for_each(|address, _| {
+                expected.insert(*address);
+                Ok(())
+            })?;
+            let keys = table.keys()?;
+            anyhow::ensure!(keys.len() == ids.len());
+            anyhow::ensure!(keys.into_iter().collect::<BTreeSet<_>>() == expected);
+        }
+        Ok(())
+    }
+
+    #[quickcheck]
+    fn len_counts_insertions(ids: BTreeSet<u64>) -> anyhow::Result<()> {
+        let store = MemoryDB::default();
+        let root =
+            make_empty_map::<_, fvm_shared3::econ::TokenAmount>(&store, BALANCE_TABLE_BITWIDTH)
+                .flush()?;
+
+        for mut table in balance_tables(&store, &root)? {
+            anyhow::ensure!(table.is_empty()?);
+            for &id in &ids {
+                table.add(&Address::new_id(id), &TokenAmount::from_atto(1))?;
+            }
+            anyhow::ensure!(table.len()? == ids.len() as u64);
+            anyhow::ensure!(table.is_empty()? == ids.is_empty());
+        }
+        Ok(())
+    }
+
+    #[quickcheck]
+    fn retain_non_zero(balances: BTreeMap<u64, u8>) -> anyhow::Result<()> {
+        let store = MemoryDB::default();
+        let mut map = make_empty_map(&store, BALANCE_TABLE_BITWIDTH);
+        let mut non_zero = make_empty_map(&store, BALANCE_TABLE_BITWIDTH);
+        for (&id, &atto) in &balances {
+            // about a third of the entries are zero
+            let balance = fvm_shared3::econ::TokenAmount::from_atto(atto % 3);
+            let key = BytesKey(Address::new_id(id).to_bytes());
+            if !balance.is_zero() {
+                non_zero.set(key.clone(), balance.clone())?;
+            }
+            map.set(key, balance)?;
+        }
+        let root = map.flush()?;
+        let expected_root = non_zero.flush()?;
+
+        for mut table in balance_tables(&store, &root)? {
+            table.retain(|_, balance| !balance.is_zero())?;
+            let mut remaining = BTreeMap::new();
+            table.for_each(|address, balance| {
+                remaining.insert(address.id()?, balance.clone());
+                Ok(())
+            })?;
+            let expected = balances
+                .iter()
+                .filter(|&(_, &atto)| atto % 3 != 0)
+                .map(|(&id, &atto)| (id, TokenAmount::from_atto(atto % 3)))
+                .collect::<BTreeMap<_, _>>();
+            anyhow::ensure!(remaining == expected);
+            anyhow::ensure!(table.flush()? == expected_root);
+        }
+        Ok(())
+    }
+
+    #[quickcheck]
+    fn for_each_mut_halves_balances(balances: BTreeMap<u64, u8>) -> anyhow::Result<()> {
+        let store = MemoryDB::default();
+        let mut map = make_empty_map(&store, BALANCE_TABLE_BITWIDTH);
+        let mut halved = make_empty_map(&store, BALANCE_TABLE_BITWIDTH);
+        // the market actor never stores zero balances
+        for (&id, &atto) in balances.iter().filter(|(_, &atto)| atto != 0) {
+            let key = BytesKey(Address::new_id(id).to_bytes());
+            map.set(key.clone(), fvm_shared3::econ::TokenAmount::from_atto(atto))?;
+            // entries that drop to zero are removed
+            if atto / 2 != 0 {
+                halved.set(key, fvm_shared3::econ::TokenAmount::from_atto(atto / 2))?;
+            }
+        }
+        let root = map.flush()?;
+        let expected_root = halved.flush()?;
+
+        for mut table in balance_tables(&store, &root)? {
+            table.for_each_mut(|_, balance| {
+                *balance = balance.div_floor(2);
+                Ok(())
+            })?;
+            anyhow::ensure!(table.flush()? == expected_root);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn for_each_mut_rejects_negative_balances() {
+        let store = MemoryDB::default();
+        let mut map = make_empty_map(&store, BALANCE_TABLE_BITWIDTH);
+        for id in [100, 101] {
+            map.set(
+                BytesKey(Address::new_id(id).to_bytes()),
+                fvm_shared3::econ::TokenAmount::from_atto(10),
+            )
+            .unwrap();
+        }
+        let root = map.flush().unwrap();
+
+        for mut table in balance_tables(&store, &root).unwrap() {
+            let error = table
+                .for_each_mut(|address, balance| {
+                    *balance -= TokenAmount::from_atto(address.id()?);
+                    Ok(())
+                })
+                .unwrap_err();
+            assert!(error.to_string().contains("negative"));
+            // nothing is written
+            assert_eq!(table.flush().unwrap(), root);
+        }
+    }
+
+    #[test]
+    fn add_and_remove() {
+        let store = MemoryDB::default();
+        let root =
+            make_empty_map::<_, fvm_shared3::econ::TokenAmount>(&store, BALANCE_TABLE_BITWIDTH)
+                .flush()
+                .unwrap();
+        let address = Address::new_id(100);
+
+        for mut table in balance_tables(&store, &root).unwrap() {
+            table.add(&address, &TokenAmount::from_atto(30)).unwrap();
+            table.add(&address, &TokenAmount::from_atto(12)).unwrap();
+            assert_eq!(
+                table.get_entry(&address).unwrap(),
+                Some(TokenAmount::from_atto(42))
+            );
+
+            let error = table
+                .remove(&address, &TokenAmount::from_atto(43))
+                .unwrap_err();
+            assert!(error.to_string().contains("insufficient balance"));
+            assert!(table.add(&address, &TokenAmount::from_atto(-1)).is_err());
+            assert_eq!(
+                table.get_entry(&address).unwrap(),
+                Some(TokenAmount::from_atto(42))
+            );
+
+            table.remove(&address, &TokenAmount::from_atto(40)).unwrap();
+            assert_eq!(
+                table.get_entry(&address).unwrap(),
+                Some(TokenAmount::from_atto(2))
+            );
+            // empty entries are removed
+            table.remove(&address, &TokenAmount::from_atto(2)).unwrap();
+            assert_eq!(table.get_entry(&address).unwrap(), None);
+            assert_eq!(table.flush().unwrap(), root);
+        }
+    }
+
+    #[test]
+    fn get_entry() {
+        let store = MemoryDB::default();
+        let mut map = make_empty_map(&store, BALANCE_TABLE_BITWIDTH);
+        for (id, atto) in [(100, 42), (101, 0)] {
+            map.set(
+                BytesKey(Address::new_id(id).to_bytes()),
+                fvm_shared3::econ::TokenAmount::from_atto(atto),
+            )
+            .unwrap();
+        }
+        let root = map.flush().unwrap();
+
+        for table in balance_tables(&store, &root).unwrap() {
+            assert_eq!(
+                table.get_entry(&Address::new_id(100)).unwrap(),
+                Some(TokenAmount::from_atto(42))
+            );
+            assert_eq!(
+                table.get_entry(&Address::new_id(101)).unwrap(),
+                Some(TokenAmount::default())
+            );
+            assert_eq!(table.get_entry(&Address::new_id(102)).unwrap(), None);
+        }
+    }
 }
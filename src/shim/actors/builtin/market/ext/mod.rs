@@ -9,6 +9,7 @@ use crate::shim::address::Address;
 use crate::shim::deal::DealID;
 use crate::shim::econ::TokenAmount;
 use ahash::HashMap;
+use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
 
 pub trait MarketStateExt {
@@ -28,4 +29,69 @@ pub trait BalanceTableExt {
     fn for_each<F>(&self, f: F) -> anyhow::Result<()>
     where
         F: FnMut(&Address, &TokenAmount) -> anyhow::Result<()>;
+
+    /// Looks up the balance of a single address. Unlike the inherent `BalanceTable::get`, this
+    /// tells missing entries apart from zero balances.
+    fn get_entry(&self, address: &Address) -> anyhow::Result<Option<TokenAmount>>;
+
+    /// Adds `amount` to the balance of `address`, creating the entry if needed.
+    fn add(&mut self, address: &Address, amount: &TokenAmount) -> anyhow::Result<()>;
+
+    /// Subtracts `amount` from the balance of `address`, failing if the balance is insufficient.
+    /// Entries that drop to zero are removed.
+    fn remove(&mut self, address: &Address, amount: &TokenAmount) -> anyhow::Result<()>;
+
+    /// Removes the entries for which `f` returns `false`. The table can't be modified while it's
+    /// traversed, so the addresses to remove are collected first.
+    fn retain<F>(&mut self, f: F) -> anyhow::Result<()>
+    where
+        F: FnMut(&Address, &TokenAmount) -> bool;
+
+    /// Lets `f` update every balance in place. The balance tables are HAMTs, which can't be
+    /// modified while they're traversed, so the updates are collected first and only written if
+    /// `f` succeeds for every entry and leaves no balance negative. Entries that drop to zero are
+    /// removed.
+    fn for_each_mut<F>(&mut self, f: F) -> anyhow::Result<()>
+    where
+        F: FnMut(&Address, &mut TokenAmount) -> anyhow::Result<()>;
+
+    /// Writes the pending changes to the store and returns the new root of the table.
+    fn flush(&mut self) -> anyhow::Result<Cid>;
+
+    /// Sums all balances in the table.
+    fn total(&self) -> anyhow::Result<TokenAmount> {
+        let mut total = TokenAmount::default();
+        self.for_each(|_, balance| {
+            total += balance.clone();
+            Ok(())
+        })?;
+        Ok(total)
+    }
+
+    /// Lists the addresses in the table. The balance tables are HAMTs, which decode the values
+    /// while iterating anyway, so there is no cheaper key-only walk to delegate to.
+    fn keys(&self) -> anyhow::Result<Vec<Address>> {
+        let mut keys = Vec::new();
+        self.for_each(|address, _| {
+            keys.push(*address);
+            Ok(())
+        })?;
+        Ok(keys)
+    }
+
+    /// Counts the entries in the table. The balance tables of all actor versions are HAMTs,
+    /// which don't keep track of their size, so this walks the whole table.
+    fn len(&self) -> anyhow::Result<u64> {
+        let mut len = 0;
+        self.for_each(|_, _| {
+            len += 1;
+            Ok(())
+        })?;
+        Ok(len)
+    }
+
+    /// Returns whether the table has no entries.
+    fn is_empty(&self) -> anyhow::Result<bool> {
+        Ok(self.len()? == 0)
+    }
 }
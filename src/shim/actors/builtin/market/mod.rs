@@ -44,7 +44,9 @@ use fil_actors_shared::v16::AsActorError as V16AsActorError;
 use fil_actors_shared::v9::AsActorError as V9AsActorError;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_shared2::error::ExitCode as FVMExitCode;
-use fvm_shared2::{address::Address, clock::ChainEpoch, econ::TokenAmount, piece::PaddedPieceSize};
+use fvm_shared2::{
+    address::Address, clock::ChainEpoch, deal::DealID, econ::TokenAmount, piece::PaddedPieceSize,
+};
 use fvm_shared3::error::ExitCode as FVM3ExitCode;
 use fvm_shared4::error::ExitCode as FVM4ExitCode;
 use serde::{Deserialize, Serialize};
@@ -200,6 +202,22 @@ impl State {
         }
     }
 
+    /// Iterates over pending deals, i.e. published deal proposals that have
+    /// no deal state yet because they haven't been activated in a sector.
+    pub fn for_each_pending_deal<BS, F>(&self, store: &BS, mut f: F) -> anyhow::Result<()>
+    where
+        BS: Blockstore,
+        F: FnMut(DealID, &DealProposal) -> anyhow::Result<()>,
+    {
+        let states = self.states(store)?;
+        self.proposals(store)?.for_each(|deal_id, proposal| {
+            if states.get(deal_id)?.is_some() {
+                return Ok(());
+            }
+            f(deal_id, &proposal?)
+        })
+    }
+
     /// Consume state to return just total funds locked
     pub fn total_locked(&self) -> TokenAmount {
         match self {
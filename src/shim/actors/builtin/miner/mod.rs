@@ -149,6 +149,11 @@ impl State {
         }
     }
 
+    /// Loads all sectors of the miner.
+    pub fn sectors<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<Vec<SectorOnChainInfo>> {
+        self.load_sectors(store, None)
+    }
+
     /// Loads sectors corresponding to the bitfield. If no bitfield is passed
     /// in, return all.
     pub fn load_sectors<BS: Blockstore>(
@@ -348,6 +353,51 @@ impl State {
         }
     }
 
+    /// Gets the total initial pledge locked by the miner
+    pub fn initial_pledge(&self) -> TokenAmount {
+        match self {
+            State::V8(st) => st.initial_pledge.clone(),
+            State::V9(st) => st.initial_pledge.clone(),
+            State::V10(st) => from_token_v3_to_v2(&st.initial_pledge),
+            State::V11(st) => from_token_v3_to_v2(&st.initial_pledge),
+            State::V12(st) => from_token_v4_to_v2(&st.initial_pledge),
+            State::V13(st) => from_token_v4_to_v2(&st.initial_pledge),
+            State::V14(st) => from_token_v4_to_v2(&st.initial_pledge),
+            State::V15(st) => from_token_v4_to_v2(&st.initial_pledge),
+            State::V16(st) => from_token_v4_to_v2(&st.initial_pledge),
+        }
+    }
+
+    /// Gets the total of pre-commit deposits locked by the miner
+    pub fn pre_commit_deposits(&self) -> TokenAmount {
+        match self {
+            State::V8(st) => st.pre_commit_deposits.clone(),
+            State::V9(st) => st.pre_commit_deposits.clone(),
+            State::V10(st) => from_token_v3_to_v2(&st.pre_commit_deposits),
+            State::V11(st) => from_token_v3_to_v2(&st.pre_commit_deposits),
+            State::V12(st) => from_token_v4_to_v2(&st.pre_commit_deposits),
+            State::V13(st) => from_token_v4_to_v2(&st.pre_commit_deposits),
+            State::V14(st) => from_token_v4_to_v2(&st.pre_commit_deposits),
+            State::V15(st) => from_token_v4_to_v2(&st.pre_commit_deposits),
+            State::V16(st) => from_token_v4_to_v2(&st.pre_commit_deposits),
+        }
+    }
+
+    /// Gets the total of funds locked in the vesting table of the miner
+    pub fn locked_funds(&self) -> TokenAmount {
+        match self {
+            State::V8(st) => st.locked_funds.clone(),
+            State::V9(st) => st.locked_funds.clone(),
+            State::V10(st) => from_token_v3_to_v2(&st.locked_funds),
+            State::V11(st) => from_token_v3_to_v2(&st.locked_funds),
+            State::V12(st) => from_token_v4_to_v2(&st.locked_funds),
+            State::V13(st) => from_token_v4_to_v2(&st.locked_funds),
+            State::V14(st) => from_token_v4_to_v2(&st.locked_funds),
+            State::V15(st) => from_token_v4_to_v2(&st.locked_funds),
+            State::V16(st) => from_token_v4_to_v2(&st.locked_funds),
+        }
+    }
+
     /// Unclaimed funds. Actor balance - (locked funds, precommit deposit, ip requirement) Can go negative if the miner is in IP debt.
     pub fn available_balance(&self, balance: &BigInt) -> anyhow::Result<TokenAmount> {
         let balance: TokenAmount = TokenAmount::from_atto(balance.clone());
@@ -1652,3 +1702,55 @@ impl From<fil_actor_miner_state::v16::DeadlineInfo> for DeadlineInfo {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use fil_actor_miner_state::v16::SectorOnChainInfo as V16SectorOnChainInfo;
+
+    #[test]
+    fn sectors_v16() {
+        let store = MemoryDB::default();
+        let policy = fil_actors_shared::v16::runtime::Policy::default();
+        let mut st =
+            fil_actor_miner_state::v16::State::new(&policy, &store, Cid::default(), 0, 0).unwrap();
+        st.put_sectors(
+            &store,
+            [3, 1, 7]
+                .into_iter()
+                .map(|sector_number| V16SectorOnChainInfo {
+                    sector_number,
+                    seal_proof: fvm_shared4::sector::RegisteredSealProof::StackedDRG32GiBV1P1,
+                    sealed_cid: Cid::default(),
+                    deprecated_deal_ids: vec![sector_number * 10],
+                    activation: 100,
+                    expiration: 100 + sector_number as ChainEpoch,
+                    ..Default::default()
+                })
+                .collect(),
+        )
+        .unwrap();
+        let state = State::V16(st);
+
+        let sectors = state.sectors(&store).unwrap();
+        assert_eq!(
+            sectors.iter().map(|s| s.sector_number).collect::<Vec<_>>(),
+            vec![1, 3, 7]
+        );
+        for sector in &sectors {
+            assert_eq!(sector.seal_proof, RegisteredSealProof::StackedDRG32GiBV1P1);
+            assert_eq!(sector.deal_ids, vec![sector.sector_number * 10]);
+            assert_eq!(sector.activation, 100);
+            assert_eq!(sector.expiration, 100 + sector.sector_number as ChainEpoch);
+        }
+
+        let selected = state
+            .load_sectors(&store, Some(&BitField::try_from_bits([3, 7]).unwrap()))
+            .unwrap();
+        assert_eq!(
+            selected.iter().map(|s| s.sector_number).collect::<Vec<_>>(),
+            vec![3, 7]
+        );
+    }
+}
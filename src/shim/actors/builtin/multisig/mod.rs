@@ -4,11 +4,16 @@
 pub mod ext;
 
 use crate::shim::actors::convert::{
-    from_address_v3_to_v2, from_address_v4_to_v2, from_token_v3_to_v2, from_token_v4_to_v2,
+    from_address_v2_to_v4, from_address_v3_to_v2, from_address_v4_to_v2, from_token_v2_to_v4,
+    from_token_v3_to_v2, from_token_v4_to_v2,
 };
+use crate::shim::state_tree::StateTree;
+use anyhow::Context as _;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::RawBytes;
 use fvm_shared2::{address::Address, clock::ChainEpoch, econ::TokenAmount, MethodNum};
+use itertools::Itertools;
+use num_traits::Zero as _;
 use serde::{Deserialize, Serialize};
 
 /// Multisig actor method.
@@ -40,6 +45,17 @@ pub struct Transaction {
     pub approved: Vec<Address>,
 }
 
+/// Linear vesting of the initial balance of a multisig wallet, see [`State::vesting_schedule`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VestingSchedule {
+    /// Epoch the vesting starts at
+    pub start_epoch: ChainEpoch,
+    /// Number of epochs until the whole initial balance has vested
+    pub unlock_duration: ChainEpoch,
+    /// Balance locked at the start epoch
+    pub initial_balance: TokenAmount,
+}
+
 impl State {
     /// Returns amount locked in multisig contract
     pub fn locked_balance(&self, height: ChainEpoch) -> anyhow::Result<TokenAmount> {
@@ -56,6 +72,285 @@ impl State {
         })
     }
 
+    /// Returns the part of the initial balance that has vested by `current_epoch`, i.e. the
+    /// initial balance minus [`State::locked_balance`]. Wallets without a vesting schedule are
+    /// fully unlocked.
+    pub fn unlock_balance(&self, current_epoch: ChainEpoch) -> anyhow::Result<TokenAmount> {
+        let initial_balance = match self {
+            State::V8(st) => st.initial_balance.clone(),
+            State::V9(st) => st.initial_balance.clone(),
+            State::V10(st) => from_token_v3_to_v2(&st.initial_balance),
+            State::V11(st) => from_token_v3_to_v2(&st.initial_balance),
+            State::V12(st) => from_token_v4_to_v2(&st.initial_balance),
+            State::V13(st) => from_token_v4_to_v2(&st.initial_balance),
+            State::V14(st) => from_token_v4_to_v2(&st.initial_balance),
+            State::V15(st) => from_token_v4_to_v2(&st.initial_balance),
+            State::V16(st) => from_token_v4_to_v2(&st.initial_balance),
+        };
+        Ok(initial_balance - self.locked_balance(current_epoch)?)
+    }
+
+    /// Returns the vesting schedule of the initial balance, or `None` if nothing is ever locked.
+    pub fn vesting_schedule(&self) -> anyhow::Result<Option<VestingSchedule>> {
+        let (start_epoch, unlock_duration, initial_balance) = match self {
+            State::V8(st) => (
+                st.start_epoch,
+                st.unlock_duration,
+                st.initial_balance.clone(),
+            ),
+            State::V9(st) => (
+                st.start_epoch,
+                st.unlock_duration,
+                st.initial_balance.clone(),
+            ),
+            State::V10(st) => (
+                st.start_epoch,
+                st.unlock_duration,
+                from_token_v3_to_v2(&st.initial_balance),
+            ),
+            State::V11(st) => (
+                st.start_epoch,
+                st.unlock_duration,
+                from_token_v3_to_v2(&st.initial_balance),
+            ),
+            State::V12(st) => (
+                st.start_epoch,
+                st.unlock_duration,
+                from_token_v4_to_v2(&st.initial_balance),
+            ),
+            State::V13(st) => (
+                st.start_epoch,
+                st.unlock_duration,
+                from_token_v4_to_v2(&st.initial_balance),
+            ),
+            State::V14(st) => (
+                st.start_epoch,
+                st.unlock_duration,
+                from_token_v4_to_v2(&st.initial_balance),
+            ),
+            State::V15(st) => (
+                st.start_epoch,
+                st.unlock_duration,
+                from_token_v4_to_v2(&st.initial_balance),
+            ),
+            State::V16(st) => (
+                st.start_epoch,
+                st.unlock_duration,
+                from_token_v4_to_v2(&st.initial_balance),
+            ),
+        };
+        // the actors don't lock anything without an unlock duration
+        if unlock_duration == 0 || initial_balance.is_zero() {
+            return Ok(None);
+        }
+        Ok(Some(VestingSchedule {
+            start_epoch,
+            unlock_duration,
+            initial_balance,
+        }))
+    }
+
+    /// Returns the addresses allowed to approve transactions
+    pub fn signers(&self) -> Vec<Address> {
+        match self {
+            State::V8(st) => st.signers.clone(),
+            State::V9(st) => st.signers.clone(),
+            State::V10(st) => st
+                .signers
+                .iter()
+                .cloned()
+                .map(from_address_v3_to_v2)
+                .collect(),
+            State::V11(st) => st
+                .signers
+                .iter()
+                .cloned()
+                .map(from_address_v3_to_v2)
+                .collect(),
+            State::V12(st) => st
+                .signers
+                .iter()
+                .cloned()
+                .map(from_address_v4_to_v2)
+                .collect(),
+            State::V13(st) => st
+                .signers
+                .iter()
+                .cloned()
+                .map(from_address_v4_to_v2)
+                .collect(),
+            State::V14(st) => st
+                .signers
+                .iter()
+                .cloned()
+                .map(from_address_v4_to_v2)
+                .collect(),
+            State::V15(st) => st
+                .signers
+                .iter()
+                .cloned()
+                .map(from_address_v4_to_v2)
+                .collect(),
+            State::V16(st) => st
+                .signers
+                .iter()
+                .cloned()
+                .map(from_address_v4_to_v2)
+                .collect(),
+        }
+    }
+
+    /// Returns whether `addr` is one of the signers. The signers are kept as ID addresses, so
+    /// other addresses are resolved to their actor ID through `state_tree` first. Addresses
+    /// unknown to the state tree are not signers.
+    pub fn is_signer<BS: Blockstore>(
+        &self,
+        state_tree: &StateTree<BS>,
+        addr: &Address,
+    ) -> anyhow::Result<bool> {
+        let signers = self.signers();
+        if signers.contains(addr) {
+            return Ok(true);
+        }
+        let Some(id) = state_tree.lookup_id(&addr.into())? else {
+            return Ok(false);
+        };
+        for signer in signers {
+            let signer_id = match signer.id() {
+                Ok(signer_id) => Some(signer_id),
+                Err(_) => state_tree.lookup_id(&signer.into())?,
+            };
+            if signer_id == Some(id) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns the number of approvals required to execute a transaction
+    pub fn threshold(&self) -> u64 {
+        match self {
+            State::V8(st) => st.num_approvals_threshold,
+            State::V9(st) => st.num_approvals_threshold,
+            State::V10(st) => st.num_approvals_threshold,
+            State::V11(st) => st.num_approvals_threshold,
+            State::V12(st) => st.num_approvals_threshold,
+            State::V13(st) => st.num_approvals_threshold,
+            State::V14(st) => st.num_approvals_threshold,
+            State::V15(st) => st.num_approvals_threshold,
+            State::V16(st) => st.num_approvals_threshold,
+        }
+    }
+
+    /// Returns the ID that will be assigned to the next proposed transaction
+    pub fn next_tx_id(&self) -> i64 {
+        match self {
+            State::V8(st) => st.next_tx_id.0,
+            State::V9(st) => st.next_tx_id.0,
+            State::V10(st) => st.next_tx_id.0,
+            State::V11(st) => st.next_tx_id.0,
+            State::V12(st) => st.next_tx_id.0,
+            State::V13(st) => st.next_tx_id.0,
+            State::V14(st) => st.next_tx_id.0,
+            State::V15(st) => st.next_tx_id.0,
+            State::V16(st) => st.next_tx_id.0,
+        }
+    }
+
+    /// Returns the number of pending transactions of the multisig wallet.
+    ///
+    /// The pending transactions are kept in a HAMT for all actor versions, which doesn't track
+    /// its size, so this is still linear in the number of transactions. It is cheaper than
+    /// [`State::get_pending_txn`] though, as the transactions aren't converted and collected.
+    pub fn pending_count<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<u64> {
+        let mut count = 0;
+        match self {
+            State::V8(st) => fil_actors_shared::v8::make_map_with_root::<
+                BS,
+                fil_actor_multisig_state::v8::Transaction,
+            >(&st.pending_txs, store)?
+            .for_each(|_, _| {
+                count += 1;
+                anyhow::Ok(())
+            })?,
+            State::V9(st) => fil_actors_shared::v9::make_map_with_root::<
+                BS,
+                fil_actor_multisig_state::v9::Transaction,
+            >(&st.pending_txs, store)?
+            .for_each(|_, _| {
+                count += 1;
+                anyhow::Ok(())
+            })?,
+            State::V10(st) => fil_actors_shared::v10::make_map_with_root::<
+                BS,
+                fil_actor_multisig_state::v10::Transaction,
+            >(&st.pending_txs, store)?
+            .for_each(|_, _| {
+                count += 1;
+                anyhow::Ok(())
+            })?,
+            State::V11(st) => fil_actors_shared::v11::make_map_with_root::<
+                BS,
+                fil_actor_multisig_state::v11::Transaction,
+            >(&st.pending_txs, store)?
+            .for_each(|_, _| {
+                count += 1;
+                anyhow::Ok(())
+            })?,
+            State::V12(st) => fil_actor_multisig_state::v12::PendingTxnMap::load(
+                store,
+                &st.pending_txs,
+                fil_actor_multisig_state::v12::PENDING_TXN_CONFIG,
+                "pending txns",
+            )?
+            .for_each(|_, _| {
+                count += 1;
+                Ok(())
+            })?,
+            State::V13(st) => fil_actor_multisig_state::v13::PendingTxnMap::load(
+                store,
+                &st.pending_txs,
+                fil_actor_multisig_state::v13::PENDING_TXN_CONFIG,
+                "pending txns",
+            )?
+            .for_each(|_, _| {
+                count += 1;
+                Ok(())
+            })?,
+            State::V14(st) => fil_actor_multisig_state::v14::PendingTxnMap::load(
+                store,
+                &st.pending_txs,
+                fil_actor_multisig_state::v14::PENDING_TXN_CONFIG,
+                "pending txns",
+            )?
+            .for_each(|_, _| {
+                count += 1;
+                Ok(())
+            })?,
+            State::V15(st) => fil_actor_multisig_state::v15::PendingTxnMap::load(
+                store,
+                &st.pending_txs,
+                fil_actor_multisig_state::v15::PENDING_TXN_CONFIG,
+                "pending txns",
+            )?
+            .for_each(|_, _| {
+                count += 1;
+                Ok(())
+            })?,
+            State::V16(st) => fil_actor_multisig_state::v16::PendingTxnMap::load(
+                store,
+                &st.pending_txs,
+                fil_actor_multisig_state::v16::PENDING_TXN_CONFIG,
+                "pending txns",
+            )?
+            .for_each(|_, _| {
+                count += 1;
+                Ok(())
+            })?,
+        }
+        Ok(count)
+    }
+
     /// Returns pending transactions for the given multisig wallet
     pub fn get_pending_txn<BS: Blockstore>(&self, store: &BS) -> anyhow::Result<Vec<Transaction>> {
         let mut res = Vec::new();
@@ -98,8 +393,7 @@ impl State {
                     &st.pending_txs,
                     fil_actor_multisig_state::v12::PENDING_TXN_CONFIG,
                     "pending txns",
-                )
-                .expect("Could not load pending transactions");
+                )?;
                 crate::parse_pending_transactions_v4!(res, txns);
                 Ok(res)
             }
@@ -109,8 +403,7 @@ impl State {
                     &st.pending_txs,
                     fil_actor_multisig_state::v13::PENDING_TXN_CONFIG,
                     "pending txns",
-                )
-                .expect("Could not load pending transactions");
+                )?;
                 crate::parse_pending_transactions_v4!(res, txns);
                 Ok(res)
             }
@@ -120,8 +413,7 @@ impl State {
                     &st.pending_txs,
                     fil_actor_multisig_state::v14::PENDING_TXN_CONFIG,
                     "pending txns",
-                )
-                .expect("Could not load pending transactions");
+                )?;
                 crate::parse_pending_transactions_v4!(res, txns);
                 Ok(res)
             }
@@ -131,8 +423,7 @@ impl State {
                     &st.pending_txs,
                     fil_actor_multisig_state::v15::PENDING_TXN_CONFIG,
                     "pending txns",
-                )
-                .expect("Could not load pending transactions");
+                )?;
                 crate::parse_pending_transactions_v4!(res, txns);
                 Ok(res)
             }
@@ -142,11 +433,572 @@ impl State {
                     &st.pending_txs,
                     fil_actor_multisig_state::v16::PENDING_TXN_CONFIG,
                     "pending txns",
-                )
-                .expect("Could not load pending transactions");
+                )?;
                 crate::parse_pending_transactions_v4!(res, txns);
                 Ok(res)
             }
         }
     }
 }
+
+/// Encodes the parameters of a [`Method::Propose`] message, proposing to send `value` to `to`
+/// and invoke `method` with `params`.
+pub fn propose_message_params(
+    to: &Address,
+    value: TokenAmount,
+    method: MethodNum,
+    params: RawBytes,
+) -> anyhow::Result<RawBytes> {
+    Ok(RawBytes::serialize(
+        fil_actor_multisig_state::v16::ProposeParams {
+            to: from_address_v2_to_v4(*to),
+            value: from_token_v2_to_v4(&value),
+            method,
+            params,
+        },
+    )?)
+}
+
+/// Encodes the parameters of a [`Method::Approve`] message for transaction `tx_id`. The
+/// approval only succeeds if `proposal_hash`, when given, matches that of the transaction.
+pub fn approve_message_params(
+    tx_id: i64,
+    proposal_hash: Option<[u8; 32]>,
+) -> anyhow::Result<RawBytes> {
+    Ok(RawBytes::serialize(
+        fil_actor_multisig_state::v16::TxnIDParams {
+            id: fil_actor_multisig_state::v16::TxnID(tx_id),
+            proposal_hash: proposal_hash.map(Vec::from).unwrap_or_default(),
+        },
+    )?)
+}
+
+/// Encodes the parameters of one [`Method::Approve`] message per `(tx_id, proposal_hash)` in
+/// `approvals`, see [`approve_message_params`]. The actor has no batch approval, so each must be
+/// submitted as its own message. Transactions are executed once approved by enough signers, so
+/// the approvals must be sorted by increasing `tx_id`, and submitted in that order, for them to
+/// execute in the order they were proposed in. Duplicate transactions are rejected.
+pub fn batch_approve_params(
+    approvals: &[(i64, Option<[u8; 32]>)],
+) -> anyhow::Result<Vec<RawBytes>> {
+    if let Some(((prev, _), (next, _))) = approvals
+        .iter()
+        .tuple_windows()
+        .find(|((prev, _), (next, _))| prev >= next)
+    {
+        anyhow::bail!(
+            "approvals must be sorted by increasing transaction ID, got {prev} before {next}"
+        );
+    }
+    approvals
+        .iter()
+        .map(|&(tx_id, proposal_hash)| approve_message_params(tx_id, proposal_hash))
+        .collect()
+}
+
+/// Computes the hash of a pending transaction that [`approve_message_params`] can be given. As in
+/// Lotus, this is the Blake2b-256 hash of the CBOR-encoded requester, who is the first approver,
+/// recipient, value, method and parameters of the transaction.
+pub fn compute_proposal_hash(tx: &Transaction) -> anyhow::Result<[u8; 32]> {
+    let requester = tx
+        .approved
+        .first()
+        .context("transaction has no approvals, not even from its proposer")?;
+    let data = fvm_ipld_encoding::to_vec(&(requester, &tx.to, &tx.value, tx.method, &tx.params))?;
+    Ok(crate::utils::encoding::blake2b_256(&data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::shim::actors::actor_code;
+    use crate::shim::actors::state_load::MultisigActorStateLoad as _;
+    use crate::utils::db::CborStoreExt as _;
+    use cid::Cid;
+    use fil_actors_shared::v11::runtime::builtins::Type;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn load_v16_roundtrip() {
+        use fil_actor_multisig_state::v16::{PendingTxnMap, TxnID, PENDING_TXN_CONFIG};
+
+        let store = MemoryDB::default();
+        let pending_txs = PendingTxnMap::empty(&store, PENDING_TXN_CONFIG, "pending txns")
+            .flush()
+            .unwrap();
+        let signers = vec![
+            fvm_shared4::address::Address::new_id(100),
+            fvm_shared4::address::Address::new_id(101),
+        ];
+        let state_cid = store
+            .put_cbor_default(&fil_actor_multisig_state::v16::State {
+                signers: signers.clone(),
+                num_approvals_threshold: 2,
+                next_tx_id: TxnID(0),
+                initial_balance: fvm_shared4::econ::TokenAmount::from_atto(1_000),
+                start_epoch: 10,
+                unlock_duration: 100,
+                pending_txs,
+            })
+            .unwrap();
+
+        let state = State::load(&store, actor_code(16, Type::Multisig), state_cid).unwrap();
+        assert!(matches!(state, State::V16(_)));
+        assert_eq!(
+            state.signers(),
+            vec![Address::new_id(100), Address::new_id(101)]
+        );
+        assert_eq!(state.threshold(), 2);
+        assert_eq!(state.next_tx_id(), 0);
+        assert!(state.get_pending_txn(&store).unwrap().is_empty());
+        // half of the initial balance is still locked halfway through the vesting period
+        assert_eq!(
+            state.locked_balance(60).unwrap(),
+            TokenAmount::from_atto(500)
+        );
+
+        // unknown actor code
+        assert!(State::load(&store, state_cid, state_cid).is_err());
+    }
+
+    #[test]
+    fn unlock_balance_vesting() {
+        use fil_actor_multisig_state::v16::{PendingTxnMap, TxnID, PENDING_TXN_CONFIG};
+
+        let store = MemoryDB::default();
+        let pending_txs = PendingTxnMap::empty(&store, PENDING_TXN_CONFIG, "pending txns")
+            .flush()
+            .unwrap();
+        let state = |initial_balance: u64, unlock_duration: ChainEpoch| {
+            State::V16(fil_actor_multisig_state::v16::State {
+                signers: vec![],
+                num_approvals_threshold: 1,
+                next_tx_id: TxnID(0),
+                initial_balance: fvm_shared4::econ::TokenAmount::from_atto(initial_balance),
+                start_epoch: 10,
+                unlock_duration,
+                pending_txs,
+            })
+        };
+
+        let vesting = state(1_000, 100);
+        for (epoch, unlocked) in [
+            (0, 0),
+            (10, 0),
+            (35, 250),
+            (60, 500),
+            (110, 1_000),
+            (500, 1_000),
+        ] {
+            assert_eq!(
+                vesting.unlock_balance(epoch).unwrap(),
+                TokenAmount::from_atto(unlocked),
+                "epoch {epoch}"
+            );
+        }
+        // the locked amount is rounded up, like in Lotus
+        assert_eq!(
+            state(1_001, 100).unlock_balance(43).unwrap(),
+            TokenAmount::from_atto(330)
+        );
+        // no vesting schedule
+        assert_eq!(
+            state(1_000, 0).unlock_balance(10).unwrap(),
+            TokenAmount::from_atto(1_000)
+        );
+    }
+
+    #[test]
+    fn vesting_schedule_matches_unlock_balance() {
+        let states = |initial_balance: u64, start_epoch, unlock_duration| {
+            [
+                State::V11(fil_actor_multisig_state::v11::State {
+                    signers: vec![],
+                    num_approvals_threshold: 1,
+                    next_tx_id: fil_actor_multisig_state::v11::TxnID(0),
+                    initial_balance: fvm_shared3::econ::TokenAmount::from_atto(initial_balance),
+                    start_epoch,
+                    unlock_duration,
+                    pending_txs: Cid::default(),
+                }),
+                State::V16(fil_actor_multisig_state::v16::State {
+                    signers: vec![],
+                    num_approvals_threshold: 1,
+                    next_tx_id: fil_actor_multisig_state::v16::TxnID(0),
+                    initial_balance: fvm_shared4::econ::TokenAmount::from_atto(initial_balance),
+                    start_epoch,
+                    unlock_duration,
+                    pending_txs: Cid::default(),
+                }),
+            ]
+        };
+
+        for state in states(1_001, 10, 100)
+            .into_iter()
+            .chain(states(7, 1_000, 3))
+        {
+            let schedule = state.vesting_schedule().unwrap().unwrap();
+            let start = schedule.start_epoch;
+            let end = start + schedule.unlock_duration;
+            for epoch in [
+                0,
+                start - 1,
+                start,
+                start + 1,
+                (start + end) / 2,
+                end - 1,
+                end,
+                end + 1,
+            ] {
+                let elapsed = (epoch - start).clamp(0, schedule.unlock_duration);
+                // the locked part is rounded up
+                let locked = (schedule.initial_balance.atto()
+                    * (schedule.unlock_duration - elapsed)
+                    + schedule.unlock_duration
+                    - 1)
+                    / schedule.unlock_duration;
+                assert_eq!(
+                    state.unlock_balance(epoch).unwrap(),
+                    TokenAmount::from_atto(schedule.initial_balance.atto() - locked),
+                    "{state:?} at epoch {epoch}"
+                );
+            }
+        }
+        assert_eq!(
+            states(1_001, 10, 100)[1].vesting_schedule().unwrap(),
+            Some(VestingSchedule {
+                start_epoch: 10,
+                unlock_duration: 100,
+                initial_balance: TokenAmount::from_atto(1_001),
+            })
+        );
+
+        // fully unlocked
+        for state in states(1_000, 10, 0).into_iter().chain(states(0, 10, 100)) {
+            assert_eq!(state.vesting_schedule().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn message_params_roundtrip() {
+        let params = propose_message_params(
+            &Address::new_id(200),
+            TokenAmount::from_atto(42),
+            3,
+            RawBytes::new(vec![1, 2, 3]),
+        )
+        .unwrap();
+        // the encoding is the same for all actor versions
+        let decoded: fil_actor_multisig_state::v8::ProposeParams =
+            fvm_ipld_encoding::from_slice(params.bytes()).unwrap();
+        assert_eq!(decoded.to, Address::new_id(200));
+        assert_eq!(decoded.value, TokenAmount::from_atto(42));
+        assert_eq!(decoded.method, 3);
+        assert_eq!(decoded.params, RawBytes::new(vec![1, 2, 3]));
+
+        let hash = [7; 32];
+        let params = approve_message_params(5, Some(hash)).unwrap();
+        let decoded: fil_actor_multisig_state::v16::TxnIDParams =
+            fvm_ipld_encoding::from_slice(params.bytes()).unwrap();
+        assert_eq!(decoded.id.0, 5);
+        assert_eq!(decoded.proposal_hash, hash.to_vec());
+
+        let params = approve_message_params(6, None).unwrap();
+        let decoded: fil_actor_multisig_state::v8::TxnIDParams =
+            fvm_ipld_encoding::from_slice(params.bytes()).unwrap();
+        assert_eq!(decoded.id.0, 6);
+        assert!(decoded.proposal_hash.is_empty());
+    }
+
+    #[test]
+    fn batch_approve_params_in_order() {
+        let approvals = [(1, None), (4, Some([4; 32])), (9, Some([9; 32]))];
+        let params = batch_approve_params(&approvals).unwrap();
+        assert_eq!(params.len(), approvals.len());
+        for (params, (tx_id, proposal_hash)) in params.iter().zip(approvals) {
+            let decoded: fil_actor_multisig_state::v16::TxnIDParams =
+                fvm_ipld_encoding::from_slice(params.bytes()).unwrap();
+            assert_eq!(decoded.id.0, tx_id);
+            assert_eq!(
+                decoded.proposal_hash,
+                proposal_hash.map(Vec::from).unwrap_or_default()
+            );
+        }
+
+        assert!(batch_approve_params(&[(4, None), (1, None)]).is_err());
+        assert!(batch_approve_params(&[(4, None), (4, None)]).is_err());
+        assert!(batch_approve_params(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn is_signer_resolves_addresses() {
+        use crate::shim::state_tree::{ActorState, StateTreeVersion};
+        use fvm_shared3::address::Address as AddressV3;
+        use std::sync::Arc;
+
+        let store = Arc::new(MemoryDB::default());
+        let mut state_tree = StateTree::new(store.clone(), StateTreeVersion::V5).unwrap();
+        let mut init_state = fil_actor_init_state::v11::State::new(&store, "test".into()).unwrap();
+        let key = AddressV3::new_secp256k1(&[1; 65]).unwrap();
+        let (key_id, _) = init_state.map_addresses_to_id(&store, &key, None).unwrap();
+        let init_state_cid = store.put_cbor_default(&init_state).unwrap();
+        state_tree
+            .set_actor(
+                &crate::shim::actors::init::ADDRESS.into(),
+                ActorState::new(Cid::default(), init_state_cid, Default::default(), 0, None),
+            )
+            .unwrap();
+
+        let signer_ids = [key_id, key_id + 1];
+        let states = [
+            State::V8(fil_actor_multisig_state::v8::State {
+                signers: signer_ids.iter().map(|&id| Address::new_id(id)).collect(),
+                num_approvals_threshold: 2,
+                next_tx_id: fil_actor_multisig_state::v8::TxnID(0),
+                initial_balance: TokenAmount::default(),
+                start_epoch: 0,
+                unlock_duration: 0,
+                pending_txs: Cid::default(),
+            }),
+            State::V11(fil_actor_multisig_state::v11::State {
+                signers: signer_ids.iter().map(|&id| AddressV3::new_id(id)).collect(),
+                num_approvals_threshold: 2,
+                next_tx_id: fil_actor_multisig_state::v11::TxnID(0),
+                initial_balance: Default::default(),
+                start_epoch: 0,
+                unlock_duration: 0,
+                pending_txs: Cid::default(),
+            }),
+            State::V16(fil_actor_multisig_state::v16::State {
+                signers: signer_ids
+                    .iter()
+                    .map(|&id| fvm_shared4::address::Address::new_id(id))
+                    .collect(),
+                num_approvals_threshold: 2,
+                next_tx_id: fil_actor_multisig_state::v16::TxnID(0),
+                initial_balance: Default::default(),
+                start_epoch: 0,
+                unlock_duration: 0,
+                pending_txs: Cid::default(),
+            }),
+        ];
+        let unknown_key = Address::new_secp256k1(&[2; 65]).unwrap();
+        for state in states {
+            for id in signer_ids {
+                assert!(state.is_signer(&state_tree, &Address::new_id(id)).unwrap());
+            }
+            // the public key address of the first signer
+            assert!(state
+                .is_signer(&state_tree, &Address::from_bytes(&key.to_bytes()).unwrap())
+                .unwrap());
+            assert!(!state
+                .is_signer(&state_tree, &Address::new_id(key_id + 2))
+                .unwrap());
+            assert!(!state.is_signer(&state_tree, &unknown_key).unwrap());
+        }
+    }
+
+    #[test]
+    fn pending_txns_v11() {
+        use fil_actor_multisig_state::v11::{Transaction as TransactionV11, TxnID};
+        use fil_actors_shared::fvm_ipld_hamt::BytesKey;
+        use fil_actors_shared::v11::{builtin::HAMT_BIT_WIDTH, make_empty_map};
+        use fvm_shared3::{address::Address as AddressV3, econ::TokenAmount as TokenAmountV3};
+        use integer_encoding::VarInt as _;
+
+        let store = MemoryDB::default();
+        let mut txns = make_empty_map(&store, HAMT_BIT_WIDTH);
+        txns.set(
+            BytesKey(7i64.encode_var_vec()),
+            TransactionV11 {
+                to: AddressV3::new_id(200),
+                value: TokenAmountV3::from_atto(42),
+                method: 3,
+                params: RawBytes::new(vec![1, 2, 3]),
+                approved: vec![AddressV3::new_id(100)],
+            },
+        )
+        .unwrap();
+        let state_cid = store
+            .put_cbor_default(&fil_actor_multisig_state::v11::State {
+                signers: vec![AddressV3::new_id(100), AddressV3::new_id(101)],
+                num_approvals_threshold: 2,
+                next_tx_id: TxnID(8),
+                initial_balance: TokenAmountV3::from_atto(1_000),
+                start_epoch: 10,
+                unlock_duration: 100,
+                pending_txs: txns.flush().unwrap(),
+            })
+            .unwrap();
+
+        let state = State::load(&store, actor_code(11, Type::Multisig), state_cid).unwrap();
+        assert!(matches!(state, State::V11(_)));
+        assert_eq!(state.next_tx_id(), 8);
+        assert_eq!(
+            state.get_pending_txn(&store).unwrap(),
+            vec![Transaction {
+                id: 7,
+                to: Address::new_id(200),
+                value: TokenAmount::from_atto(42),
+                method: 3,
+                params: RawBytes::new(vec![1, 2, 3]),
+                approved: vec![Address::new_id(100)],
+            }]
+        );
+    }
+
+    #[test]
+    fn pending_txns_v16() {
+        use fil_actor_multisig_state::v16::{
+            PendingTxnMap, Transaction as TransactionV16, TxnID, PENDING_TXN_CONFIG,
+        };
+        use fvm_shared4::{address::Address as AddressV4, econ::TokenAmount as TokenAmountV4};
+
+        let store = MemoryDB::default();
+        let mut txns = PendingTxnMap::empty(&store, PENDING_TXN_CONFIG, "pending txns");
+        for id in [3, 5] {
+            txns.set(
+                &TxnID(id),
+                TransactionV16 {
+                    to: AddressV4::new_id(200 + id as u64),
+                    value: TokenAmountV4::from_atto(id),
+                    method: 0,
+                    params: RawBytes::default(),
+                    approved: vec![AddressV4::new_id(101)],
+                },
+            )
+            .unwrap();
+        }
+        let state_cid = store
+            .put_cbor_default(&fil_actor_multisig_state::v16::State {
+                signers: vec![AddressV4::new_id(100), AddressV4::new_id(101)],
+                num_approvals_threshold: 2,
+                next_tx_id: TxnID(6),
+                initial_balance: TokenAmountV4::from_atto(1_000),
+                start_epoch: 10,
+                unlock_duration: 100,
+                pending_txs: txns.flush().unwrap(),
+            })
+            .unwrap();
+
+        let state = State::load(&store, actor_code(16, Type::Multisig), state_cid).unwrap();
+        let mut pending = state.get_pending_txn(&store).unwrap();
+        pending.sort_by_key(|txn| txn.id);
+        assert_eq!(
+            pending
+                .iter()
+                .map(|txn| (txn.id, txn.to, txn.value.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (3, Address::new_id(203), TokenAmount::from_atto(3)),
+                (5, Address::new_id(205), TokenAmount::from_atto(5)),
+            ]
+        );
+        assert!(pending
+            .iter()
+            .all(|txn| txn.approved == vec![Address::new_id(101)]));
+
+        // a missing pending transactions map is an error rather than a panic
+        let state_cid = store
+            .put_cbor_default(&fil_actor_multisig_state::v16::State {
+                signers: vec![],
+                num_approvals_threshold: 0,
+                next_tx_id: TxnID(0),
+                initial_balance: TokenAmountV4::default(),
+                start_epoch: 0,
+                unlock_duration: 0,
+                pending_txs: state_cid,
+            })
+            .unwrap();
+        let state = State::load(&store, actor_code(16, Type::Multisig), state_cid).unwrap();
+        assert!(state.get_pending_txn(&store).is_err());
+    }
+
+    #[quickcheck]
+    fn pending_count_matches_pending_txns(ids: std::collections::BTreeSet<u8>) {
+        use fil_actors_shared::fvm_ipld_hamt::BytesKey;
+        use fil_actors_shared::v11::{builtin::HAMT_BIT_WIDTH, make_empty_map};
+        use fvm_shared3::address::Address as AddressV3;
+        use fvm_shared4::address::Address as AddressV4;
+        use integer_encoding::VarInt as _;
+
+        let store = MemoryDB::default();
+        let mut txns_v11 = make_empty_map(&store, HAMT_BIT_WIDTH);
+        let mut txns_v16 = fil_actor_multisig_state::v16::PendingTxnMap::empty(
+            &store,
+            fil_actor_multisig_state::v16::PENDING_TXN_CONFIG,
+            "pending txns",
+        );
+        for &id in &ids {
+            txns_v11
+                .set(
+                    BytesKey(i64::from(id).encode_var_vec()),
+                    fil_actor_multisig_state::v11::Transaction {
+                        to: AddressV3::new_id(id.into()),
+                        value: Default::default(),
+                        method: 0,
+                        params: RawBytes::default(),
+                        approved: vec![],
+                    },
+                )
+                .unwrap();
+            txns_v16
+                .set(
+                    &fil_actor_multisig_state::v16::TxnID(id.into()),
+                    fil_actor_multisig_state::v16::Transaction {
+                        to: AddressV4::new_id(id.into()),
+                        value: Default::default(),
+                        method: 0,
+                        params: RawBytes::default(),
+                        approved: vec![],
+                    },
+                )
+                .unwrap();
+        }
+        let states = [
+            State::V11(fil_actor_multisig_state::v11::State {
+                signers: vec![],
+                num_approvals_threshold: 0,
+                next_tx_id: fil_actor_multisig_state::v11::TxnID(0),
+                initial_balance: Default::default(),
+                start_epoch: 0,
+                unlock_duration: 0,
+                pending_txs: txns_v11.flush().unwrap(),
+            }),
+            State::V16(fil_actor_multisig_state::v16::State {
+                signers: vec![],
+                num_approvals_threshold: 0,
+                next_tx_id: fil_actor_multisig_state::v16::TxnID(0),
+                initial_balance: Default::default(),
+                start_epoch: 0,
+                unlock_duration: 0,
+                pending_txs: txns_v16.flush().unwrap(),
+            }),
+        ];
+        for state in states {
+            let count = state.pending_count(&store).unwrap();
+            assert_eq!(count, ids.len() as u64);
+            assert_eq!(count, state.get_pending_txn(&store).unwrap().len() as u64);
+        }
+    }
+
+    #[test]
+    fn proposal_hash_matches_reference() {
+        let mut tx = Transaction {
+            id: 0,
+            to: Address::new_id(102),
+            value: TokenAmount::from_atto(1),
+            method: 2,
+            params: RawBytes::new(vec![0xde, 0xad]),
+            approved: vec![Address::new_id(101), Address::new_id(103)],
+        };
+        // Blake2b-256 of the CBOR array [f0101, f0102, 1 attoFIL, 2, 0xdead]
+        assert_eq!(
+            hex::encode(compute_proposal_hash(&tx).unwrap()),
+            "e643656b58e96fc2576359d9492ba5d8ee632c79bb5b0bb210a916f6c8ea3e8f"
+        );
+        tx.approved.clear();
+        compute_proposal_hash(&tx).unwrap_err();
+    }
+}
@@ -147,6 +147,31 @@ impl State {
         }
     }
 
+    /// Returns the total raw byte power of the network.
+    pub fn total_raw_byte_power(&self) -> StoragePower {
+        self.total_power().raw_byte_power
+    }
+
+    /// Returns the total quality adjusted power of the network.
+    pub fn total_quality_adjusted_power(&self) -> StoragePower {
+        self.total_power().quality_adj_power
+    }
+
+    /// Returns the number of miners registered with the power actor.
+    pub fn miner_count(&self) -> i64 {
+        match self {
+            State::V8(st) => st.miner_count,
+            State::V9(st) => st.miner_count,
+            State::V10(st) => st.miner_count,
+            State::V11(st) => st.miner_count,
+            State::V12(st) => st.miner_count,
+            State::V13(st) => st.miner_count,
+            State::V14(st) => st.miner_count,
+            State::V15(st) => st.miner_count,
+            State::V16(st) => st.miner_count,
+        }
+    }
+
     /// Consume state to return total locked funds
     pub fn into_total_locked(self) -> TokenAmount {
         match self {
@@ -418,3 +443,43 @@ macro_rules! list_miners_for_state {
         Ok(miners)
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::shim::actors::actor_code;
+    use crate::shim::actors::state_load::PowerActorStateLoad as _;
+    use crate::utils::db::CborStoreExt as _;
+    use cid::Cid;
+    use fil_actors_shared::v11::runtime::builtins::Type;
+
+    #[test]
+    fn total_power_all_versions() {
+        let store = MemoryDB::default();
+        let raw_byte_power = StoragePower::from(1_u64 << 60);
+        let quality_adj_power = StoragePower::from(3_u64 << 60);
+        macro_rules! load {
+            ($($version:literal => $module:ident),* $(,)*) => {
+                vec![$({
+                    let mut st = fil_actor_power_state::$module::State::new(&store).unwrap();
+                    st.total_raw_byte_power = raw_byte_power.clone();
+                    st.total_quality_adj_power = quality_adj_power.clone();
+                    st.miner_count = 42;
+                    let state_cid = store.put_cbor_default(&st).unwrap();
+                    State::load(&store, actor_code($version, Type::Power), state_cid).unwrap()
+                }),*]
+            };
+        }
+        let states = load!(
+            8 => v8, 9 => v9, 10 => v10, 11 => v11, 12 => v12,
+            13 => v13, 14 => v14, 15 => v15, 16 => v16,
+        );
+
+        for state in &states {
+            assert_eq!(state.total_raw_byte_power(), raw_byte_power);
+            assert_eq!(state.total_quality_adjusted_power(), quality_adj_power);
+            assert_eq!(state.miner_count(), 42);
+        }
+    }
+}
@@ -20,7 +20,8 @@ use fil_actor_miner_state::v15::initial_pledge_for_power as initial_pledge_for_p
 use fil_actor_miner_state::v16::initial_pledge_for_power as initial_pledge_for_power_v16;
 use fvm_shared2::address::Address;
 use fvm_shared2::bigint::Integer;
-use fvm_shared2::sector::StoragePower;
+use fvm_shared2::clock::ChainEpoch;
+use fvm_shared2::sector::{Spacetime, StoragePower};
 use fvm_shared2::smooth::FilterEstimate;
 use fvm_shared2::{econ::TokenAmount, piece::PaddedPieceSize, TOTAL_FILECOIN};
 use num::BigInt;
@@ -66,6 +67,41 @@ impl State {
         }
     }
 
+    /// Total storage power reward paid out so far.
+    pub fn total_storage_power_reward(&self) -> TokenAmount {
+        match self {
+            State::V8(st) => st.total_storage_power_reward.clone(),
+            State::V9(st) => st.total_storage_power_reward.clone(),
+            State::V10(st) => from_token_v3_to_v2(&st.total_storage_power_reward),
+            State::V11(st) => from_token_v3_to_v2(&st.total_storage_power_reward),
+            State::V12(st) => from_token_v4_to_v2(&st.total_storage_power_reward),
+            State::V13(st) => from_token_v4_to_v2(&st.total_storage_power_reward),
+            State::V14(st) => from_token_v4_to_v2(&st.total_storage_power_reward),
+            State::V15(st) => from_token_v4_to_v2(&st.total_storage_power_reward),
+            State::V16(st) => from_token_v4_to_v2(&st.total_storage_power_reward),
+        }
+    }
+
+    /// Smoothed estimate of the block reward at this state's epoch.
+    pub fn this_epoch_reward_smoothed(&self) -> FilterEstimate {
+        RewardActorState::from(self).this_epoch_reward_smoothed
+    }
+
+    /// Cumulative sum of network power capped by the baseline, in byte-epochs.
+    pub fn cumsum_realized(&self) -> &Spacetime {
+        match self {
+            State::V8(st) => &st.cumsum_realized,
+            State::V9(st) => &st.cumsum_realized,
+            State::V10(st) => &st.cumsum_realized,
+            State::V11(st) => &st.cumsum_realized,
+            State::V12(st) => &st.cumsum_realized,
+            State::V13(st) => &st.cumsum_realized,
+            State::V14(st) => &st.cumsum_realized,
+            State::V15(st) => &st.cumsum_realized,
+            State::V16(st) => &st.cumsum_realized,
+        }
+    }
+
     /// The baseline power the network is targeting at this state's epoch.
     pub fn this_epoch_baseline_power(&self) -> &StoragePower {
         match self {
@@ -382,3 +418,123 @@ impl State {
         }
     }
 }
+
+/// Version-agnostic view of the reward actor state.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RewardActorState {
+    /// Epoch the state was last updated at.
+    pub epoch: ChainEpoch,
+    /// Block reward for this epoch.
+    pub this_epoch_reward: TokenAmount,
+    /// Smoothed estimate of `this_epoch_reward`.
+    pub this_epoch_reward_smoothed: FilterEstimate,
+    /// The baseline power the network is targeting at `epoch`.
+    pub this_epoch_baseline_power: StoragePower,
+    /// Total storage power reward paid out so far.
+    pub total_storage_power_reward: TokenAmount,
+    /// Cumulative sum of the baseline power, in byte-epochs.
+    pub cumsum_baseline: Spacetime,
+    /// Cumulative sum of network power capped by the baseline, in byte-epochs.
+    pub cumsum_realized: Spacetime,
+}
+
+macro_rules! from_reward_state {
+    ($($type:ty),* $(,)*) => {
+        $(
+        impl From<&$type> for RewardActorState {
+            fn from(st: &$type) -> Self {
+                Self {
+                    epoch: st.epoch,
+                    this_epoch_reward: TokenAmount::from_atto(st.this_epoch_reward.atto().clone()),
+                    this_epoch_reward_smoothed: FilterEstimate {
+                        position: st.this_epoch_reward_smoothed.position.clone(),
+                        velocity: st.this_epoch_reward_smoothed.velocity.clone(),
+                    },
+                    this_epoch_baseline_power: st.this_epoch_baseline_power.clone(),
+                    total_storage_power_reward: TokenAmount::from_atto(
+                        st.total_storage_power_reward.atto().clone(),
+                    ),
+                    cumsum_baseline: st.cumsum_baseline.clone(),
+                    cumsum_realized: st.cumsum_realized.clone(),
+                }
+            }
+        }
+        )*
+    };
+}
+
+from_reward_state!(
+    fil_actor_reward_state::v16::State,
+    fil_actor_reward_state::v15::State,
+    fil_actor_reward_state::v14::State,
+    fil_actor_reward_state::v13::State,
+    fil_actor_reward_state::v12::State,
+    fil_actor_reward_state::v11::State,
+    fil_actor_reward_state::v10::State,
+    fil_actor_reward_state::v9::State,
+    fil_actor_reward_state::v8::State,
+);
+
+impl From<&State> for RewardActorState {
+    fn from(state: &State) -> Self {
+        match state {
+            State::V8(st) => st.into(),
+            State::V9(st) => st.into(),
+            State::V10(st) => st.into(),
+            State::V11(st) => st.into(),
+            State::V12(st) => st.into(),
+            State::V13(st) => st.into(),
+            State::V14(st) => st.into(),
+            State::V15(st) => st.into(),
+            State::V16(st) => st.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::shim::actors::actor_code;
+    use crate::shim::actors::state_load::RewardActorStateLoad as _;
+    use crate::utils::db::CborStoreExt as _;
+    use cid::Cid;
+    use fil_actors_shared::v11::runtime::builtins::Type;
+
+    #[test]
+    fn load_all_versions() {
+        let store = MemoryDB::default();
+        let realized_power = StoragePower::from(1_u64 << 50);
+        macro_rules! load {
+            ($($version:literal => $module:ident),* $(,)*) => {
+                vec![$({
+                    let st = fil_actor_reward_state::$module::State::new(realized_power.clone());
+                    let state_cid = store.put_cbor_default(&st).unwrap();
+                    let state = State::load(&store, actor_code($version, Type::Reward), state_cid).unwrap();
+                    assert_eq!(RewardActorState::from(&state), RewardActorState::from(&st));
+                    state
+                }),*]
+            };
+        }
+        let states = load!(
+            8 => v8, 9 => v9, 10 => v10, 11 => v11, 12 => v12,
+            13 => v13, 14 => v14, 15 => v15, 16 => v16,
+        );
+
+        let expected = RewardActorState::from(&states[0]);
+        assert!(expected.this_epoch_reward.is_positive());
+        for state in &states {
+            // the reward calculation is unchanged across versions
+            assert_eq!(RewardActorState::from(state), expected);
+            assert_eq!(
+                state.total_storage_power_reward(),
+                expected.total_storage_power_reward
+            );
+            assert_eq!(
+                state.this_epoch_reward_smoothed(),
+                expected.this_epoch_reward_smoothed
+            );
+            assert_eq!(state.cumsum_realized(), &expected.cumsum_realized);
+        }
+    }
+}
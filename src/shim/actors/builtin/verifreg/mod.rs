@@ -2,6 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 pub mod ext;
+use crate::shim::actors::datacap;
+use crate::shim::actors::state_load::DataCapActorStateLoad as _;
+use crate::shim::state_tree::StateTree;
 use anyhow::anyhow;
 use cid::Cid;
 use fil_actor_verifreg_state::v13::ClaimID;
@@ -28,6 +31,9 @@ use serde::{Deserialize, Serialize};
 /// verifreg actor address.
 pub const ADDRESS: Address = Address::new_id(6);
 
+/// Amount of storage, in bytes, a verified client may still make verified deals for.
+pub type DataCap = BigInt;
+
 /// Verifreg actor state.
 #[derive(Serialize, Debug)]
 #[serde(untagged)]
@@ -69,6 +75,30 @@ impl State {
         }
     }
 
+    /// Returns the remaining DataCap of the verified client `addr`, or `None` if it isn't one.
+    /// From actors v9 on, DataCap is a token of the [`datacap`] actor, whose state is looked up
+    /// in `state_tree`. Addresses other than ID addresses are resolved through `state_tree` as
+    /// well, unknown ones have no DataCap.
+    pub fn data_cap<BS: Blockstore>(
+        &self,
+        state_tree: &StateTree<BS>,
+        addr: &Address,
+    ) -> anyhow::Result<Option<DataCap>> {
+        let Some(id) = state_tree.lookup_id(&addr.into())? else {
+            return Ok(None);
+        };
+        let id = Address::new_id(id);
+        let store = state_tree.store();
+        match self {
+            State::V8(_) => self.verified_client_data_cap(store, id),
+            _ => {
+                let actor = state_tree.get_required_actor(&datacap::ADDRESS.into())?;
+                datacap::State::load(store, actor.code, actor.state)?
+                    .verified_client_data_cap(store, id)
+            }
+        }
+    }
+
     pub fn verifier_data_cap<BS>(&self, store: &BS, addr: Address) -> anyhow::Result<Option<BigInt>>
     where
         BS: Blockstore,
@@ -368,3 +398,121 @@ from_allocation!(fil_actor_verifreg_state::v12::Allocation);
 from_allocation!(fil_actor_verifreg_state::v11::Allocation);
 from_allocation!(fil_actor_verifreg_state::v10::Allocation);
 from_allocation!(fil_actor_verifreg_state::v9::Allocation);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::shim::actors::actor_code;
+    use crate::shim::actors::state_load::VerifregActorStateLoad as _;
+    use crate::shim::state_tree::{ActorState, StateTreeVersion};
+    use crate::utils::db::CborStoreExt as _;
+    use fil_actors_shared::fvm_ipld_hamt::BytesKey;
+    use fil_actors_shared::v11::runtime::builtins::Type;
+    use std::sync::Arc;
+
+    #[test]
+    fn data_cap_v8() {
+        let store = Arc::new(MemoryDB::default());
+        let state_tree = StateTree::new(store.clone(), StateTreeVersion::V4).unwrap();
+        let mut state =
+            fil_actor_verifreg_state::v8::State::new(&store, Address::new_id(80)).unwrap();
+        let mut verified_clients =
+            fil_actors_shared::v8::make_empty_map::<_, crate::shim::bigint::BigInt>(
+                &*store,
+                fil_actors_shared::v8::builtin::HAMT_BIT_WIDTH,
+            );
+        verified_clients
+            .set(Address::new_id(1001).key(), BigInt::from(42).into())
+            .unwrap();
+        state.verified_clients = verified_clients.flush().unwrap();
+        let state_cid = store.put_cbor_default(&state).unwrap();
+
+        let state = State::load(&*store, actor_code(8, Type::VerifiedRegistry), state_cid).unwrap();
+        assert_eq!(
+            state.data_cap(&state_tree, &Address::new_id(1001)).unwrap(),
+            Some(DataCap::from(42))
+        );
+        assert_eq!(
+            state.data_cap(&state_tree, &Address::new_id(1002)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn data_cap_v9_datacap_actor() {
+        use fil_actors_shared::frc46_token::token::state::TokenState;
+
+        let store = Arc::new(MemoryDB::default());
+        let mut state_tree = StateTree::new(store.clone(), StateTreeVersion::V5).unwrap();
+        let init_state = fil_actor_init_state::v11::State::new(&store, "test".into()).unwrap();
+        state_tree
+            .set_actor(
+                &crate::shim::actors::init::ADDRESS.into(),
+                ActorState::new(
+                    Cid::default(),
+                    store.put_cbor_default(&init_state).unwrap(),
+                    Default::default(),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+
+        // since v9, DataCap is a token, with 10^18 units per byte
+        let mut balances = fil_actors_shared::v9::make_empty_map::<_, crate::shim::bigint::BigInt>(
+            &*store,
+            fil_actors_shared::v9::builtin::HAMT_BIT_WIDTH,
+        );
+        balances
+            .set(
+                BytesKey(Address::new_id(1001).payload_bytes()),
+                (BigInt::from(42) * crate::shim::econ::TokenAmount::PRECISION).into(),
+            )
+            .unwrap();
+        let mut token =
+            TokenState::new_with_bit_width(&*store, fil_actors_shared::v9::builtin::HAMT_BIT_WIDTH)
+                .unwrap();
+        token.balances = balances.flush().unwrap();
+        let datacap_state = store
+            .put_cbor_default(&fil_actor_datacap_state::v9::State {
+                governor: ADDRESS,
+                token,
+            })
+            .unwrap();
+        state_tree
+            .set_actor(
+                &datacap::ADDRESS.into(),
+                ActorState::new(
+                    actor_code(9, Type::DataCap),
+                    datacap_state,
+                    Default::default(),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+
+        let state_cid = store
+            .put_cbor_default(
+                &fil_actor_verifreg_state::v9::State::new(&*store, Address::new_id(80)).unwrap(),
+            )
+            .unwrap();
+        let state = State::load(&*store, actor_code(9, Type::VerifiedRegistry), state_cid).unwrap();
+        assert_eq!(
+            state.data_cap(&state_tree, &Address::new_id(1001)).unwrap(),
+            Some(DataCap::from(42))
+        );
+        assert_eq!(
+            state.data_cap(&state_tree, &Address::new_id(1002)).unwrap(),
+            None
+        );
+        // not in the state tree
+        assert_eq!(
+            state
+                .data_cap(&state_tree, &Address::new_secp256k1(&[1; 65]).unwrap())
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -48,6 +48,13 @@ macro_rules! actor_state_load_impl {
                             .context("Actor state doesn't exist in store");
                     }
                 )*
+                // e.g. the bundle of a network upgrade is known before the actor state is
+                if let Some(version) = [< $actor:lower _cid_version >](&code) {
+                    anyhow::bail!(
+                        "{} actor version {version} not yet supported by this Forest build",
+                        stringify!([< $actor:lower >])
+                    );
+                }
                 anyhow::bail!("Unknown actor code {}", code)
             }
         }
@@ -89,3 +96,80 @@ actor_state_load_impl!(
 actor_state_load_impl!(
     Reward, 8, V8, 9, V9, 10, V10, 11, V11, 12, V12, 13, V13, 14, V14, 15, V15, 16, V16
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::shim::actors;
+    use crate::shim::machine::BuiltinActor;
+
+    #[test]
+    fn load_dispatches_every_registered_code() {
+        let store = MemoryDB::default();
+        // nothing is stored under this CID, so only unknown actor codes fail differently
+        let state = Cid::default();
+        for bundle in crate::networks::ACTOR_BUNDLES_METADATA.values() {
+            for (actor, code) in bundle.manifest.builtin_actors() {
+                let result = match actor {
+                    BuiltinActor::System => {
+                        actors::system::State::load(&store, code, state).map(|_| ())
+                    }
+                    BuiltinActor::Init => {
+                        actors::init::State::load(&store, code, state).map(|_| ())
+                    }
+                    BuiltinActor::Cron => {
+                        actors::cron::State::load(&store, code, state).map(|_| ())
+                    }
+                    BuiltinActor::Account => {
+                        actors::account::State::load(&store, code, state).map(|_| ())
+                    }
+                    BuiltinActor::Power => {
+                        actors::power::State::load(&store, code, state).map(|_| ())
+                    }
+                    BuiltinActor::Miner => {
+                        actors::miner::State::load(&store, code, state).map(|_| ())
+                    }
+                    BuiltinActor::Market => {
+                        actors::market::State::load(&store, code, state).map(|_| ())
+                    }
+                    BuiltinActor::Multisig => {
+                        actors::multisig::State::load(&store, code, state).map(|_| ())
+                    }
+                    BuiltinActor::Reward => {
+                        actors::reward::State::load(&store, code, state).map(|_| ())
+                    }
+                    BuiltinActor::VerifiedRegistry => {
+                        actors::verifreg::State::load(&store, code, state).map(|_| ())
+                    }
+                    BuiltinActor::DataCap => {
+                        actors::datacap::State::load(&store, code, state).map(|_| ())
+                    }
+                    BuiltinActor::EVM => actors::evm::State::load(&store, code, state).map(|_| ()),
+                    // no state is loaded for these
+                    BuiltinActor::PaymentChannel
+                    | BuiltinActor::Placeholder
+                    | BuiltinActor::EAM
+                    | BuiltinActor::EthAccount => continue,
+                };
+                assert_eq!(
+                    result.unwrap_err().to_string(),
+                    "Actor state doesn't exist in store",
+                    "{actor:?} actor code {code} of {} {}",
+                    bundle.network,
+                    bundle.version
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn load_unknown_code() {
+        let store = MemoryDB::default();
+        let error = actors::multisig::State::load(&store, Cid::default(), Cid::default())
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("Unknown actor code"));
+        assert_eq!(multisig_cid_version(&Cid::default()), None);
+    }
+}
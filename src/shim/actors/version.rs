@@ -39,6 +39,15 @@ pub fn [<is_ $actor:lower _actor>](actor_code_cid: &Cid) -> bool {
         .any(|(_, cid)| cid == actor_code_cid)
 }
 
+#[allow(unused)]
+/// Returns the actor version of the provided actor code CID, if it belongs to the given actor.
+pub fn [<$actor:lower _cid_version>](actor_code_cid: &Cid) -> Option<u64> {
+    [<$actor:upper _ACTOR_CIDS>]
+        .iter()
+        .find(|(_, cid)| cid == actor_code_cid)
+        .map(|(version, _)| *version)
+}
+
 #[allow(unused)]
 /// Checks if the provided actor code CID and version are valid for the given actor.
 pub fn [<is_ $actor:lower _cid_version>](actor_code_cid: &Cid, version: u64) -> bool {
@@ -79,3 +88,14 @@ impl_actor_cids!(
 
 // A special snowflake which has a slightly different type and and package name.
 impl_actor_cids_type_actor!(VerifiedRegistry, Verifreg);
+
+/// Returns the code CID of `actor` in the bundle of actors major `version`, for tests to create
+/// actor states of a given version.
+#[cfg(test)]
+pub fn actor_code(version: u64, actor: Type) -> Cid {
+    crate::networks::ACTOR_BUNDLES_METADATA
+        .values()
+        .find(|bundle| bundle.actor_major_version().ok() == Some(version))
+        .and_then(|bundle| bundle.manifest.get(actor).ok())
+        .unwrap_or_else(|| panic!("no {actor:?} actor code in the v{version} bundle"))
+}
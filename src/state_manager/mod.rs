@@ -29,7 +29,7 @@ use crate::rpc::state::{ApiInvocResult, InvocResult, MessageGasCost};
 use crate::rpc::types::{MiningBaseInfo, SectorOnChainInfo};
 use crate::shim::actors::init::{self, State};
 use crate::shim::actors::miner::{MinerInfo, MinerPower, Partition};
-use crate::shim::actors::verifreg::{Allocation, AllocationID, Claim};
+use crate::shim::actors::verifreg::{Allocation, AllocationID, Claim, DataCap};
 use crate::shim::actors::*;
 use crate::shim::{
     actors::{
@@ -55,7 +55,6 @@ use bls_signatures::{PublicKey as BlsPublicKey, Serialize as _};
 use chain_rand::ChainRand;
 use cid::Cid;
 pub use circulating_supply::GenesisInfo;
-use fil_actor_verifreg_state::v12::DataCap;
 use fil_actor_verifreg_state::v13::ClaimID;
 use fil_actors_shared::fvm_ipld_amt::Amtv0 as Amt;
 use fil_actors_shared::fvm_ipld_bitfield::BitField;
@@ -1511,24 +1510,9 @@ where
         ts: &Tipset,
     ) -> anyhow::Result<Option<DataCap>> {
         let id = self.lookup_required_id(addr, ts)?;
-        let network_version = self.get_network_version(ts.epoch());
-
-        // This is a copy of Lotus code, we need to treat all the actors below version 9
-        // differently. Which maps to network below version 17.
-        // Original: https://github.com/filecoin-project/lotus/blob/5e76b05b17771da6939c7b0bf65127c3dc70ee23/node/impl/full/state.go#L1627-L1664.
-        if (u32::from(network_version.0)) < 17 {
-            let state = self.get_verified_registry_actor_state(ts)?;
-            return state.verified_client_data_cap(self.blockstore(), id.into());
-        }
-
-        let act = self
-            .get_actor(&Address::DATACAP_TOKEN_ACTOR, *ts.parent_state())
-            .map_err(|e| Error::State(e.to_string()))?
-            .ok_or_else(|| Error::State("Miner actor not found".to_string()))?;
-
-        let state = datacap::State::load(self.blockstore(), act.code, act.state)?;
-
-        state.verified_client_data_cap(self.blockstore(), id.into())
+        let state = self.get_verified_registry_actor_state(ts)?;
+        let state_tree = self.get_state_tree(ts.parent_state())?;
+        state.data_cap(&state_tree, &id.into())
     }
 
     pub async fn resolve_to_deterministic_address(
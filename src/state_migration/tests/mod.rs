@@ -2,13 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use crate::utils::net::global_http_client;
-use crate::utils::{retry, RetryArgs};
 use crate::{
     daemon::bundle::load_actor_bundles,
     networks::{ChainConfig, Height, NetworkChain},
     shim::state_tree::StateRoot,
     state_migration::run_state_migrations,
 };
+use backon::{ExponentialBuilder, Retryable as _};
 use cid::Cid;
 use futures::{AsyncWriteExt, TryStreamExt};
 use fvm_ipld_encoding::CborStore;
@@ -82,29 +82,29 @@ async fn test_state_migration(
             .unwrap()
             .into_temp_path();
         let timeout = Duration::from_secs(5);
-        retry(
-            RetryArgs {
-                timeout: Some(timeout),
-                max_retries: Some(5),
-                ..Default::default()
-            },
-            || async {
-                let response = global_http_client().get(format!(
+        let download = || async {
+            let response = global_http_client().get(format!(
                     "https://forest-continuous-integration.fra1.digitaloceanspaces.com/state_migration/state/{old_state}.car"
                 )).timeout(timeout).send().await.unwrap();
-                let reader = response
-                    .bytes_stream()
-                    .map_err(std::io::Error::other)
-                    .into_async_read();
-                let mut writer = futures::io::BufWriter::new(async_fs::File::create(&tmp).await.unwrap());
-                futures::io::copy(reader, &mut writer).await.unwrap();
-                writer.flush().await.unwrap();
-                writer.close().await.unwrap();
+            let reader = response
+                .bytes_stream()
+                .map_err(std::io::Error::other)
+                .into_async_read();
+            let mut writer =
+                futures::io::BufWriter::new(async_fs::File::create(&tmp).await.unwrap());
+            futures::io::copy(reader, &mut writer).await.unwrap();
+            writer.flush().await.unwrap();
+            writer.close().await.unwrap();
 
-                anyhow::Ok(())
-            },
+            anyhow::Ok(())
+        };
+        tokio::time::timeout(
+            timeout,
+            download.retry(ExponentialBuilder::default().with_max_times(5)),
         )
-        .await.unwrap();
+        .await
+        .unwrap()
+        .unwrap();
         tmp.persist(&car_path).unwrap();
     }
 
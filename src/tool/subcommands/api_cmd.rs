@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use crate::blocks::{ElectionProof, Ticket, Tipset};
-use crate::db::car::ManyCar;
+use crate::db::{car::ManyCar, CachingBlockstore};
 use crate::eth::{EthChainId as EthChainIdType, SAFE_EPOCH_DELAY};
 use crate::lotus_json::HasLotusJson;
 use crate::message::{Message as _, SignedMessage};
@@ -42,6 +42,7 @@ use ipld_core::ipld::Ipld;
 use itertools::Itertools as _;
 use jsonrpsee::types::ErrorCode;
 use libp2p::PeerId;
+use nonzero_ext::nonzero;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -49,6 +50,7 @@ use similar::{ChangeTag, TextDiff};
 use std::{
     borrow::Cow,
     io,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
@@ -59,6 +61,8 @@ use tokio::sync::Semaphore;
 use tracing::debug;
 
 const COLLECTION_SAMPLE_SIZE: usize = 5;
+/// Number of blocks cached while creating the snapshot tests, see [`snapshot_tests`].
+const SNAPSHOT_TESTS_BLOCK_CACHE_SIZE: NonZeroUsize = nonzero!(16_384_usize);
 
 const CALIBNET_CHAIN_ID: EthChainIdType = crate::networks::calibnet::ETH_CHAIN_ID;
 
@@ -1664,10 +1668,15 @@ fn snapshot_tests(
     eth_chain_id: u64,
 ) -> anyhow::Result<Vec<RpcTest>> {
     let mut tests = vec![];
+    let heaviest_tipset = store.heaviest_tipset()?;
+    // the tests of consecutive tipsets load mostly the same actor states
+    let store = Arc::new(CachingBlockstore::new(
+        store,
+        SNAPSHOT_TESTS_BLOCK_CACHE_SIZE,
+    ));
     // shared_tipset in the snapshot might not be finalized for the offline RPC server
     // use heaviest - SAFE_EPOCH_DELAY instead
-    let shared_tipset = store
-        .heaviest_tipset()?
+    let shared_tipset = heaviest_tipset
         .chain(&store)
         .take(SAFE_EPOCH_DELAY as usize)
         .last()
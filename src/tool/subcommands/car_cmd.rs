@@ -1,22 +1,26 @@
 // Copyright 2019-2024 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::io::{self, BufRead, Read as _, Seek as _, SeekFrom, Write as _};
 use std::path::{Path, PathBuf};
 
+use anyhow::Context as _;
 use clap::Subcommand;
 use futures::{StreamExt, TryStreamExt};
 use fvm_ipld_blockstore::Blockstore;
 use indicatif::{ProgressBar, ProgressStyle};
+use integer_encoding::VarInt as _;
 use itertools::Itertools;
 use nunny::Vec as NonEmpty;
+use serde::Serialize;
 use tokio::{
     fs::File,
     io::{AsyncWriteExt, BufReader},
 };
 
-use crate::db::car::ForestCar;
+use crate::db::car::{plain, ForestCar};
 use crate::utils::db::{
-    car_stream::CarStream,
+    car_stream::{is_zstd, CarStream},
     car_util::{dedup_block_stream, merge_car_streams},
 };
 
@@ -43,6 +47,38 @@ pub enum CarCommands {
         #[arg(long)]
         ignore_forest_index: bool,
     },
+    /// Print the format version, root CIDs, block count and size of a CAR file, without loading
+    /// it
+    Inspect {
+        /// CAR file, either CARv1 or CARv2. It may be zstd compressed, e.g. a `.forest.car.zst`
+        /// file, in which case blocks aren't counted
+        car_file: PathBuf,
+        /// Print the summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Split a CARv1 file into chunks, at block boundaries. Each chunk is a CARv1 file with the
+    /// roots of the input file, see `forest-tool car join`
+    Split {
+        /// Uncompressed CARv1 file
+        #[arg(long)]
+        input: PathBuf,
+        /// Maximum size of a chunk in bytes, header included
+        #[arg(long)]
+        max_size: u64,
+        /// Directory to write the chunks to, named after the input file, e.g.
+        /// `snapshot.0000.car`, `snapshot.0001.car`, and so on
+        #[arg(long)]
+        output_dir: PathBuf,
+    },
+    /// Join the chunks written by `forest-tool car split` into the original CAR file
+    Join {
+        /// Chunks, in order
+        chunks: Vec<PathBuf>,
+        /// The output CAR file path
+        #[arg(short, long)]
+        output: PathBuf,
+    },
 }
 
 impl CarCommands {
@@ -78,6 +114,23 @@ impl CarCommands {
                 ignore_block_validity,
                 ignore_forest_index,
             } => validate(&car_file, ignore_block_validity, ignore_forest_index).await?,
+            Self::Inspect { car_file, json } => {
+                let info = inspect(&car_file).await?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&info)?);
+                } else {
+                    println!("{info}");
+                }
+            }
+            Self::Split {
+                input,
+                max_size,
+                output_dir,
+            } => {
+                let chunks = split(&input, max_size, &output_dir)?;
+                println!("Wrote {} chunks to {}", chunks.len(), output_dir.display());
+            }
+            Self::Join { chunks, output } => join(&chunks, &output)?,
         }
         Ok(())
     }
@@ -122,15 +175,202 @@ async fn validate(
     Ok(())
 }
 
+/// Summary of a CAR file, see [`inspect`].
+#[derive(Debug, Serialize)]
+struct CarInfo {
+    /// CAR format version, 1 or 2
+    version: u64,
+    /// Whether the file is zstd compressed
+    compressed: bool,
+    roots: Vec<String>,
+    /// Unknown for compressed files, whose blocks can't be counted without decompressing them
+    block_count: Option<u64>,
+    file_size: u64,
+}
+
+impl std::fmt::Display for CarInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "CAR version: {}", self.version)?;
+        writeln!(f, "Compressed:  {}", self.compressed)?;
+        match self.block_count {
+            Some(count) => writeln!(f, "Blocks:      {count}")?,
+            None => writeln!(f, "Blocks:      unknown")?,
+        }
+        writeln!(f, "File size:   {}", indicatif::HumanBytes(self.file_size))?;
+        write!(f, "Root CIDs:   {}", self.roots.join("\n             "))
+    }
+}
+
+/// Reads the header of a CAR file. Blocks are counted from the index of CARv2 files if they have
+/// one, or by skipping from block to block otherwise, so that no block data is read.
+async fn inspect(car_file: &Path) -> anyhow::Result<CarInfo> {
+    let mut file = std::fs::File::open(car_file)?;
+    let file_size = file.metadata()?.len();
+    // the zstd frame header is at most 18 bytes long
+    let mut start = vec![];
+    (&mut file).take(18).read_to_end(&mut start)?;
+    file.rewind()?;
+    let to_strings = |roots: NonEmpty<cid::Cid>| roots.iter().map(ToString::to_string).collect();
+
+    if is_zstd(&start) {
+        let stream = CarStream::new(BufReader::new(File::open(car_file).await?)).await?;
+        return Ok(CarInfo {
+            version: stream.header.version,
+            compressed: true,
+            roots: to_strings(stream.header.roots),
+            block_count: None,
+            file_size,
+        });
+    }
+    let (version, data_offset, data_size, index_offset) = match plain::V2Header::read(&mut file)? {
+        Some(header) => (2, header.data_offset, header.data_size, header.index_offset),
+        None => (1, 0, file_size, 0),
+    };
+    file.seek(SeekFrom::Start(data_offset))?;
+    let roots = plain::get_roots_from_v1_header(std::io::BufReader::new(&file))?;
+    let indexed = if index_offset != 0 {
+        file.seek(SeekFrom::Start(index_offset))?;
+        plain::count_v2_index_entries(std::io::BufReader::new(&file))?
+    } else {
+        None
+    };
+    let block_count = match indexed {
+        Some(count) => count,
+        None => {
+            file.seek(SeekFrom::Start(data_offset))?;
+            plain::count_v1_blocks(&file, data_size)?
+        }
+    };
+    Ok(CarInfo {
+        version,
+        compressed: false,
+        roots: to_strings(roots),
+        block_count: Some(block_count),
+        file_size,
+    })
+}
+
+/// Reads a varint length-prefixed frame, prefix included, or returns `None` at the end of the
+/// file. The prefix is copied as is, so that the frame can be written back byte for byte.
+fn read_frame(mut reader: impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    let mut frame = vec![];
+    loop {
+        if frame.len() == 10 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "frame length overflows",
+            ));
+        }
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        frame.push(byte[0]);
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    let (body_length, prefix_length) = u64::decode_var(&frame)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid frame length"))?;
+    (&mut reader).take(body_length).read_to_end(&mut frame)?;
+    if (frame.len() - prefix_length) as u64 != body_length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(Some(frame))
+}
+
+/// Splits `input` into CARv1 files of at most `max_size` bytes, which all have the header of
+/// `input`, and returns their paths in order.
+fn split(input: &Path, max_size: u64, output_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(input)?);
+    anyhow::ensure!(
+        !is_zstd(reader.fill_buf()?),
+        "{} is compressed, decompress it first",
+        input.display()
+    );
+    let header = read_frame(&mut reader)?.context("empty CAR file")?;
+    plain::get_roots_from_v1_header(header.as_slice())?;
+    let stem = input
+        .file_stem()
+        .context("input has no file name")?
+        .to_string_lossy();
+    std::fs::create_dir_all(output_dir)?;
+
+    let mut chunks = vec![];
+    // the chunk being written and its size
+    let mut chunk: Option<(std::io::BufWriter<std::fs::File>, u64)> = None;
+    while let Some(frame) = read_frame(&mut reader)? {
+        let frame_size = frame.len() as u64;
+        anyhow::ensure!(
+            header.len() as u64 + frame_size <= max_size,
+            "a block of {frame_size} bytes doesn't fit in a chunk of {max_size} bytes with the {} bytes header",
+            header.len()
+        );
+        let (writer, size) = match chunk.take() {
+            Some((writer, size)) if size + frame_size <= max_size => chunk.insert((writer, size)),
+            full => {
+                if let Some((mut writer, _)) = full {
+                    writer.flush()?;
+                }
+                let path = output_dir.join(format!("{stem}.{:04}.car", chunks.len()));
+                let mut writer = std::io::BufWriter::new(std::fs::File::create(&path)?);
+                writer.write_all(&header)?;
+                chunks.push(path);
+                chunk.insert((writer, header.len() as u64))
+            }
+        };
+        writer.write_all(&frame)?;
+        *size += frame_size;
+    }
+    match chunk {
+        Some((mut writer, _)) => writer.flush()?,
+        // no blocks, the header alone is the only chunk
+        None => {
+            let path = output_dir.join(format!("{stem}.0000.car"));
+            std::fs::write(&path, &header)?;
+            chunks.push(path);
+        }
+    }
+    Ok(chunks)
+}
+
+/// Concatenates the blocks of `chunks`, which must all have the same header, behind that header.
+fn join(chunks: &[PathBuf], output: &Path) -> anyhow::Result<()> {
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(output)?);
+    let mut first_header = None;
+    for path in chunks {
+        let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+        let header =
+            read_frame(&mut reader)?.with_context(|| format!("{} is empty", path.display()))?;
+        match &first_header {
+            None => {
+                plain::get_roots_from_v1_header(header.as_slice())?;
+                writer.write_all(&header)?;
+                first_header = Some(header);
+            }
+            Some(first_header) => anyhow::ensure!(
+                &header == first_header,
+                "the header of {} differs from that of the first chunk",
+                path.display()
+            ),
+        }
+        io::copy(&mut reader, &mut writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use super::validate;
+    use super::{inspect, join, split, validate};
     use crate::db::car::forest;
     use crate::networks::{calibnet, mainnet};
     use crate::utils::db::car_stream::CarBlock;
     use crate::utils::multihash::prelude::*;
     use cid::Cid;
     use futures::{stream::iter, StreamExt, TryStreamExt};
+    use integer_encoding::VarInt as _;
     use nunny::{vec as nonempty, Vec as NonEmpty};
     use std::io::Write;
     use tempfile::{Builder, TempPath};
@@ -171,6 +411,76 @@ mod tests {
             .unwrap();
     }
 
+    #[tokio::test]
+    async fn inspect_v1_v2_and_compressed() {
+        let mut v1 = Builder::new().tempfile().unwrap();
+        v1.write_all(calibnet::DEFAULT_GENESIS).unwrap();
+        let v1 = v1.into_temp_path();
+        let info = inspect(&v1).await.unwrap();
+        assert_eq!((info.version, info.compressed), (1, false));
+        let blocks = info.block_count.unwrap();
+        assert!(blocks > 0);
+
+        let mut v2 = Builder::new().tempfile().unwrap();
+        crate::db::car::plain::write_v2(std::io::Cursor::new(calibnet::DEFAULT_GENESIS), &mut v2)
+            .unwrap();
+        let v2 = inspect(&v2.into_temp_path()).await.unwrap();
+        assert_eq!((v2.version, v2.compressed), (2, false));
+        assert_eq!(v2.roots, info.roots);
+        assert_eq!(v2.block_count, Some(blocks));
+
+        let compressed = create_raw_car_file(
+            nonempty![valid_block("compressed blocks aren't counted")],
+            vec![],
+        )
+        .await;
+        let compressed = inspect(&compressed).await.unwrap();
+        assert!(compressed.compressed);
+        assert_eq!(compressed.block_count, None);
+    }
+
+    #[test]
+    fn split_and_join() {
+        let blocks = (0..20)
+            .map(|i| valid_block(&"block ".repeat(i + 1)))
+            .collect::<Vec<_>>();
+        let header = crate::utils::db::car_stream::CarHeader {
+            roots: nonempty![blocks[0].cid],
+            version: 1,
+        };
+        let header = fvm_ipld_encoding::to_vec(&header).unwrap();
+        let mut car = header.len().encode_var_vec();
+        car.extend(&header);
+        for block in &blocks {
+            block.write(&mut car).unwrap();
+        }
+        let directory = tempfile::tempdir().unwrap();
+        let input = directory.path().join("input.car");
+        std::fs::write(&input, &car).unwrap();
+
+        let max_size = 300;
+        let chunks = split(&input, max_size, &directory.path().join("chunks")).unwrap();
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            let bytes = std::fs::read(chunk).unwrap();
+            assert!(bytes.len() as u64 <= max_size);
+            let roots = crate::db::car::plain::get_roots_from_v1_header(bytes.as_slice()).unwrap();
+            assert_eq!(roots, nonempty![blocks[0].cid]);
+        }
+        let output = directory.path().join("output.car");
+        join(&chunks, &output).unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), car);
+
+        // the largest block doesn't fit with the header
+        assert!(split(&input, 100, &directory.path().join("small")).is_err());
+        // chunks of different files can't be joined
+        let mut other = Builder::new().tempfile().unwrap();
+        other.write_all(calibnet::DEFAULT_GENESIS).unwrap();
+        let mut mixed = chunks.clone();
+        mixed.push(other.path().to_owned());
+        assert!(join(&mixed, &output).is_err());
+    }
+
     fn valid_block(msg: &str) -> CarBlock {
         let data = msg.as_bytes().to_vec();
         CarBlock {
@@ -1,10 +1,11 @@
 // Copyright 2019-2024 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 
 use crate::shim::sector::SectorSize;
-use crate::utils::proofs_api::{get_params_default, SectorSizeOpt};
+use crate::utils::proofs_api::{get_params_from_file, SectorSizeOpt, DEFAULT_PARAMS_FETCH_JOBS};
 
 use crate::cli::subcommands::cli_error_and_die;
 use crate::cli_shared::read_config;
@@ -21,11 +22,22 @@ pub struct FetchCommands {
     /// Print out download location instead of downloading files
     #[arg(short, long)]
     dry_run: bool,
-    /// Size in bytes
-    params_size: Option<String>,
+    /// Sector sizes in bytes, e.g. `32GiB 64GiB`
+    params_size: Vec<String>,
     /// Optional TOML file containing forest daemon configuration
     #[arg(short, long)]
     pub config: Option<PathBuf>,
+    /// JSON manifest of the parameter files to use instead of the built-in one, in the format of
+    /// `parameters.json`
+    #[arg(long)]
+    params_file: Option<PathBuf>,
+    /// Maximum read bandwidth in bytes per second for verifying the downloaded parameter files,
+    /// shared by all files verified in parallel. Unlimited if not set
+    #[arg(long)]
+    params_verify_rate_limit: Option<u64>,
+    /// Maximum number of parameter files downloaded in parallel
+    #[arg(short, long, default_value_t = DEFAULT_PARAMS_FETCH_JOBS)]
+    jobs: NonZeroUsize,
 }
 
 impl FetchCommands {
@@ -34,9 +46,13 @@ impl FetchCommands {
 
         let sizes = if self.all {
             SectorSizeOpt::All
-        } else if let Some(size) = &self.params_size {
-            let sector_size = ram_to_int(size)?;
-            SectorSizeOpt::Size(sector_size)
+        } else if !self.params_size.is_empty() {
+            let sector_sizes = self
+                .params_size
+                .iter()
+                .map(|size| Ok(ram_to_int(size)? as u64))
+                .collect::<anyhow::Result<_>>()?;
+            SectorSizeOpt::Sizes(sector_sizes)
         } else if self.keys {
             SectorSizeOpt::Keys
         } else {
@@ -46,7 +62,15 @@ impl FetchCommands {
             );
         };
 
-        get_params_default(&config.client.data_dir, sizes, self.dry_run).await
+        get_params_from_file(
+            &config.client.data_dir,
+            self.params_file.as_deref(),
+            sizes,
+            self.dry_run,
+            self.params_verify_rate_limit,
+            self.jobs,
+        )
+        .await
     }
 }
 
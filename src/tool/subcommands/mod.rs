@@ -9,6 +9,7 @@ mod car_cmd;
 mod db_cmd;
 mod fetch_params_cmd;
 mod net_cmd;
+mod params_cmd;
 mod shed_cmd;
 mod snapshot_cmd;
 mod state_migration_cmd;
@@ -51,6 +52,10 @@ pub enum Subcommand {
     #[command(name = "fetch-params")]
     Fetch(fetch_params_cmd::FetchCommands),
 
+    /// Manage proof parameter manifests
+    #[command(subcommand)]
+    Params(params_cmd::ParamsCommands),
+
     /// Manage archives
     #[command(subcommand)]
     Archive(archive_cmd::ArchiveCommands),
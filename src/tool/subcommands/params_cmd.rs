@@ -0,0 +1,103 @@
+// Copyright 2019-2024 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::path::PathBuf;
+
+use clap::Subcommand;
+use colored::Colorize as _;
+
+use super::fetch_params_cmd::FetchCommands;
+use crate::cli_shared::read_config;
+use crate::utils::proofs_api::{
+    export_params, param_dir, param_files_status, set_param_entry, ParamEntryPatch, ParamFileStatus,
+};
+
+#[derive(Debug, Subcommand)]
+pub enum ParamsCommands {
+    /// Download parameters for generating and verifying proofs for given size, like `fetch-params`
+    Fetch(FetchCommands),
+    /// Write the proof parameter manifest in use to a file, in the format of `parameters.json`
+    Export {
+        /// Path to the output JSON file
+        #[arg(long)]
+        output: PathBuf,
+        /// JSON manifest to export instead of the built-in one, e.g. to normalize it
+        #[arg(long)]
+        params_file: Option<PathBuf>,
+    },
+    /// Update an entry of a proof parameter manifest in place, e.g. to pin a locally computed file
+    Set {
+        /// Path to the JSON manifest, in the format of `parameters.json`
+        #[arg(long)]
+        file: PathBuf,
+        /// Name of the entry to update, e.g. `v28-stacked-proof-of-replication-...-sha256_hasher.params`
+        #[arg(long)]
+        name: String,
+        /// New CID of the parameter file
+        #[arg(long)]
+        cid: Option<String>,
+        /// New BLAKE2b digest prefix of the parameter file, as 32 hex characters
+        #[arg(long)]
+        digest: Option<String>,
+        /// New sector size, in bytes
+        #[arg(long)]
+        sector_size: Option<u64>,
+    },
+    /// Print the proof parameter directory in use and verify the parameter files in it
+    Info {
+        /// Optional TOML file containing forest daemon configuration
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// JSON manifest of the parameter files to check instead of the built-in one
+        #[arg(long)]
+        params_file: Option<PathBuf>,
+    },
+}
+
+impl ParamsCommands {
+    pub async fn run(self) -> anyhow::Result<()> {
+        match self {
+            Self::Fetch(cmd) => cmd.run().await,
+            Self::Export {
+                output,
+                params_file,
+            } => export_params(params_file.as_deref(), &output),
+            Self::Set {
+                file,
+                name,
+                cid,
+                digest,
+                sector_size,
+            } => set_param_entry(
+                &file,
+                &name,
+                ParamEntryPatch {
+                    cid,
+                    digest,
+                    sector_size,
+                },
+            ),
+            Self::Info {
+                config,
+                params_file,
+            } => {
+                let (_, config) = read_config(config.as_ref(), None)?;
+                // takes `FIL_PROOFS_PARAMETER_CACHE` and XDG directories into account
+                let dir = param_dir(&config.client.data_dir);
+                println!("Parameter directory: {}", dir.display());
+                for file in param_files_status(&dir, params_file.as_deref()).await? {
+                    let status = match &file.status {
+                        ParamFileStatus::Verified => format!("{:<8}", "ok").green(),
+                        ParamFileStatus::Missing => format!("{:<8}", "missing").yellow(),
+                        ParamFileStatus::Invalid(_) => format!("{:<8}", "failed").red(),
+                    };
+                    println!("{status} {} {}", file.digest, file.name);
+                    if let ParamFileStatus::Invalid(e) = &file.status {
+                        println!("         {e}");
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -4,6 +4,7 @@
 use super::*;
 use crate::blocks::Tipset;
 use crate::chain::index::{ChainIndex, ResolveNullTipset};
+use crate::cid_collections::CidHashSet;
 use crate::cli_shared::snapshot;
 use crate::daemon::bundle::load_actor_bundles;
 use crate::db::car::forest::DEFAULT_FOREST_CAR_FRAME_SIZE;
@@ -18,6 +19,8 @@ use crate::shim::fvm_shared_latest::address::Network;
 use crate::shim::machine::MultiEngine;
 use crate::state_manager::{apply_block_messages, StateOutput};
 use crate::utils::db::car_stream::CarStream;
+use crate::utils::encoding::extract_cids;
+use crate::utils::multihash::prelude::*;
 use crate::utils::proofs_api::ensure_params_downloaded;
 use anyhow::{bail, Context as _};
 use cid::Cid;
@@ -25,11 +28,13 @@ use clap::Subcommand;
 use dialoguer::{theme::ColorfulTheme, Confirm};
 use futures::TryStreamExt;
 use fvm_ipld_blockstore::Blockstore;
+use human_repr::HumanCount as _;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
+use url::Url;
 
 #[derive(Debug, Subcommand)]
 pub enum SnapshotCommands {
@@ -43,6 +48,43 @@ pub enum SnapshotCommands {
         /// Vendor to fetch the snapshot from
         #[arg(short, long, value_enum, default_value_t = snapshot::TrustedVendor::default())]
         vendor: snapshot::TrustedVendor,
+        /// Fetch the snapshot from this URL instead, e.g. a private mirror. A checksum file
+        /// next to it, such as `<name>.sha256sum`, is required unless `--skip-checksum` is set.
+        /// Local paths and `file://` URLs are used in place, and verified if there is a checksum file.
+        #[arg(long, value_parser = snapshot::parse_url_or_path)]
+        snapshot_url: Option<Url>,
+        /// Don't verify the snapshot against its checksum file
+        #[arg(long)]
+        skip_checksum: bool,
+        /// How many times to try the download, with exponential backoff between attempts
+        #[arg(long, default_value_t = snapshot::DEFAULT_FETCH_ATTEMPTS)]
+        max_attempts: usize,
+        /// Print the snapshot URL, size and checksum without downloading it
+        #[arg(long)]
+        dry_run: bool,
+        /// Decompress `.car.zst` snapshots after downloading them
+        #[arg(long)]
+        decompress: bool,
+        /// Write the snapshot as a CARv2 file with an index of its blocks, decompressing it if needed
+        #[arg(long)]
+        car_v2: bool,
+        /// How long to wait for a connection to the snapshot server
+        #[arg(long, default_value_t = snapshot::DEFAULT_SNAPSHOT_CONNECT_TIMEOUT.into())]
+        snapshot_connect_timeout: humantime::Duration,
+        /// Give up on an attempt after receiving no data for this long
+        #[arg(long, default_value_t = snapshot::DEFAULT_SNAPSHOT_READ_TIMEOUT.into())]
+        snapshot_read_timeout: humantime::Duration,
+    },
+
+    /// Verify a snapshot on disk against its checksum file, without downloading it again.
+    Verify {
+        /// Path to the snapshot
+        #[arg(long)]
+        snapshot: PathBuf,
+        /// Checksum file to verify against, such as `<name>.sha256sum`. Local paths are
+        /// supported. Defaults to the checksum file next to the snapshot.
+        #[arg(long, value_parser = snapshot::parse_url_or_path)]
+        checksum_url: Option<Url>,
     },
 
     /// Validate the provided snapshots as a whole.
@@ -101,6 +143,18 @@ pub enum SnapshotCommands {
         #[arg(long, default_value_t = false)]
         force: bool,
     },
+    /// Compare two snapshots, listing the blocks reachable from the roots of only one of them.
+    Diff {
+        /// Path to the older snapshot CAR, which may be zstd compressed
+        #[arg(long)]
+        old: PathBuf,
+        /// Path to the newer snapshot CAR, which may be zstd compressed
+        #[arg(long)]
+        new: PathBuf,
+        /// Generate JSON output
+        #[arg(long)]
+        json: bool,
+    },
     /// Filecoin keeps track of "the state of the world", including:
     /// wallets and their balances;
     /// storage providers and their deals;
@@ -138,13 +192,69 @@ impl SnapshotCommands {
                 directory,
                 chain,
                 vendor,
-            } => match snapshot::fetch(&directory, &chain, vendor).await {
-                Ok(out) => {
-                    println!("{}", out.display());
+                snapshot_url,
+                skip_checksum,
+                max_attempts,
+                dry_run,
+                decompress,
+                car_v2,
+                snapshot_connect_timeout,
+                snapshot_read_timeout,
+            } => match snapshot::fetch(
+                &directory,
+                &chain,
+                vendor,
+                snapshot::FetchOptions {
+                    url: snapshot_url,
+                    skip_checksum,
+                    progress: None,
+                    max_attempts,
+                    dry_run,
+                    decompress,
+                    car_v2,
+                    metrics: None,
+                    http: snapshot::SnapshotFetchConfig {
+                        connect_timeout: snapshot_connect_timeout.into(),
+                        read_timeout: snapshot_read_timeout.into(),
+                        total_timeout: None,
+                    },
+                },
+            )
+            .await
+            {
+                Ok(meta) if dry_run => {
+                    println!("URL:      {}", meta.url);
+                    println!("Path:     {}", meta.path.display());
+                    println!("Size:     {}", meta.total_size.human_count_bytes());
+                    match meta.checksum {
+                        Some(checksum) => {
+                            println!("Checksum: {} {}", checksum.algorithm, checksum.digest)
+                        }
+                        None => println!("Checksum: none"),
+                    }
+                    Ok(())
+                }
+                Ok(meta) => {
+                    println!("{}", meta.path.display());
                     Ok(())
                 }
                 Err(e) => cli_error_and_die(format!("Failed fetching the snapshot: {e}"), 1),
             },
+            Self::Verify {
+                snapshot,
+                checksum_url,
+            } => match snapshot::verify(&snapshot, checksum_url.as_ref()).await {
+                Ok(checksum) => {
+                    println!(
+                        "{}: passed ({} {})",
+                        snapshot.display(),
+                        checksum.algorithm,
+                        checksum.digest
+                    );
+                    Ok(())
+                }
+                Err(e) => cli_error_and_die(format!("{}: failed: {e:#}", snapshot.display()), 1),
+            },
             Self::ValidateDiffs {
                 check_links,
                 check_network,
@@ -262,6 +372,32 @@ impl SnapshotCommands {
                 dest.flush().await?;
                 Ok(())
             }
+            Self::Diff { old, new, json } => {
+                let old = AnyCar::try_from(old.as_path())
+                    .with_context(|| format!("couldn't open {}", old.display()))?;
+                let new = AnyCar::try_from(new.as_path())
+                    .with_context(|| format!("couldn't open {}", new.display()))?;
+                let added = dag_diff(new.roots(), &new, &old)?;
+                let removed = dag_diff(old.roots(), &old, &new)?;
+                if json {
+                    println!(
+                        "{:#}",
+                        serde_json::json!({
+                            "added": added.iter().map(Cid::to_string).collect::<Vec<_>>(),
+                            "removed": removed.iter().map(Cid::to_string).collect::<Vec<_>>(),
+                        })
+                    );
+                } else {
+                    for cid in &added {
+                        println!("+ {cid}");
+                    }
+                    for cid in &removed {
+                        println!("- {cid}");
+                    }
+                    println!("{} added, {} removed", added.len(), removed.len());
+                }
+                Ok(())
+            }
             SnapshotCommands::ComputeState {
                 snapshot,
                 epoch,
@@ -418,7 +554,7 @@ where
         &Config::default().client.data_dir,
     );
 
-    ensure_params_downloaded().await?;
+    ensure_params_downloaded(None).await?;
 
     let chain_index = Arc::new(ChainIndex::new(Arc::new(db.clone())));
 
@@ -459,6 +595,41 @@ fn validation_spinner(prefix: &'static str) -> indicatif::ProgressBar {
     pb
 }
 
+/// Returns the blocks reachable from `roots` in `store` that are missing from `other`, in
+/// depth-first order.
+///
+/// Blocks present in both stores aren't descended into: their content, and so everything they
+/// link to, is the same on both sides. Links to blocks that `store` doesn't have are skipped,
+/// as snapshots only hold a limited range of state trees and messages.
+fn dag_diff(
+    roots: &[Cid],
+    store: &impl Blockstore,
+    other: &impl Blockstore,
+) -> anyhow::Result<Vec<Cid>> {
+    let mut seen = CidHashSet::new();
+    let mut stack = roots.iter().rev().copied().collect::<Vec<_>>();
+    let mut diff = vec![];
+    while let Some(cid) = stack.pop() {
+        // identity CIDs embed their data, so they are never stored
+        if cid.hash().code() == u64::from(MultihashCode::Identity)
+            || !seen.insert(cid)
+            || other.has(&cid)?
+        {
+            continue;
+        }
+        let Some(data) = store.get(&cid)? else {
+            continue;
+        };
+        diff.push(cid);
+        if cid.codec() == fvm_ipld_encoding::DAG_CBOR {
+            let links = extract_cids(&data)
+                .with_context(|| format!("couldn't decode the links of block {cid}"))?;
+            stack.extend(links.into_iter().rev());
+        }
+    }
+    Ok(diff)
+}
+
 fn print_computed_state(snapshot: PathBuf, epoch: ChainEpoch, json: bool) -> anyhow::Result<()> {
     // Initialize Blockstore
     let store = Arc::new(AnyCar::try_from(snapshot.as_path())?);
@@ -575,3 +746,41 @@ mod structured {
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::utils::db::CborStoreExt as _;
+
+    #[test]
+    fn dag_diff_stops_at_shared_blocks() {
+        let old = MemoryDB::default();
+        let new = MemoryDB::default();
+        let put = |stores: &[&MemoryDB], value: Vec<Cid>| {
+            let mut cid = Cid::default();
+            for store in stores {
+                cid = store.put_cbor_default(&value).unwrap();
+            }
+            cid
+        };
+        let shared_leaf = put(&[&old, &new], vec![]);
+        let shared = put(&[&old, &new], vec![shared_leaf]);
+        let old_leaf = put(&[&old], vec![shared_leaf, Cid::default()]);
+        let new_leaf = put(&[&new], vec![shared, shared_leaf]);
+        let old_root = put(&[&old], vec![shared, old_leaf]);
+        let new_root = put(&[&new], vec![shared, new_leaf, new_leaf]);
+
+        assert_eq!(
+            dag_diff(&[new_root], &new, &old).unwrap(),
+            vec![new_root, new_leaf]
+        );
+        assert_eq!(
+            dag_diff(&[old_root], &old, &new).unwrap(),
+            vec![old_root, old_leaf]
+        );
+        assert_eq!(dag_diff(&[shared], &new, &old).unwrap(), vec![]);
+        // roots missing from the snapshot itself are ignored
+        assert_eq!(dag_diff(&[old_root], &new, &old).unwrap(), vec![]);
+    }
+}
@@ -94,7 +94,7 @@ pin_project! {
 // This method checks the header in order to see whether or not we are operating on a zstd
 // archive. The zstd header has a maximum size of 18 bytes:
 // https://github.com/facebook/zstd/blob/dev/doc/zstd_compression_format.md#zstandard-frames.
-fn is_zstd(buf: &[u8]) -> bool {
+pub fn is_zstd(buf: &[u8]) -> bool {
     zstd::zstd_safe::get_frame_content_size(buf).is_ok()
 }
 
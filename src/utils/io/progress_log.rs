@@ -90,6 +90,21 @@ impl<S> WithProgress<S> {
     }
 }
 
+impl WithProgress<()> {
+    /// Tracks progress that is reported via [`WithProgress::set`] rather than by reading.
+    pub fn new(message: &str) -> Self {
+        WithProgress {
+            inner: (),
+            progress: Progress::new(message),
+        }
+    }
+
+    pub fn set(&mut self, completed_items: u64, total_items: u64) {
+        self.progress.total_items = Some(total_items);
+        self.progress.set(completed_items);
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Progress {
     completed_items: u64,
@@ -2,18 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use crate::utils::io::WithProgress;
-use crate::utils::reqwest_resume;
 use cid::Cid;
 use futures::{AsyncWriteExt, TryStreamExt};
 use once_cell::sync::Lazy;
 use reqwest::Response;
 use std::path::Path;
 use tap::Pipe;
-use tokio::io::AsyncBufRead;
-use tokio_util::{
-    compat::TokioAsyncReadCompatExt,
-    either::Either::{Left, Right},
-};
+use tokio_util::compat::TokioAsyncReadCompatExt;
 use tracing::info;
 use url::Url;
 
@@ -22,9 +17,11 @@ pub fn global_http_client() -> reqwest::Client {
     CLIENT.clone()
 }
 
-/// Download a file via IPFS HTTP gateway in trustless mode.
+/// Download a file via IPFS HTTP gateway in trustless mode, with `client`. Failed requests aren't
+/// resumed, so that the caller can retry them or fall back to another gateway.
 /// See <https://github.com/ipfs/specs/blob/main/http-gateways/TRUSTLESS_GATEWAY.md>
 pub async fn download_ipfs_file_trustlessly(
+    client: &reqwest::Client,
     cid: &Cid,
     gateway: &Url,
     destination: &Path,
@@ -39,7 +36,17 @@ pub async fn download_ipfs_file_trustlessly(
         tempfile::NamedTempFile::new_in(destination.parent().unwrap_or_else(|| Path::new(".")))?
             .into_temp_path();
     {
-        let mut reader = reader(url.as_str()).await?.compat();
+        info!("Downloading file: {}", url);
+        let response = client.get(url).send().await?.error_for_status()?;
+        let content_length = response.content_length().unwrap_or_default();
+        let stream = response
+            .bytes_stream()
+            .map_err(std::io::Error::other)
+            .pipe(tokio_util::io::StreamReader::new);
+        let mut reader = tokio::io::BufReader::new(
+            WithProgress::wrap_async_read("Loading", stream, content_length).bytes(),
+        )
+        .compat();
         let mut writer = futures::io::BufWriter::new(async_fs::File::create(&tmp).await?);
         let cid_v10 = crate::utils::cid::cid_11_to_10(cid);
         rs_car_ipfs::single_file::read_single_file_seek(&mut reader, &mut writer, Some(&cid_v10))
@@ -53,43 +60,6 @@ pub async fn download_ipfs_file_trustlessly(
     Ok(())
 }
 
-/// `location` may be:
-/// - a path to a local file
-/// - a URL to a web resource
-/// - compressed
-/// - uncompressed
-///
-/// This function returns a reader of uncompressed data.
-pub async fn reader(location: &str) -> anyhow::Result<impl AsyncBufRead> {
-    // This isn't the cleanest approach in terms of error-handling, but it works. If the URL is
-    // malformed it'll end up trying to treat it as a local filepath. If that fails - an error
-    // is thrown.
-    let (stream, content_length) = match Url::parse(location) {
-        Ok(url) => {
-            info!("Downloading file: {}", url);
-            let resume_resp = reqwest_resume::get(url).await?;
-            let resp = resume_resp.response().error_for_status_ref()?;
-            let content_length = resp.content_length().unwrap_or_default();
-            let stream = resume_resp
-                .bytes_stream()
-                .map_err(std::io::Error::other)
-                .pipe(tokio_util::io::StreamReader::new);
-
-            (Left(stream), content_length)
-        }
-        Err(_) => {
-            info!("Reading file: {}", location);
-            let stream = tokio::fs::File::open(location).await?;
-            let content_length = stream.metadata().await?.len();
-            (Right(stream), content_length)
-        }
-    };
-
-    Ok(tokio::io::BufReader::new(
-        WithProgress::wrap_async_read("Loading", stream, content_length).bytes(),
-    ))
-}
-
 pub async fn http_get(url: &Url) -> anyhow::Result<Response> {
     info!(%url, "GET");
     Ok(global_http_client()
@@ -4,5 +4,11 @@
 mod parameters;
 mod paramfetch;
 
-pub use parameters::set_proofs_parameter_cache_dir_env;
-pub use paramfetch::{ensure_params_downloaded, get_params_default, SectorSizeOpt};
+pub use parameters::{param_dir, set_proofs_parameter_cache_dir_env};
+#[cfg(feature = "benchmark-private")]
+pub use parameters::{param_file_digest, param_file_digest_blocking};
+pub use paramfetch::{
+    ensure_params_downloaded, export_params, get_params_from_file, param_files_status,
+    set_param_entry, set_proofs_gateways_env, ParamEntryPatch, ParamFileStatus, SectorSizeOpt,
+    DEFAULT_PARAMS_FETCH_JOBS,
+};
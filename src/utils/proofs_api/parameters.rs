@@ -9,13 +9,22 @@ use std::{
     fs::File as SyncFile,
     io::{self, copy as sync_copy, BufReader as SyncBufReader},
     path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+    time::SystemTime,
 };
 
 use ahash::HashMap;
 use anyhow::{bail, Context};
 use blake2b_simd::{Hash, State as Blake2b};
 use cid::Cid;
+use itertools::Itertools as _;
 use serde::{Deserialize, Serialize};
+use tokio::{
+    io::AsyncReadExt as _,
+    sync::{Mutex, Semaphore},
+    task::JoinSet,
+    time::{Duration, Instant},
+};
 use tracing::{debug, warn};
 
 use crate::utils::misc::env::is_env_truthy;
@@ -39,8 +48,53 @@ pub(super) const DEFAULT_PARAMETERS: &str = include_str!("./parameters.json");
 /// Map of parameter data, to be deserialized from the parameter file.
 pub(super) type ParameterMap = HashMap<String, ParameterData>;
 
+/// Returns [`DEFAULT_PARAMETERS`], which is parsed once per process.
+pub(super) fn default_parameters() -> &'static ParameterMap {
+    static PARAMETERS: OnceLock<ParameterMap> = OnceLock::new();
+    PARAMETERS.get_or_init(|| {
+        serde_json::from_str(DEFAULT_PARAMETERS).expect("embedded parameters.json must be valid")
+    })
+}
+
+/// Reads a parameter manifest in the format of [`DEFAULT_PARAMETERS`] from `path`, e.g. with
+/// patched or additional entries, or returns the default one if `path` is `None`. Each entry is
+/// validated, including its CID, so that a broken manifest is reported before any download.
+pub(super) fn load_parameter_map(path: Option<&Path>) -> anyhow::Result<ParameterMap> {
+    let Some(path) = path else {
+        return Ok(default_parameters().clone());
+    };
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read parameter file {}", path.display()))?;
+    let entries: HashMap<String, serde_json::Value> = serde_json::from_str(&content)
+        .with_context(|| format!("Parameter file {} is not valid JSON", path.display()))?;
+    entries
+        .into_iter()
+        .map(|(name, entry)| {
+            let info = serde_json::from_value(entry).with_context(|| {
+                format!("Invalid entry {name} in parameter file {}", path.display())
+            })?;
+            Ok((name, info))
+        })
+        .collect()
+}
+
+/// Writes `params` as JSON in the format of [`DEFAULT_PARAMETERS`], so that it can be loaded
+/// again with [`load_parameter_map`]. Entries are sorted by name for stable output.
+pub(super) fn write_parameter_map(
+    params: &ParameterMap,
+    writer: impl std::io::Write,
+) -> anyhow::Result<()> {
+    let sorted: std::collections::BTreeMap<_, _> = params.iter().collect();
+    let mut serializer = serde_json::Serializer::with_formatter(
+        writer,
+        serde_json::ser::PrettyFormatter::with_indent(b"    "),
+    );
+    sorted.serialize(&mut serializer)?;
+    Ok(())
+}
+
 /// Data structure for retrieving the proof parameter data from provided JSON.
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub(super) struct ParameterData {
     #[serde(with = "crate::lotus_json::stringify")]
     pub cid: Cid,
@@ -51,31 +105,62 @@ pub(super) struct ParameterData {
 
 /// Ensures the parameter file is downloaded and has the correct checksum.
 /// This behavior can be disabled by setting the [`FOREST_FORCE_TRUST_PARAMS_ENV`] environment variable to 1.
+///
+/// The checksum only covers the first [`PROOF_DIGEST_LEN`] bytes of the BLAKE2b hash.
+///
+/// Verified files are recorded in a [`VerifiedSidecar`], so that they aren't hashed again until
+/// they change.
 pub(super) async fn check_parameter_file(path: &Path, info: &ParameterData) -> anyhow::Result<()> {
+    check_parameter_file_limited(path, info, true, None).await
+}
+
+/// Like [`check_parameter_file`], but verified files aren't recorded, so that the parameter
+/// directory isn't written to.
+pub(super) async fn check_parameter_file_read_only(
+    path: &Path,
+    info: &ParameterData,
+) -> anyhow::Result<()> {
+    check_parameter_file_limited(path, info, false, None).await
+}
+
+/// Like [`check_parameter_file`], but file reads are throttled by `limiter`, if any. Verified
+/// files are only recorded with `record`.
+async fn check_parameter_file_limited(
+    path: &Path,
+    info: &ParameterData,
+    record: bool,
+    limiter: Option<&RateLimiter>,
+) -> anyhow::Result<()> {
     if is_env_truthy(FOREST_FORCE_TRUST_PARAMS_ENV) {
         warn!("Assuming parameter files are okay. Do not use in production!");
         return Ok(());
     }
 
-    let hash = tokio::task::spawn_blocking({
-        let file = SyncFile::open(path)?;
-        move || -> Result<Hash, io::Error> {
-            let mut reader = SyncBufReader::new(file);
-            let mut hasher = Blake2b::new();
-            sync_copy(&mut reader, &mut hasher)?;
-            Ok(hasher.finalize())
+    let verified = VerifiedSidecar::new(path, info).await?;
+    if let Some(verified) = &verified {
+        if verified.is_covered_by(VerifiedSidecar::read(path).await.as_ref()) {
+            debug!(
+                "Parameter file {:?} is unchanged since it was verified",
+                path
+            );
+            return Ok(());
         }
-    })
-    .await??;
+    }
+
+    // synchronous reads can't wait for the rate limiter
+    #[cfg(feature = "blocking-param-hash")]
+    let hash = match limiter {
+        Some(_) => param_file_digest(path, limiter).await?,
+        None => param_file_digest_blocking(path).await?,
+    };
+    #[cfg(not(feature = "blocking-param-hash"))]
+    let hash = param_file_digest(path, limiter).await?;
 
     let hash_chunk = hash
         .as_bytes()
         .get(..PROOF_DIGEST_LEN)
         .context("invalid digest length")?;
-    if info.digest == hash_chunk {
-        debug!("Parameter file {:?} is ok", path);
-        Ok(())
-    } else {
+    if info.digest != hash_chunk {
         bail!(
             "Checksum mismatch in param file {:?}. ({:x?} != {:x?})",
             path,
@@ -83,15 +168,222 @@ pub(super) async fn check_parameter_file(path: &Path, info: &ParameterData) -> a
             info.digest,
         )
     }
+    debug!("Parameter file {:?} is ok", path);
+    if let Some(verified) = verified.filter(|_| record) {
+        // the check passed either way, it just has to be done again next time
+        if let Err(e) = verified.write(path).await {
+            debug!("Failed to record verified param file {:?}: {e}", path);
+        }
+    }
+    Ok(())
+}
+
+/// Sidecar file recording that the parameter file next to it passed [`check_parameter_file`].
+/// It is only trusted while the size and modification time of the parameter file are unchanged.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+struct VerifiedSidecar {
+    #[serde(with = "hex::serde")]
+    digest: [u8; PROOF_DIGEST_LEN],
+    len: u64,
+    modified: SystemTime,
+}
+
+impl VerifiedSidecar {
+    /// Returns the sidecar recording a successful check of the parameter file at `path` as it is
+    /// now, or `None` if the file system doesn't report modification times.
+    async fn new(path: &Path, info: &ParameterData) -> io::Result<Option<Self>> {
+        let metadata = tokio::fs::metadata(path).await?;
+        Ok(metadata.modified().ok().map(|modified| Self {
+            digest: info.digest,
+            len: metadata.len(),
+            modified,
+        }))
+    }
+
+    fn path(path: &Path) -> PathBuf {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(".verified");
+        sidecar.into()
+    }
+
+    /// Reads the sidecar of the parameter file at `path`. Missing or unreadable sidecars are
+    /// `None`, the file is then verified again.
+    async fn read(path: &Path) -> Option<Self> {
+        let content = tokio::fs::read(Self::path(path)).await.ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    async fn write(&self, path: &Path) -> anyhow::Result<()> {
+        tokio::fs::write(Self::path(path), serde_json::to_vec(self)?).await?;
+        Ok(())
+    }
+
+    /// Whether the `recorded` check also covers this one, i.e. it was made against the same
+    /// digest and the file hasn't changed since.
+    fn is_covered_by(&self, recorded: Option<&Self>) -> bool {
+        recorded.is_some_and(|recorded| {
+            recorded.digest == self.digest
+                && recorded.len == self.len
+                && recorded.modified == self.modified
+        })
+    }
+}
+
+/// Runs [`check_parameter_file`] on all `paths`, at most `concurrency` at a time. Unlike checking
+/// the files one by one, this doesn't stop at the first failure: all errors are reported together.
+///
+/// With `rate_limit_bytes_per_sec`, the combined read bandwidth of all checks is capped, so that
+/// verification doesn't saturate slow disks.
+pub(super) async fn check_all_parameter_files(
+    paths: &[(PathBuf, ParameterData)],
+    concurrency: usize,
+    rate_limit_bytes_per_sec: Option<u64>,
+) -> anyhow::Result<()> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let limiter = rate_limit_bytes_per_sec.map(|rate| Arc::new(RateLimiter::new(rate)));
+    let mut tasks = JoinSet::new();
+    for (path, info) in paths {
+        let (path, info) = (path.clone(), info.clone());
+        let semaphore = semaphore.clone();
+        let limiter = limiter.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await?;
+            check_parameter_file_limited(&path, &info, true, limiter.as_deref())
+                .await
+                .with_context(|| format!("Failed to verify param file {}", path.display()))
+        });
+    }
+
+    let mut errors = vec![];
+    while let Some(result) = tasks.join_next().await {
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => errors.push(e),
+            Err(e) => errors.push(e.into()),
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        bail!(
+            "{} of {} param files failed verification:\n{}",
+            errors.len(),
+            paths.len(),
+            errors.iter().map(|e| format!("{e:#}")).join("\n")
+        )
+    }
+}
+
+/// Token bucket limiting the combined read bandwidth of concurrent parameter file checks. The
+/// bucket holds up to one second worth of bytes. Reads larger than that go into debt, which later
+/// reads wait out.
+pub struct RateLimiter {
+    bytes_per_sec: u64,
+    // available bytes, negative while in debt, and when they were last refilled
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    pub(super) fn new(bytes_per_sec: u64) -> Self {
+        let bytes_per_sec = bytes_per_sec.max(1);
+        Self {
+            bytes_per_sec,
+            bucket: Mutex::new((bytes_per_sec as f64, Instant::now())),
+        }
+    }
+
+    /// Waits until `bytes` may be read. The lock is held while waiting, so readers are served
+    /// in turn.
+    async fn acquire(&self, bytes: u64) {
+        let rate = self.bytes_per_sec as f64;
+        let mut bucket = self.bucket.lock().await;
+        let (tokens, refilled) = &mut *bucket;
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*refilled).as_secs_f64() * rate).min(rate);
+        *refilled = now;
+        *tokens -= bytes as f64;
+        if *tokens < 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(-*tokens / rate)).await;
+        }
+    }
+}
+
+/// Computes the BLAKE2b digest of a parameter file, reading it in chunks without blocking the
+/// runtime, throttled by `limiter`, if any. The chunks are hashed on the blocking thread pool.
+pub async fn param_file_digest(path: &Path, limiter: Option<&RateLimiter>) -> io::Result<Hash> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Blake2b::new();
+    let mut buf = vec![0; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(hasher.finalize());
+        }
+        if let Some(limiter) = limiter {
+            limiter.acquire(n as u64).await;
+        }
+        (hasher, buf) = tokio::task::spawn_blocking(move || {
+            hasher.update(buf.get(..n).unwrap_or_default());
+            (hasher, buf)
+        })
+        .await?;
+    }
+}
+
+/// Like [`param_file_digest`], but reads the file synchronously on the blocking thread pool, for
+/// environments where async file IO is slow. Enabled by the `blocking-param-hash` feature.
+#[cfg_attr(not(feature = "blocking-param-hash"), allow(dead_code))]
+pub async fn param_file_digest_blocking(path: &Path) -> io::Result<Hash> {
+    let file = SyncFile::open(path)?;
+    tokio::task::spawn_blocking(move || {
+        let mut reader = SyncBufReader::new(file);
+        let mut hasher = Blake2b::new();
+        sync_copy(&mut reader, &mut hasher)?;
+        Ok(hasher.finalize())
+    })
+    .await?
 }
 
 // Proof parameter file directory. Defaults to
-// %DATA_DIR/filecoin-proof-parameters unless the FIL_PROOFS_PARAMETER_CACHE
-// environment variable is set.
-pub(super) fn param_dir(data_dir: &Path) -> PathBuf {
+// $XDG_DATA_HOME/forest/filecoin-proof-parameters on Linux and
+// %DATA_DIR/filecoin-proof-parameters elsewhere, unless the
+// FIL_PROOFS_PARAMETER_CACHE environment variable is set. An existing
+// %DATA_DIR/filecoin-proof-parameters directory keeps being used on Linux.
+pub fn param_dir(data_dir: &Path) -> PathBuf {
     std::env::var(PathBuf::from(PROOFS_PARAMETER_CACHE_ENV))
         .map(PathBuf::from)
-        .unwrap_or_else(|_| data_dir.join(PARAM_DIR))
+        .unwrap_or_else(|_| default_param_dir(data_dir))
+}
+
+#[cfg(target_os = "linux")]
+fn default_param_dir(data_dir: &Path) -> PathBuf {
+    // parameters downloaded before the XDG directory was the default aren't fetched again
+    let data_param_dir = data_dir.join(PARAM_DIR);
+    if data_param_dir.is_dir() {
+        return data_param_dir;
+    }
+    xdg_param_dir(
+        std::env::var_os("XDG_DATA_HOME").map(PathBuf::from),
+        directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_owned()),
+    )
+    .unwrap_or(data_param_dir)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_param_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(PARAM_DIR)
+}
+
+/// Parameter directory following the XDG Base Directory specification, i.e.
+/// `$XDG_DATA_HOME/forest/filecoin-proof-parameters`, where `$XDG_DATA_HOME` defaults to
+/// `$HOME/.local/share`. Relative `XDG_DATA_HOME` values are ignored, as the specification
+/// requires.
+#[cfg(target_os = "linux")]
+fn xdg_param_dir(xdg_data_home: Option<PathBuf>, home: Option<PathBuf>) -> Option<PathBuf> {
+    xdg_data_home
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| Some(home?.join(".local").join("share")))
+        .map(|dir| dir.join("forest").join(PARAM_DIR))
 }
 
 /// Forest uses a set of external crates for verifying the proofs generated by
@@ -100,8 +392,8 @@ pub(super) fn param_dir(data_dir: &Path) -> PathBuf {
 /// `/var/tmp/filecoin-proof-parameters` but it can be overridden by the
 /// `FIL_PROOFS_PARAMETER_CACHE` environment variable. Forest will automatically
 /// download the parameter files from Cloudflare/IPFS and verify their validity. For
-/// consistency, Forest will prefer to download the files it's local data
-/// directory. To this end, the `FIL_PROOFS_PARAMETER_CACHE` environment
+/// consistency, Forest will prefer to download the files to its own directory, see
+/// [`param_dir`]. To this end, the `FIL_PROOFS_PARAMETER_CACHE` environment
 /// variable is updated before the parameters are downloaded.
 ///
 /// More information available [here](https://github.com/filecoin-project/rust-fil-proofs/blob/8f5bd86be36a55e33b9b293ba22ea13ca1f28163/README.md?plain=1#L219-L235).
@@ -113,6 +405,183 @@ pub fn set_proofs_parameter_cache_dir_env(data_dir: &Path) {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn param_file_digest_matches_blocking() {
+        let mut file = tempfile::Builder::new().tempfile().unwrap();
+        // spans several chunks
+        let data: Vec<u8> = (0..3 * 1024 * 1024 + 7).map(|i| i as u8).collect();
+        std::io::Write::write_all(&mut file, &data).unwrap();
+        let expected = Blake2b::new().update(&data).finalize();
+        assert_eq!(
+            param_file_digest(file.path(), None).await.unwrap(),
+            expected
+        );
+        assert_eq!(
+            param_file_digest_blocking(file.path()).await.unwrap(),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn check_all_parameter_files_collects_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut paths = vec![];
+        for i in 0..4u8 {
+            let path = dir.path().join(format!("{i}.params"));
+            std::fs::write(&path, [i]).unwrap();
+            let hash = Blake2b::new().update(&[i]).finalize();
+            let mut digest: [u8; PROOF_DIGEST_LEN] =
+                hash.as_bytes()[..PROOF_DIGEST_LEN].try_into().unwrap();
+            // corrupt the expected digest of every other file
+            if i % 2 == 1 {
+                digest[0] ^= 0xff;
+            }
+            let info = ParameterData {
+                cid: Cid::default(),
+                digest,
+                sector_size: 32,
+            };
+            paths.push((path, info));
+        }
+
+        check_all_parameter_files(&paths[..1], 2, None)
+            .await
+            .unwrap();
+        for concurrency in [0, 1, 2, 8] {
+            let error = check_all_parameter_files(&paths, concurrency, None)
+                .await
+                .unwrap_err()
+                .to_string();
+            assert!(error.starts_with("2 of 4 param files failed verification"));
+            assert!(error.contains("1.params") && error.contains("3.params"));
+            assert!(!error.contains("0.params") && !error.contains("2.params"));
+        }
+    }
+
+    #[tokio::test]
+    async fn check_all_parameter_files_rate_limited() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = (0..4u8)
+            .map(|i| {
+                let path = dir.path().join(format!("{i}.params"));
+                let data = [i; 1000];
+                std::fs::write(&path, data).unwrap();
+                let hash = Blake2b::new().update(&data).finalize();
+                let info = ParameterData {
+                    cid: Cid::default(),
+                    digest: hash.as_bytes()[..PROOF_DIGEST_LEN].try_into().unwrap(),
+                    sector_size: 32,
+                };
+                (path, info)
+            })
+            .collect_vec();
+
+        // the bucket starts with one second worth of bytes, the other 2000 bytes take a second
+        let start = Instant::now();
+        check_all_parameter_files(&paths, 4, Some(2000))
+            .await
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(900));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn xdg_param_dir_fallbacks() {
+        let home = Some(PathBuf::from("/home/forest"));
+        assert_eq!(
+            xdg_param_dir(Some("/xdg/data".into()), home.clone()),
+            Some(PathBuf::from("/xdg/data/forest/filecoin-proof-parameters"))
+        );
+        // relative paths are invalid per the specification
+        for xdg_data_home in [None, Some("relative/data".into()), Some("".into())] {
+            assert_eq!(
+                xdg_param_dir(xdg_data_home, home.clone()),
+                Some(PathBuf::from(
+                    "/home/forest/.local/share/forest/filecoin-proof-parameters"
+                ))
+            );
+        }
+        assert_eq!(xdg_param_dir(None, None), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn default_param_dir_keeps_data_dir() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let data_param_dir = data_dir.path().join(PARAM_DIR);
+        std::fs::create_dir(&data_param_dir).unwrap();
+        assert_eq!(default_param_dir(data_dir.path()), data_param_dir);
+    }
+
+    #[test]
+    fn load_parameter_map_from_file() {
+        assert_eq!(
+            load_parameter_map(None).unwrap().len(),
+            default_parameters().len()
+        );
+
+        let mut file = tempfile::Builder::new().tempfile().unwrap();
+        std::io::Write::write_all(
+            &mut file,
+            br#"{
+                "v28-test.vk": {
+                    "cid": "QmUa7f9JtJMsqJJ3s3ZXk6WyF4xJLE8FiqYskZGgk8GCDv",
+                    "digest": "00112233445566778899aabbccddeeff",
+                    "sector_size": 2048
+                }
+            }"#,
+        )
+        .unwrap();
+        let params = load_parameter_map(Some(file.path())).unwrap();
+        assert_eq!(params.len(), 1);
+        assert_eq!(params["v28-test.vk"].sector_size, 2048);
+
+        std::fs::write(file.path(), "not json").unwrap();
+        let error = load_parameter_map(Some(file.path())).unwrap_err();
+        assert!(error.to_string().contains("is not valid JSON"));
+
+        std::fs::write(
+            file.path(),
+            r#"{"v28-test.vk": {"cid": "not a cid", "digest": "00112233445566778899aabbccddeeff", "sector_size": 2048}}"#,
+        )
+        .unwrap();
+        let error = load_parameter_map(Some(file.path())).unwrap_err();
+        assert!(error.to_string().contains("Invalid entry v28-test.vk"));
+
+        assert!(load_parameter_map(Some(Path::new("cthulhuazathoh.json"))).is_err());
+    }
+
+    #[test]
+    fn parameter_map_json_roundtrip() {
+        let params: ParameterMap = serde_json::from_str(DEFAULT_PARAMETERS).unwrap();
+        let mut json = vec![];
+        write_parameter_map(&params, &mut json).unwrap();
+        let reparsed: ParameterMap = serde_json::from_slice(&json).unwrap();
+        assert_eq!(reparsed, params);
+
+        // the CIDs are written in their original string form
+        let name = "v28-empty-sector-update-merkletree-poseidon_hasher-8-0-0-61fa69f38b9cc771ba27b670124714b4ea77fbeae05e377fb859c4a43b73a30c.vk";
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(
+            value[name]["cid"],
+            "QmUa7f9JtJMsqJJ3s3ZXk6WyF4xJLE8FiqYskZGgk8GCDv"
+        );
+        assert_eq!(value[name]["digest"], "994c5b7d450ca9da348c910689f2dc7f");
+    }
+
+    #[test]
+    fn default_parameters_concurrent() {
+        let maps: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8).map(|_| scope.spawn(default_parameters)).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let expected: ParameterMap = serde_json::from_str(DEFAULT_PARAMETERS).unwrap();
+        for map in maps {
+            assert!(std::ptr::eq(map, default_parameters()));
+            assert_eq!(map.len(), expected.len());
+        }
+    }
+
     #[tokio::test]
     async fn test_proof_file_check() {
         let tempfile = tempfile::Builder::new().tempfile().unwrap();
@@ -139,6 +608,44 @@ mod tests {
         check_parameter_file(path, &param_data).await.unwrap()
     }
 
+    #[tokio::test]
+    async fn test_proof_file_check_verified_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.params");
+        let sidecar = dir.path().join("test.params.verified");
+        std::fs::write(&path, b"Cthulhu fhtagn!").unwrap();
+        let hash = Blake2b::new().update(b"Cthulhu fhtagn!").finalize();
+        let info = ParameterData {
+            cid: Cid::default(),
+            digest: hash.as_bytes()[..PROOF_DIGEST_LEN].try_into().unwrap(),
+            sector_size: 32,
+        };
+
+        // no sidecar yet, the file is hashed and recorded
+        check_parameter_file(&path, &info).await.unwrap();
+        assert!(sidecar.exists());
+
+        // the sidecar is trusted while the file is unchanged, even if its content isn't
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+        std::fs::write(&path, b"Cthulhu fhtagn?").unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(modified).unwrap();
+        check_parameter_file(&path, &info).await.unwrap();
+
+        // a changed modification time invalidates the sidecar
+        file.set_modified(modified + Duration::from_secs(1))
+            .unwrap();
+        let error = check_parameter_file(&path, &info).await.unwrap_err();
+        assert!(error.to_string().contains("Checksum mismatch"));
+
+        // restoring it makes the sidecar trusted again, until it goes missing
+        file.set_modified(modified).unwrap();
+        check_parameter_file(&path, &info).await.unwrap();
+        std::fs::remove_file(&sidecar).unwrap();
+        let error = check_parameter_file(&path, &info).await.unwrap_err();
+        assert!(error.to_string().contains("Checksum mismatch"));
+    }
+
     #[tokio::test]
     async fn test_proof_file_check_no_file() {
         let param_data = ParameterData {
@@ -9,26 +9,35 @@
 
 use std::{
     io::{self, ErrorKind},
+    num::NonZeroUsize,
     path::{Path, PathBuf},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
-use crate::{
-    shim::sector::SectorSize,
-    utils::{
-        misc::env::is_env_truthy,
-        net::{download_ipfs_file_trustlessly, global_http_client},
-    },
+use crate::utils::{
+    misc::env::{env_or_default, is_env_truthy},
+    net::{download_ipfs_file_trustlessly, global_http_client},
 };
+use ahash::HashSet;
 use anyhow::{bail, Context};
 use backon::{ExponentialBuilder, Retryable};
-use futures::{stream::FuturesUnordered, AsyncWriteExt, TryStreamExt};
-use tokio::fs::{self};
+use cid::Cid;
+use futures::{AsyncWriteExt, TryStreamExt};
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use itertools::Itertools as _;
+use nonzero_ext::nonzero;
+use tokio::{
+    fs::{self},
+    task::JoinSet,
+};
 use tracing::{debug, info, warn};
+use url::Url;
 
 use super::parameters::{
-    check_parameter_file, param_dir, ParameterData, ParameterMap, DEFAULT_PARAMETERS,
-    PROOFS_PARAMETER_CACHE_ENV,
+    check_all_parameter_files, check_parameter_file, check_parameter_file_read_only,
+    default_parameters, load_parameter_map, param_dir, write_parameter_map, ParameterData,
+    ParameterMap, PROOFS_PARAMETER_CACHE_ENV,
 };
 
 /// Default IPFS gateway to use for fetching parameters.
@@ -43,66 +52,188 @@ const PROOFS_ONLY_IPFS_GATEWAY_ENV: &str = "FOREST_PROOFS_ONLY_IPFS_GATEWAY";
 /// Running Forest requires the download of chain's proof parameters which are large files, by default are hosted outside of China and very slow to download there.
 /// To get around that, users should set this variable to:
 /// <https://proof-parameters.s3.cn-south-1.jdcloud-oss.com/ipfs/>
+/// Multiple gateways can be given as a comma-separated list, in which case they are tried in order.
 const IPFS_GATEWAY_ENV: &str = "IPFS_GATEWAY";
 
+/// How long to wait for a connection to an IPFS gateway.
+const IPFS_GATEWAY_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long an IPFS gateway may send no data before the download attempt fails.
+const IPFS_GATEWAY_READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Maximum time, in seconds, spent downloading a parameter file from the IPFS gateways, all
+/// gateways and retries included. Defaults to [`DEFAULT_IPFS_GATEWAY_DEADLINE_SECS`].
+const IPFS_GATEWAY_DEADLINE_ENV: &str = "FOREST_PROOFS_IPFS_GATEWAY_DEADLINE_SECS";
+/// Large parameter files take hours to download on slow connections.
+const DEFAULT_IPFS_GATEWAY_DEADLINE_SECS: u64 = 4 * 60 * 60;
+
+/// Default number of parameter files downloaded in parallel.
+pub const DEFAULT_PARAMS_FETCH_JOBS: NonZeroUsize = nonzero!(4usize);
+
 /// Sector size options for fetching.
 pub enum SectorSizeOpt {
     /// All keys and proofs gen parameters
     All,
     /// Only verification parameters
     Keys,
-    /// All keys and proofs gen parameters for a given size
-    Size(SectorSize),
+    /// All keys and proofs gen parameters for the given sizes, in bytes
+    Sizes(HashSet<u64>),
+}
+
+impl SectorSizeOpt {
+    /// Whether the parameter file `name` is needed for these sector size options.
+    fn matches(&self, name: &str, info: &ParameterData) -> bool {
+        match self {
+            SectorSizeOpt::Keys => !name.ends_with("params"),
+            SectorSizeOpt::Sizes(sizes) => {
+                sizes.contains(&info.sector_size) || !name.ends_with(".params")
+            }
+            SectorSizeOpt::All => true,
+        }
+    }
+}
+
+/// Where the parameter files are downloaded from.
+#[derive(Debug, Clone)]
+struct ParamSources {
+    /// Base URL of the Cloudflare R2 bucket, tried first unless `None`, see
+    /// [`PROOFS_ONLY_IPFS_GATEWAY_ENV`].
+    cloudflare: Option<Url>,
+    /// IPFS gateways to fall back to, in order.
+    ipfs_gateways: Vec<Url>,
+    /// How files are downloaded from `ipfs_gateways`.
+    ipfs: IpfsFetchConfig,
+}
+
+impl ParamSources {
+    fn from_env() -> anyhow::Result<Self> {
+        let cloudflare = if is_env_truthy(PROOFS_ONLY_IPFS_GATEWAY_ENV) {
+            None
+        } else {
+            Some(Url::parse(&format!(
+                "https://{CLOUDFLARE_PROOF_PARAMETER_DOMAIN}/"
+            ))?)
+        };
+        Ok(Self {
+            cloudflare,
+            ipfs_gateways: ipfs_gateways()?,
+            ipfs: IpfsFetchConfig::from_env()?,
+        })
+    }
+}
+
+/// How parameter files are downloaded from IPFS gateways.
+#[derive(Debug, Clone)]
+struct IpfsFetchConfig {
+    /// Client with [`IPFS_GATEWAY_CONNECT_TIMEOUT`] and [`IPFS_GATEWAY_READ_TIMEOUT`], so that an
+    /// unresponsive gateway fails the attempt instead of holding up the download.
+    client: reqwest::Client,
+    /// Backoff of the retries of each gateway.
+    backoff: ExponentialBuilder,
+    /// Maximum time spent on a file, see [`IPFS_GATEWAY_DEADLINE_ENV`].
+    deadline: Duration,
+}
+
+impl IpfsFetchConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .connect_timeout(IPFS_GATEWAY_CONNECT_TIMEOUT)
+                .read_timeout(IPFS_GATEWAY_READ_TIMEOUT)
+                .build()?,
+            backoff: ExponentialBuilder::default(),
+            deadline: Duration::from_secs(env_or_default(
+                IPFS_GATEWAY_DEADLINE_ENV,
+                DEFAULT_IPFS_GATEWAY_DEADLINE_SECS,
+            )),
+        })
+    }
 }
 
-/// Ensures the parameter files are downloaded to cache dir
-pub async fn ensure_params_downloaded() -> anyhow::Result<()> {
+/// Ensures the parameter files of the manifest at `params_file`, or the built-in one if `None`,
+/// are downloaded to cache dir
+pub async fn ensure_params_downloaded(params_file: Option<&Path>) -> anyhow::Result<()> {
     let data_dir = std::env::var(PROOFS_PARAMETER_CACHE_ENV).unwrap_or_default();
     if data_dir.is_empty() {
         anyhow::bail!("Proof parameter data dir is not set");
     }
-    get_params_default(Path::new(&data_dir), SectorSizeOpt::Keys, false).await?;
+    let data_dir = Path::new(&data_dir);
+    match params_file {
+        Some(params_file) => {
+            get_params_from_file(
+                data_dir,
+                Some(params_file),
+                SectorSizeOpt::Keys,
+                false,
+                None,
+                DEFAULT_PARAMS_FETCH_JOBS,
+            )
+            .await?
+        }
+        None => get_params_default(data_dir, SectorSizeOpt::Keys, false).await?,
+    }
 
     Ok(())
 }
 
 /// Get proofs parameters and all verification keys for a given sector size
-/// given a parameter JSON manifest.
-pub async fn get_params(
+/// given a parameter manifest. At most `jobs` files are downloaded at a time, each
+/// with its own progress bar if `progress` is set. Verifying the downloaded files
+/// reads at most `verify_rate_limit` bytes per second, if set.
+async fn get_params(
     data_dir: &Path,
-    param_json: &str,
+    params: &ParameterMap,
     storage_size: SectorSizeOpt,
     dry_run: bool,
+    verify_rate_limit: Option<u64>,
+    jobs: NonZeroUsize,
+    progress: bool,
 ) -> Result<(), anyhow::Error> {
+    if matches!(&storage_size, SectorSizeOpt::Sizes(sizes) if sizes.is_empty()) {
+        bail!("No sector sizes given, at least one is required to fetch proofs parameters");
+    }
+
     // Just print out the parameters download directory path and exit.
     if dry_run {
         println!("{}", param_dir(data_dir).to_string_lossy());
         return Ok(());
     }
 
-    fs::create_dir_all(param_dir(data_dir)).await?;
-
-    let params: ParameterMap = serde_json::from_str(param_json)?;
+    let dir = param_dir(data_dir);
+    fs::create_dir_all(&dir).await?;
 
-    FuturesUnordered::from_iter(
-        params
-            .into_iter()
-            .filter(|(name, info)| match storage_size {
-                SectorSizeOpt::Keys => !name.ends_with("params"),
-                SectorSizeOpt::Size(size) => {
-                    size as u64 == info.sector_size || !name.ends_with(".params")
-                }
-                SectorSizeOpt::All => true,
-            })
-            .map(|(name, info)| async move {
-                let data_dir_clone = data_dir.to_owned();
-                fetch_verify_params(&data_dir_clone, &name, Arc::new(info)).await
-            }),
-    )
-    .try_collect::<Vec<_>>()
-    .await?;
+    let wanted = params
+        .iter()
+        .filter(|(name, info)| storage_size.matches(name, info))
+        .map(|(name, info)| (name.clone(), info.clone()))
+        .collect_vec();
+    let bars = if progress {
+        MultiProgress::new()
+    } else {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    };
+    let start = Instant::now();
+    let downloaded = download_params(&dir, wanted, ParamSources::from_env()?, jobs, &bars).await?;
+    if !downloaded.is_empty() {
+        let mut bytes = 0;
+        for (path, _) in &downloaded {
+            bytes += fs::metadata(path).await?.len();
+        }
+        let summary = format!(
+            "Downloaded {} param files, {} in {}",
+            downloaded.len(),
+            HumanBytes(bytes),
+            humantime::format_duration(Duration::from_secs(start.elapsed().as_secs()))
+        );
+        if progress {
+            println!("{summary}");
+        } else {
+            info!("{summary}");
+        }
+    }
 
-    Ok(())
+    // Verify the freshly downloaded files
+    let concurrency = std::thread::available_parallelism().map_or(1, |n| n.get());
+    check_all_parameter_files(&downloaded, concurrency, verify_rate_limit).await
 }
 
 /// Get proofs parameters and all verification keys for a given sector size
@@ -113,18 +244,226 @@ pub async fn get_params_default(
     storage_size: SectorSizeOpt,
     dry_run: bool,
 ) -> Result<(), anyhow::Error> {
-    get_params(data_dir, DEFAULT_PARAMETERS, storage_size, dry_run).await
+    get_params(
+        data_dir,
+        default_parameters(),
+        storage_size,
+        dry_run,
+        None,
+        DEFAULT_PARAMS_FETCH_JOBS,
+        false,
+    )
+    .await
 }
 
-async fn fetch_verify_params(
+/// Get proofs parameters and all verification keys for a given sector size
+/// using the manifest at `params_file`, or the default one if `None`. See
+/// [`load_parameter_map`]. Progress bars of the downloads, at most `jobs` in
+/// parallel, are shown on the terminal.
+pub async fn get_params_from_file(
     data_dir: &Path,
-    name: &str,
-    info: Arc<ParameterData>,
+    params_file: Option<&Path>,
+    storage_size: SectorSizeOpt,
+    dry_run: bool,
+    verify_rate_limit: Option<u64>,
+    jobs: NonZeroUsize,
 ) -> Result<(), anyhow::Error> {
-    let path: PathBuf = param_dir(data_dir).join(name);
+    let params = load_parameter_map(params_file)?;
+    get_params(
+        data_dir,
+        &params,
+        storage_size,
+        dry_run,
+        verify_rate_limit,
+        jobs,
+        true,
+    )
+    .await
+}
+
+/// Writes the parameter manifest at `params_file`, or the default one if `None`, to `output` in
+/// the format of `parameters.json`. See [`load_parameter_map`].
+pub fn export_params(params_file: Option<&Path>, output: &Path) -> anyhow::Result<()> {
+    let params = load_parameter_map(params_file)?;
+    let file = std::fs::File::create(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut writer = std::io::BufWriter::new(file);
+    write_parameter_map(&params, &mut writer)?;
+    std::io::Write::flush(&mut writer)?;
+    Ok(())
+}
+
+/// New values for some fields of a parameter manifest entry, see [`set_param_entry`]. The CID and
+/// digest are given as in `parameters.json`.
+#[derive(Debug, Clone, Default)]
+pub struct ParamEntryPatch {
+    pub cid: Option<String>,
+    pub digest: Option<String>,
+    pub sector_size: Option<u64>,
+}
+
+/// Patches the entry `name` of the parameter manifest at `path` in place, e.g. to pin a locally
+/// computed parameter file. Fields not set in `patch` are kept. The file is left untouched if the
+/// entry doesn't exist or would be invalid after the patch.
+pub fn set_param_entry(path: &Path, name: &str, patch: ParamEntryPatch) -> anyhow::Result<()> {
+    let mut params = load_parameter_map(Some(path))?;
+    let info = params
+        .get_mut(name)
+        .with_context(|| format!("No entry {name} in parameter file {}", path.display()))?;
+    let mut entry = serde_json::to_value(&*info)?;
+    let fields = entry
+        .as_object_mut()
+        .context("Parameter entries must be JSON objects")?;
+    if let Some(cid) = patch.cid {
+        fields.insert("cid".into(), cid.into());
+    }
+    if let Some(digest) = patch.digest {
+        fields.insert("digest".into(), digest.into());
+    }
+    if let Some(sector_size) = patch.sector_size {
+        fields.insert("sector_size".into(), sector_size.into());
+    }
+    *info = serde_json::from_value(entry)
+        .with_context(|| format!("Invalid entry {name} after the update"))?;
+    let mut content = vec![];
+    write_parameter_map(&params, &mut content)?;
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Whether a parameter file is in the parameter directory and matches its manifest entry, see
+/// [`param_files_status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamFileStatus {
+    Missing,
+    Verified,
+    /// The file is present, but failed verification with the given error.
+    Invalid(String),
+}
+
+/// A parameter file of the manifest, see [`param_files_status`].
+#[derive(Debug, Clone)]
+pub struct ParamFileInfo {
+    pub name: String,
+    /// Expected BLAKE2b digest prefix, as in `parameters.json`
+    pub digest: String,
+    pub status: ParamFileStatus,
+}
+
+/// Checks every file of the parameter manifest at `params_file`, or the default one if `None`,
+/// against the copy in `dir`, sorted by name. Files are verified as when fetching them, so
+/// unchanged files that were verified before aren't hashed again, but newly verified files aren't
+/// recorded as such.
+pub async fn param_files_status(
+    dir: &Path,
+    params_file: Option<&Path>,
+) -> anyhow::Result<Vec<ParamFileInfo>> {
+    let params = load_parameter_map(params_file)?;
+    let mut files = vec![];
+    for (name, info) in params.into_iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
+        let path = dir.join(&name);
+        let status = if !fs::try_exists(&path).await? {
+            ParamFileStatus::Missing
+        } else {
+            match check_parameter_file_read_only(&path, &info).await {
+                Ok(()) => ParamFileStatus::Verified,
+                Err(e) => ParamFileStatus::Invalid(format!("{e:#}")),
+            }
+        };
+        files.push(ParamFileInfo {
+            name,
+            digest: hex::encode(info.digest),
+            status,
+        });
+    }
+    Ok(files)
+}
+
+/// Overrides the IPFS gateways used for fetching the parameters, see [`IPFS_GATEWAY_ENV`].
+/// Does nothing if `gateways` is empty, so the environment variable or the default gateway is
+/// used instead.
+pub fn set_proofs_gateways_env(gateways: &[Url]) {
+    if !gateways.is_empty() {
+        std::env::set_var(IPFS_GATEWAY_ENV, gateways.iter().join(","));
+    }
+}
 
-    match check_parameter_file(&path, &info).await {
-        Ok(()) => return Ok(()),
+/// Downloads the parameter files of `params` to `dir` that are missing or invalid, at most `jobs`
+/// at a time. Each download gets a progress bar in `bars`. Returns the downloaded files, which
+/// still need to be verified. Stops at the first failed download.
+async fn download_params(
+    dir: &Path,
+    params: Vec<(String, ParameterData)>,
+    sources: ParamSources,
+    jobs: NonZeroUsize,
+    bars: &MultiProgress,
+) -> anyhow::Result<Vec<(PathBuf, ParameterData)>> {
+    let sources = Arc::new(sources);
+    let mut params = params.into_iter();
+    let mut tasks = JoinSet::new();
+    let mut downloaded = vec![];
+    loop {
+        while tasks.len() < jobs.get() {
+            let Some((name, info)) = params.next() else {
+                break;
+            };
+            let path = dir.join(&name);
+            let pb = bars.add(param_progress_bar(&name));
+            let sources = sources.clone();
+            tasks.spawn(async move {
+                match fetch_params(&path, &name, &info, &sources, &pb).await {
+                    Ok(true) => {
+                        pb.finish_with_message("done");
+                        Ok(Some((path, info)))
+                    }
+                    Ok(false) => {
+                        pb.finish_and_clear();
+                        Ok(None)
+                    }
+                    Err(e) => {
+                        pb.abandon_with_message("failed");
+                        Err(e)
+                    }
+                }
+            });
+        }
+        let Some(result) = tasks.join_next().await else {
+            break;
+        };
+        downloaded.extend(result??);
+    }
+    Ok(downloaded)
+}
+
+const PARAM_SPINNER_TEMPLATE: &str =
+    "{spinner} {prefix} {bytes} at {binary_bytes_per_sec} in {elapsed} {msg}";
+const PARAM_BAR_TEMPLATE: &str =
+    "{spinner} {prefix} [{bar:30}] {bytes}/{total_bytes} at {binary_bytes_per_sec}, {eta} left {msg}";
+
+/// Spinner showing the download of the parameter file `name`, turned into a bar once the size of
+/// the file is known.
+fn param_progress_bar(name: &str) -> ProgressBar {
+    let pb = ProgressBar::new_spinner()
+        .with_style(
+            ProgressStyle::with_template(PARAM_SPINNER_TEMPLATE)
+                .expect("indicatif template must be valid"),
+        )
+        .with_prefix(name.to_owned());
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb
+}
+
+/// Downloads the parameter file `name` to `path` unless a valid copy already exists. Returns
+/// whether the file was downloaded, in which case it still needs to be verified.
+async fn fetch_params(
+    path: &Path,
+    name: &str,
+    info: &ParameterData,
+    sources: &ParamSources,
+    pb: &ProgressBar,
+) -> Result<bool, anyhow::Error> {
+    pb.set_message("checking");
+    match check_parameter_file(path, info).await {
+        Ok(()) => return Ok(false),
         Err(e) => {
             if let Some(e) = e.downcast_ref::<io::Error>() {
                 if e.kind() == ErrorKind::NotFound {
@@ -136,46 +475,112 @@ async fn fetch_verify_params(
         }
     }
 
-    if is_env_truthy(PROOFS_ONLY_IPFS_GATEWAY_ENV) {
-        fetch_params_ipfs_gateway(&path, &info).await?;
-    } else if let Err(e) = fetch_params_cloudflare(name, &path).await {
-        warn!("Failed to fetch param file from Cloudflare R2: {e:?}. Falling back to IPFS gateway",);
-        fetch_params_ipfs_gateway(&path, &info).await?;
+    match &sources.cloudflare {
+        Some(cloudflare) => {
+            if let Err(e) = fetch_params_cloudflare(cloudflare, name, path, pb).await {
+                warn!(
+                    "Failed to fetch param file from Cloudflare R2: {e:?}. Falling back to IPFS gateway",
+                );
+                fetch_from_ipfs_gateways(
+                    &sources.ipfs,
+                    &sources.ipfs_gateways,
+                    &info.cid,
+                    path,
+                    pb,
+                )
+                .await?;
+            }
+        }
+        None => {
+            fetch_from_ipfs_gateways(&sources.ipfs, &sources.ipfs_gateways, &info.cid, path, pb)
+                .await?
+        }
     }
 
-    check_parameter_file(&path, &info).await?;
-    Ok(())
+    Ok(true)
 }
 
-async fn fetch_params_ipfs_gateway(path: &Path, info: &ParameterData) -> anyhow::Result<()> {
-    let gateway = std::env::var(IPFS_GATEWAY_ENV)
+/// Returns the IPFS gateways set via the [`IPFS_GATEWAY_ENV`] environment variable as a
+/// comma-separated list, or [`DEFAULT_IPFS_GATEWAY`] if unset.
+fn ipfs_gateways() -> anyhow::Result<Vec<Url>> {
+    std::env::var(IPFS_GATEWAY_ENV)
         .unwrap_or_else(|_| DEFAULT_IPFS_GATEWAY.to_owned())
-        .parse()?;
-    info!(
-        "Fetching param file {path} from {gateway}",
-        path = path.display()
-    );
-    let result = (|| download_ipfs_file_trustlessly(&info.cid, &gateway, path))
-        .retry(ExponentialBuilder::default())
-        .notify(|err, dur| {
-            debug!(
-                "retrying download_ipfs_file_trustlessly {err} after {}",
-                humantime::format_duration(dur)
-            );
+        .split(',')
+        .map(str::trim)
+        .filter(|gateway| !gateway.is_empty())
+        .map(|gateway| {
+            Url::parse(gateway).with_context(|| format!("Invalid IPFS gateway {gateway}"))
         })
-        .await;
+        .collect()
+}
 
-    debug!(
-        "Done fetching param file {path} from {gateway}",
-        path = path.display(),
-    );
-    result
+/// Tries the given gateways in order until one of them serves the file. Each gateway is retried
+/// with `config.backoff` before falling back to the next one, and the whole download is bounded by
+/// `config.deadline`. The file is verified against `cid` while streaming, so a misbehaving gateway
+/// cannot corrupt it. Only the gateway in use is shown by `pb`, as the download progress is logged
+/// instead.
+async fn fetch_from_ipfs_gateways(
+    config: &IpfsFetchConfig,
+    gateways: &[Url],
+    cid: &Cid,
+    path: &Path,
+    pb: &ProgressBar,
+) -> anyhow::Result<()> {
+    let fetch = async {
+        for gateway in gateways {
+            pb.set_message(format!("from {gateway}"));
+            info!(
+                "Fetching param file {path} from {gateway}",
+                path = path.display()
+            );
+            let download = (|| download_ipfs_file_trustlessly(&config.client, cid, gateway, path))
+                .retry(config.backoff)
+                .notify(|err, dur| {
+                    debug!(
+                        "retrying download_ipfs_file_trustlessly {err} after {}",
+                        humantime::format_duration(dur)
+                    );
+                });
+            match download.await {
+                Ok(()) => {
+                    debug!(
+                        "Done fetching param file {path} from {gateway}",
+                        path = path.display(),
+                    );
+                    return Ok(());
+                }
+                Err(e) => warn!(
+                    "Failed to fetch param file {path} from {gateway}: {e:?}",
+                    path = path.display()
+                ),
+            }
+        }
+        bail!(
+            "Failed to fetch param file {} from all IPFS gateways",
+            path.display()
+        )
+    };
+    tokio::time::timeout(config.deadline, fetch)
+        .await
+        .with_context(|| {
+            format!(
+                "Timed out fetching param file {} from the IPFS gateways after {}",
+                path.display(),
+                humantime::format_duration(config.deadline)
+            )
+        })?
 }
 
 /// Downloads the parameter file from Cloudflare R2 to the given path. It wraps the [`download_from_cloudflare`] function with a retry and timeout mechanisms.
-async fn fetch_params_cloudflare(name: &str, path: &Path) -> anyhow::Result<()> {
-    info!("Fetching param file {name} from Cloudflare R2 {CLOUDFLARE_PROOF_PARAMETER_DOMAIN}");
-    let result = (|| download_from_cloudflare(name, path))
+async fn fetch_params_cloudflare(
+    bucket: &Url,
+    name: &str,
+    path: &Path,
+    pb: &ProgressBar,
+) -> anyhow::Result<()> {
+    info!("Fetching param file {name} from Cloudflare R2 {bucket}");
+    pb.set_message("from Cloudflare R2");
+    let result = (|| download_from_cloudflare(bucket, name, path, pb))
         .retry(ExponentialBuilder::default())
         .notify(|err, dur| {
             debug!(
@@ -191,13 +596,16 @@ async fn fetch_params_cloudflare(name: &str, path: &Path) -> anyhow::Result<()>
     result
 }
 
-/// Downloads the parameter file from Cloudflare R2 to the given path. In case of an error,
-/// the file is not written to the final path to avoid corrupted files.
-async fn download_from_cloudflare(name: &str, path: &Path) -> anyhow::Result<()> {
+/// Downloads the parameter file from Cloudflare R2 to the given path, reporting the progress to
+/// `pb`. In case of an error, the file is not written to the final path to avoid corrupted files.
+async fn download_from_cloudflare(
+    bucket: &Url,
+    name: &str,
+    path: &Path,
+    pb: &ProgressBar,
+) -> anyhow::Result<()> {
     let response = global_http_client()
-        .get(format!(
-            "https://{CLOUDFLARE_PROOF_PARAMETER_DOMAIN}/{name}"
-        ))
+        .get(bucket.join(name)?)
         .send()
         .await
         .context("Failed to fetch param file from Cloudflare R2")?;
@@ -217,8 +625,19 @@ async fn download_from_cloudflare(name: &str, path: &Path) -> anyhow::Result<()>
         .context("Failed to create temp file")?
         .into_temp_path();
 
+    // a retry starts over
+    pb.set_position(0);
+    if let Some(length) = response.content_length() {
+        pb.set_length(length);
+        pb.set_style(
+            ProgressStyle::with_template(PARAM_BAR_TEMPLATE)
+                .expect("indicatif template must be valid")
+                .progress_chars("=> "),
+        );
+    }
     let reader = response
         .bytes_stream()
+        .inspect_ok(|chunk| pb.inc(chunk.len() as u64))
         .map_err(std::io::Error::other)
         .into_async_read();
 
@@ -233,3 +652,349 @@ async fn download_from_cloudflare(name: &str, path: &Path) -> anyhow::Result<()>
     tmp.persist(path).context("Failed to persist temp file")?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shim::sector::SectorSize;
+
+    fn matching_params(storage_size: SectorSizeOpt) -> Vec<String> {
+        let mut names: Vec<_> = default_parameters()
+            .iter()
+            .filter(|(name, info)| storage_size.matches(name, info))
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn sector_sizes_filter() {
+        let keys = matching_params(SectorSizeOpt::Keys);
+        assert!(keys.iter().all(|name| !name.ends_with(".params")));
+
+        let sizes = matching_params(SectorSizeOpt::Sizes(HashSet::from_iter([
+            SectorSize::_32GiB as u64,
+        ])));
+        assert!(sizes.len() > keys.len());
+
+        let both = matching_params(SectorSizeOpt::Sizes(HashSet::from_iter([
+            SectorSize::_32GiB as u64,
+            SectorSize::_64GiB as u64,
+        ])));
+        assert!(both.len() > sizes.len());
+        assert!(both.len() < matching_params(SectorSizeOpt::All).len());
+    }
+
+    #[tokio::test]
+    async fn empty_sector_sizes() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let err = get_params_default(
+            data_dir.path(),
+            SectorSizeOpt::Sizes(HashSet::default()),
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("No sector sizes given"));
+    }
+
+    #[tokio::test]
+    async fn param_files_status_of_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = b"parameters";
+        let digest = hex::encode(&blake2b_simd::blake2b(content).as_bytes()[..16]);
+        let manifest = dir.path().join("parameters.json");
+        std::fs::write(
+            &manifest,
+            format!(
+                r#"{{
+                    "a.vk": {{"cid": "QmUa7f9JtJMsqJJ3s3ZXk6WyF4xJLE8FiqYskZGgk8GCDv", "digest": "{digest}", "sector_size": 2048}},
+                    "b.vk": {{"cid": "QmUa7f9JtJMsqJJ3s3ZXk6WyF4xJLE8FiqYskZGgk8GCDv", "digest": "00112233445566778899aabbccddeeff", "sector_size": 2048}},
+                    "c.vk": {{"cid": "QmUa7f9JtJMsqJJ3s3ZXk6WyF4xJLE8FiqYskZGgk8GCDv", "digest": "{digest}", "sector_size": 2048}}
+                }}"#
+            ),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("a.vk"), content).unwrap();
+        std::fs::write(dir.path().join("b.vk"), content).unwrap();
+
+        let files = param_files_status(dir.path(), Some(&manifest))
+            .await
+            .unwrap();
+        let names: Vec<_> = files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(names, ["a.vk", "b.vk", "c.vk"]);
+        assert_eq!(files[0].status, ParamFileStatus::Verified);
+        assert_eq!(files[0].digest, digest);
+        assert!(
+            matches!(&files[1].status, ParamFileStatus::Invalid(e) if e.contains("Checksum mismatch"))
+        );
+        assert_eq!(files[2].status, ParamFileStatus::Missing);
+        // checking the status doesn't record verified files
+        assert!(!dir.path().join("a.vk.verified").exists());
+    }
+
+    #[test]
+    fn set_param_entry_patches_fields() {
+        use crate::utils::multihash::prelude::*;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("parameters.json");
+        export_params(None, &path).unwrap();
+        let (name, info) = default_parameters()
+            .iter()
+            .find(|(_, info)| info.sector_size == 2048)
+            .unwrap();
+        let cid = Cid::new_v1(
+            fvm_ipld_encoding::IPLD_RAW,
+            MultihashCode::Sha2_256.digest(b"pinned"),
+        );
+
+        set_param_entry(
+            &path,
+            name,
+            ParamEntryPatch {
+                cid: Some(cid.to_string()),
+                digest: Some("00112233445566778899aabbccddeeff".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let params = load_parameter_map(Some(&path)).unwrap();
+        let patched = &params[name];
+        assert_eq!(patched.cid, cid);
+        assert_eq!(
+            hex::encode(patched.digest),
+            "00112233445566778899aabbccddeeff"
+        );
+        assert_eq!(patched.sector_size, info.sector_size);
+        // the other entries are unchanged
+        assert_eq!(params.len(), default_parameters().len());
+        assert!(params
+            .iter()
+            .filter(|(other, _)| *other != name)
+            .all(|(other, info)| default_parameters()[other] == *info));
+
+        set_param_entry(
+            &path,
+            name,
+            ParamEntryPatch {
+                sector_size: Some(8_388_608),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            load_parameter_map(Some(&path)).unwrap()[name].sector_size,
+            8_388_608
+        );
+    }
+
+    #[test]
+    fn set_param_entry_rejects_invalid_updates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("parameters.json");
+        export_params(None, &path).unwrap();
+        let original = std::fs::read(&path).unwrap();
+        let name = default_parameters().keys().next().unwrap();
+
+        let err =
+            set_param_entry(&path, "v28-missing.params", ParamEntryPatch::default()).unwrap_err();
+        assert!(err.to_string().contains("No entry v28-missing.params"));
+
+        for patch in [
+            ParamEntryPatch {
+                cid: Some("not a CID".into()),
+                ..Default::default()
+            },
+            // a digest is 16 bytes long
+            ParamEntryPatch {
+                digest: Some("00112233445566778899aabbccddee".into()),
+                ..Default::default()
+            },
+            ParamEntryPatch {
+                digest: Some("00112233445566778899aabbccddeeff00".into()),
+                ..Default::default()
+            },
+            ParamEntryPatch {
+                digest: Some("not hex".into()),
+                ..Default::default()
+            },
+        ] {
+            let err = set_param_entry(&path, name, patch.clone()).unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid entry"),
+                "{patch:?}: {err:#}"
+            );
+        }
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+    }
+
+    #[tokio::test]
+    async fn download_params_in_parallel() {
+        use axum::extract::{Path as UrlPath, State};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        struct Requests {
+            in_flight: AtomicUsize,
+            max_in_flight: AtomicUsize,
+            total: AtomicUsize,
+        }
+        /// Names and contents of the served parameter files.
+        type Files = Vec<(String, String)>;
+
+        let files = (0..6)
+            .map(|i| (format!("v28-test-{i}.vk"), format!("verification key {i}")))
+            .collect_vec();
+        let requests = Arc::new(Requests::default());
+        let app = axum::Router::new()
+            .route(
+                "/:name",
+                axum::routing::get(
+                    |State((files, requests)): State<(Files, Arc<Requests>)>,
+                     UrlPath(name): UrlPath<String>| async move {
+                        let in_flight = requests.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        requests
+                            .max_in_flight
+                            .fetch_max(in_flight, Ordering::SeqCst);
+                        requests.total.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        requests.in_flight.fetch_sub(1, Ordering::SeqCst);
+                        files
+                            .into_iter()
+                            .find(|(file, _)| *file == name)
+                            .map(|(_, content)| content)
+                            .ok_or(axum::http::StatusCode::NOT_FOUND)
+                    },
+                ),
+            )
+            .with_state((files.clone(), requests.clone()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let bucket = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let params = files
+            .iter()
+            .map(|(name, content)| {
+                let mut digest = [0; 16];
+                digest.copy_from_slice(&blake2b_simd::blake2b(content.as_bytes()).as_bytes()[..16]);
+                let info = ParameterData {
+                    cid: Cid::default(),
+                    digest,
+                    sector_size: 2048,
+                };
+                (name.clone(), info)
+            })
+            .collect_vec();
+        let dir = tempfile::tempdir().unwrap();
+        // a valid copy is kept, an invalid one is downloaded again
+        std::fs::write(dir.path().join(&files[0].0), &files[0].1).unwrap();
+        std::fs::write(dir.path().join(&files[1].0), "corrupted").unwrap();
+
+        let sources = ParamSources {
+            cloudflare: Some(bucket),
+            ipfs_gateways: vec![],
+            ipfs: IpfsFetchConfig::from_env().unwrap(),
+        };
+        let bars = MultiProgress::with_draw_target(ProgressDrawTarget::hidden());
+        let jobs = nonzero!(2usize);
+        let downloaded = download_params(dir.path(), params, sources, jobs, &bars)
+            .await
+            .unwrap();
+
+        assert_eq!(downloaded.len(), files.len() - 1);
+        assert_eq!(requests.total.load(Ordering::SeqCst), files.len() - 1);
+        assert!(requests.max_in_flight.load(Ordering::SeqCst) <= jobs.get());
+        check_all_parameter_files(&downloaded, 1, None)
+            .await
+            .unwrap();
+        for (name, content) in &files {
+            assert_eq!(
+                std::fs::read_to_string(dir.path().join(name)).unwrap(),
+                *content
+            );
+        }
+    }
+
+    /// Serves `body` for any CID requested from the returned gateway.
+    async fn serve_gateway(body: Vec<u8>) -> Url {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let gateway =
+            Url::parse(&format!("http://{}/ipfs/", listener.local_addr().unwrap())).unwrap();
+        let app = axum::Router::new().route(
+            "/ipfs/:cid",
+            axum::routing::get(move || {
+                let body = body.clone();
+                async move { body }
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        gateway
+    }
+
+    #[tokio::test]
+    async fn ipfs_gateway_fallback() {
+        use crate::utils::db::car_stream::{CarBlock, CarWriter};
+        use crate::utils::multihash::prelude::*;
+        use futures::StreamExt as _;
+        use nunny::vec as nonempty;
+
+        let data = b"proof parameters".to_vec();
+        // a single dag-pb node whose data is a UnixFS file holding `data`
+        let unixfs = [&[0x08, 0x02, 0x12, data.len() as u8][..], &data].concat();
+        let block = [&[0x0a, unixfs.len() as u8][..], &unixfs].concat();
+        let cid = Cid::new_v1(0x70, MultihashCode::Sha2_256.digest(&block));
+        let mut car = vec![];
+        futures::stream::iter([Ok(CarBlock { cid, data: block })])
+            .forward(CarWriter::new_carv1(nonempty![cid], &mut car).unwrap())
+            .await
+            .unwrap();
+
+        let broken = serve_gateway(b"not a CAR file".to_vec()).await;
+        let working = serve_gateway(car).await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.params");
+
+        let pb = ProgressBar::hidden();
+        let config = IpfsFetchConfig {
+            backoff: ExponentialBuilder::default().with_max_times(0),
+            ..IpfsFetchConfig::from_env().unwrap()
+        };
+        fetch_from_ipfs_gateways(&config, &[broken.clone()], &cid, &path, &pb)
+            .await
+            .unwrap_err();
+        assert!(!path.exists());
+
+        fetch_from_ipfs_gateways(&config, &[broken, working], &cid, &path, &pb)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn ipfs_gateway_deadline() {
+        // accepts connections, but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let gateway =
+            Url::parse(&format!("http://{}/ipfs/", listener.local_addr().unwrap())).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.params");
+
+        let config = IpfsFetchConfig {
+            deadline: Duration::from_millis(100),
+            ..IpfsFetchConfig::from_env().unwrap()
+        };
+        let error = fetch_from_ipfs_gateways(
+            &config,
+            &[gateway],
+            &Cid::default(),
+            &path,
+            &ProgressBar::hidden(),
+        )
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("Timed out"));
+        assert!(!path.exists());
+    }
+}
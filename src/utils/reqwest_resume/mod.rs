@@ -10,7 +10,6 @@
 //! Some modifications have been done to update the code regarding `tokio`,
 //! replace the `hyperx` dependency with `hyper` and add two unit tests.
 
-use crate::utils::net::global_http_client;
 use bytes::Bytes;
 use futures::{ready, FutureExt as _, Stream, TryFutureExt as _};
 use std::{
@@ -26,10 +25,6 @@ use tokio::time::sleep;
 #[derive(Debug)]
 pub struct Client(reqwest::Client);
 impl Client {
-    /// Constructs a new `Client` using the global Forest HTTP client.
-    pub fn new() -> Self {
-        Self(global_http_client())
-    }
     /// Convenience method to make a `GET` request to a URL.
     ///
     /// See [`reqwest::Client::get()`].
@@ -38,6 +33,12 @@ impl Client {
     }
 }
 
+impl From<reqwest::Client> for Client {
+    fn from(client: reqwest::Client) -> Self {
+        Self(client)
+    }
+}
+
 /// A builder to construct the properties of a Request.
 ///
 /// See [`reqwest::RequestBuilder`].
@@ -150,12 +151,5 @@ impl Stream for Decoder {
     }
 }
 
-/// Shortcut method to quickly make a GET request.
-///
-/// See [`reqwest::get`].
-pub async fn get(url: reqwest::Url) -> reqwest::Result<Response> {
-    Client::new().get(url).send().await
-}
-
 #[cfg(test)]
 mod tests;
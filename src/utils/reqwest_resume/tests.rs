@@ -1,7 +1,8 @@
 // Copyright 2019-2024 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use crate::utils::reqwest_resume::get;
+use crate::utils::net::global_http_client;
+use crate::utils::reqwest_resume::Client;
 use axum::body::Body;
 use axum::response::IntoResponse;
 use bytes::Bytes;
@@ -86,7 +87,9 @@ async fn test_resumable_get() {
     let addr = listener.local_addr().unwrap();
     create_flaky_server(listener);
 
-    let resp = get(reqwest::Url::parse(&format!("http://{addr}")).unwrap())
+    let resp = Client::from(global_http_client())
+        .get(reqwest::Url::parse(&format!("http://{addr}")).unwrap())
+        .send()
         .await
         .unwrap();
 
@@ -39,7 +39,13 @@ fn test_download_location_of_proof_parameter_files_env() {
 #[test]
 fn test_download_location_of_proof_parameter_files_default() {
     let tmp_dir = TempDir::new().unwrap();
-    let tmp_param_dir = tmp_dir.path().join("filecoin-proof-parameters");
+    // the parameters follow the XDG base directories on Linux, and the data directory elsewhere
+    let tmp_param_dir = if cfg!(target_os = "linux") {
+        tmp_dir.path().join("forest")
+    } else {
+        tmp_dir.path().to_path_buf()
+    }
+    .join("filecoin-proof-parameters");
     let config = Config {
         client: Client {
             data_dir: tmp_dir.path().to_path_buf(),
@@ -55,6 +61,7 @@ fn test_download_location_of_proof_parameter_files_default() {
 
     tool()
         .env("FOREST_CONFIG_PATH", config_file.path())
+        .env("XDG_DATA_HOME", tmp_dir.path())
         .arg("fetch-params")
         .arg("--keys")
         .arg("--dry-run")